@@ -12,23 +12,167 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::openrpc::RPC_DISCOVER;
+use crate::rest::{Error, ErrorKind};
 use crate::router::{Handler, HandlerObj, ResponseFuture};
-use crate::web::response;
+use crate::util::{from_base64, from_hex};
+use crate::web::{response, result_to_response};
 use futures::future::ok;
 use hyper::header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
-use hyper::{Body, Request, Response, StatusCode};
+use hyper::{body, Body, Method, Request, Response, StatusCode};
 use ring::constant_time::verify_slices_are_equal;
+use ring::digest;
+use std::sync::Arc;
 
 lazy_static! {
 	pub static ref MWC_BASIC_REALM: HeaderValue =
 		HeaderValue::from_str("Basic realm=MWC-API").unwrap();
 	pub static ref MWC_FOREIGN_BASIC_REALM: HeaderValue =
 		HeaderValue::from_str("Basic realm=MWCForeignAPI").unwrap();
+	pub static ref MWC_METRICS_BASIC_REALM: HeaderValue =
+		HeaderValue::from_str("Basic realm=MWCMetrics").unwrap();
+}
+
+/// Wildcard allowing a token to call every method and route.
+pub const ALLOW_ALL: &str = "*";
+
+/// Scoped API token as configured in the server config. Clients authenticate
+/// with basic auth, using the token name as user name and the token secret
+/// as password.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ApiToken {
+	/// Name of the token, also shown in the logs for every call made with it
+	pub name: String,
+	/// Hex encoded SHA-256 hash of the token secret
+	pub secret_hash: String,
+	/// JSON-RPC methods (e.g. `get_status`) and v1 routes (e.g. `/v1/status`)
+	/// the token may call, `*` allows everything
+	pub allowed: Vec<String>,
+}
+
+#[derive(Clone)]
+enum Credential {
+	/// Full expected `Authorization` header value
+	BasicAuth(String),
+	/// SHA-256 hash of the basic auth password
+	SecretHash(Vec<u8>),
+}
+
+/// Name of the scoped token a request was authenticated with, set in the
+/// request extensions.
+#[derive(Clone, Debug)]
+pub struct TokenName(pub String);
+
+#[derive(Clone)]
+struct AuthToken {
+	name: String,
+	credential: Credential,
+	allowed: Vec<String>,
+}
+
+impl AuthToken {
+	fn is_scoped(&self) -> bool {
+		match self.credential {
+			Credential::SecretHash(_) => true,
+			Credential::BasicAuth(_) => false,
+		}
+	}
+
+	fn allows_all(&self) -> bool {
+		self.allowed.iter().any(|a| a == ALLOW_ALL)
+	}
+
+	fn allows_method(&self, method: &str) -> bool {
+		method == RPC_DISCOVER || self.allows_all() || self.allowed.iter().any(|a| a == method)
+	}
+
+	fn allows_route(&self, path: &str) -> bool {
+		self.allows_all()
+			|| self
+				.allowed
+				.iter()
+				.any(|a| a.starts_with('/') && path_matches(path, a))
+	}
+}
+
+/// Tokens accepted by the auth middlewares.
+#[derive(Clone, Default)]
+pub struct ApiTokens {
+	tokens: Vec<AuthToken>,
+}
+
+impl ApiTokens {
+	/// Create an empty token set.
+	pub fn new() -> ApiTokens {
+		ApiTokens { tokens: vec![] }
+	}
+
+	/// Add a token allowed to call everything, checked against the full
+	/// `Authorization` header value. Used for the legacy secret files.
+	pub fn add_secret(&mut self, name: &str, api_basic_auth: String) {
+		self.tokens.push(AuthToken {
+			name: name.to_owned(),
+			credential: Credential::BasicAuth(api_basic_auth),
+			allowed: vec![ALLOW_ALL.to_owned()],
+		});
+	}
+
+	/// Add a scoped token.
+	pub fn add_token(&mut self, token: &ApiToken) -> Result<(), Error> {
+		let hash = from_hex(&token.secret_hash).map_err(|e| {
+			ErrorKind::Argument(format!(
+				"invalid secret hash of token {}, {}",
+				token.name, e
+			))
+		})?;
+		if hash.len() != digest::SHA256_OUTPUT_LEN {
+			return Err(ErrorKind::Argument(format!(
+				"secret hash of token {} is not a SHA-256 hash",
+				token.name
+			))
+			.into());
+		}
+		self.tokens.push(AuthToken {
+			name: token.name.clone(),
+			credential: Credential::SecretHash(hash),
+			allowed: token.allowed.clone(),
+		});
+		Ok(())
+	}
+
+	/// Whether no token is configured.
+	pub fn is_empty(&self) -> bool {
+		self.tokens.is_empty()
+	}
+
+	/// Find the token matching the `Authorization` header of a request.
+	fn authenticate(&self, header: Option<&HeaderValue>) -> Option<&AuthToken> {
+		let header = header?.as_bytes();
+		// User name and password, for the scoped tokens
+		let credentials = std::str::from_utf8(header)
+			.ok()
+			.filter(|h| h.starts_with("Basic "))
+			.and_then(|h| from_base64(&h["Basic ".len()..]));
+		let (user, password) = credentials
+			.as_ref()
+			.and_then(|c| c.find(':').map(|i| (&c[..i], &c[i + 1..])))
+			.unwrap_or(("", ""));
+		let password_hash = digest::digest(&digest::SHA256, password.as_bytes());
+
+		self.tokens.iter().find(|t| match &t.credential {
+			Credential::BasicAuth(expected) => {
+				verify_slices_are_equal(header, expected.as_bytes()).is_ok()
+			}
+			Credential::SecretHash(hash) => {
+				t.name == user && verify_slices_are_equal(password_hash.as_ref(), hash).is_ok()
+			}
+		})
+	}
 }
 
 // Basic Authentication Middleware
 pub struct BasicAuthMiddleware {
-	api_basic_auth: String,
+	tokens: Arc<ApiTokens>,
 	basic_realm: &'static HeaderValue,
 	ignore_uri: Option<String>,
 }
@@ -38,9 +182,20 @@ impl BasicAuthMiddleware {
 		api_basic_auth: String,
 		basic_realm: &'static HeaderValue,
 		ignore_uri: Option<String>,
+	) -> BasicAuthMiddleware {
+		let mut tokens = ApiTokens::new();
+		tokens.add_secret("api_secret", api_basic_auth);
+		BasicAuthMiddleware::with_tokens(tokens, basic_realm, ignore_uri)
+	}
+
+	/// Middleware accepting any of the provided tokens, within their scope.
+	pub fn with_tokens(
+		tokens: ApiTokens,
+		basic_realm: &'static HeaderValue,
+		ignore_uri: Option<String>,
 	) -> BasicAuthMiddleware {
 		BasicAuthMiddleware {
-			api_basic_auth,
+			tokens: Arc::new(tokens),
 			basic_realm,
 			ignore_uri,
 		}
@@ -61,28 +216,17 @@ impl Handler for BasicAuthMiddleware {
 			return next_handler.call(req, handlers);
 		}
 		if let Some(u) = self.ignore_uri.as_ref() {
-			if req.uri().path() == u {
+			if path_matches(req.uri().path(), u) {
 				return next_handler.call(req, handlers);
 			}
 		}
-		if req.headers().contains_key(AUTHORIZATION)
-			&& verify_slices_are_equal(
-				req.headers()[AUTHORIZATION].as_bytes(),
-				&self.api_basic_auth.as_bytes(),
-			)
-			.is_ok()
-		{
-			next_handler.call(req, handlers)
-		} else {
-			// Unauthorized 401
-			unauthorized_response(&self.basic_realm)
-		}
+		authorize(&self.tokens, self.basic_realm, req, next_handler, handlers)
 	}
 }
 
 // Basic Authentication Middleware
 pub struct BasicAuthURIMiddleware {
-	api_basic_auth: String,
+	tokens: Arc<ApiTokens>,
 	basic_realm: &'static HeaderValue,
 	target_uri: String,
 }
@@ -92,9 +236,20 @@ impl BasicAuthURIMiddleware {
 		api_basic_auth: String,
 		basic_realm: &'static HeaderValue,
 		target_uri: String,
+	) -> BasicAuthURIMiddleware {
+		let mut tokens = ApiTokens::new();
+		tokens.add_secret("foreign_api_secret", api_basic_auth);
+		BasicAuthURIMiddleware::with_tokens(tokens, basic_realm, target_uri)
+	}
+
+	/// Middleware accepting any of the provided tokens, within their scope.
+	pub fn with_tokens(
+		tokens: ApiTokens,
+		basic_realm: &'static HeaderValue,
+		target_uri: String,
 	) -> BasicAuthURIMiddleware {
 		BasicAuthURIMiddleware {
-			api_basic_auth,
+			tokens: Arc::new(tokens),
 			basic_realm,
 			target_uri,
 		}
@@ -114,25 +269,90 @@ impl Handler for BasicAuthURIMiddleware {
 		if req.method().as_str() == "OPTIONS" {
 			return next_handler.call(req, handlers);
 		}
-		if req.uri().path() == self.target_uri {
-			if req.headers().contains_key(AUTHORIZATION)
-				&& verify_slices_are_equal(
-					req.headers()[AUTHORIZATION].as_bytes(),
-					&self.api_basic_auth.as_bytes(),
-				)
-				.is_ok()
-			{
-				next_handler.call(req, handlers)
-			} else {
-				// Unauthorized 401
-				unauthorized_response(&self.basic_realm)
-			}
+		if path_matches(req.uri().path(), &self.target_uri) {
+			authorize(&self.tokens, self.basic_realm, req, next_handler, handlers)
 		} else {
 			next_handler.call(req, handlers)
 		}
 	}
 }
 
+/// Authenticate the request and check it's within the scope of its token.
+/// JSON-RPC calls (POST on a v2 endpoint) are checked against the allowed
+/// methods, every call of a batch must be allowed. Anything else is checked
+/// against the allowed routes.
+fn authorize(
+	tokens: &ApiTokens,
+	basic_realm: &'static HeaderValue,
+	mut req: Request<Body>,
+	next_handler: HandlerObj,
+	handlers: Box<dyn Iterator<Item = HandlerObj>>,
+) -> ResponseFuture {
+	let token = match tokens.authenticate(req.headers().get(AUTHORIZATION)) {
+		Some(token) => token.clone(),
+		// Unauthorized 401
+		None => return unauthorized_response(basic_realm),
+	};
+	if token.is_scoped() {
+		req.extensions_mut().insert(TokenName(token.name.clone()));
+	}
+	let path = req.uri().path().to_owned();
+	if req.method() != Method::POST || !path.starts_with("/v2/") {
+		if !token.allows_route(&path) {
+			warn!("api: token {} is not allowed to call {}", token.name, path);
+			return forbidden_response(format!("{} is not allowed for {}", path, token.name));
+		}
+		info!("api: token {} called {} {}", token.name, req.method(), path);
+		return next_handler.call(req, handlers);
+	}
+
+	// The body has to be read to know the methods, the remaining handlers are
+	// collected so they can be moved into the future.
+	let handlers: Vec<HandlerObj> = handlers.collect();
+	Box::pin(async move {
+		let (parts, req_body) = req.into_parts();
+		let bytes = body::to_bytes(req_body).await?;
+		let methods = rpc_methods(&bytes);
+		if let Some(method) = methods.iter().find(|m| !token.allows_method(m)) {
+			warn!(
+				"api: token {} is not allowed to call {} on {}",
+				token.name, method, path
+			);
+			return forbidden_response(format!("{} is not allowed for {}", method, token.name))
+				.await;
+		}
+		info!(
+			"api: token {} called {} on {}",
+			token.name,
+			methods.join(","),
+			path
+		);
+		let req = Request::from_parts(parts, Body::from(bytes));
+		next_handler.call(req, Box::new(handlers.into_iter())).await
+	})
+}
+
+/// Method names of a JSON-RPC request body, single call or batch. A body
+/// that can't be parsed has no methods, the API handler reports the error.
+fn rpc_methods(body: &[u8]) -> Vec<String> {
+	let method = |call: &serde_json::Value| {
+		call.get("method")
+			.and_then(|m| m.as_str())
+			.map(|m| m.to_owned())
+	};
+	match serde_json::from_slice::<serde_json::Value>(body) {
+		Ok(serde_json::Value::Array(calls)) => calls.iter().filter_map(method).collect(),
+		Ok(call) => method(&call).into_iter().collect(),
+		Err(_) => vec![],
+	}
+}
+
+/// Whether the request path is the given uri or one of its sub-paths
+/// (e.g. `/v2/foreign/subscribe` for `/v2/foreign`).
+pub(crate) fn path_matches(path: &str, uri: &str) -> bool {
+	path == uri || path.starts_with(&format!("{}/", uri))
+}
+
 fn unauthorized_response(basic_realm: &HeaderValue) -> ResponseFuture {
 	let response = Response::builder()
 		.status(StatusCode::UNAUTHORIZED)
@@ -141,3 +361,68 @@ fn unauthorized_response(basic_realm: &HeaderValue) -> ResponseFuture {
 		.unwrap();
 	Box::pin(ok(response))
 }
+
+fn forbidden_response(msg: String) -> ResponseFuture {
+	result_to_response::<()>(Err(ErrorKind::Forbidden(msg).into()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::util::{to_base64, to_hex};
+
+	fn header(user: &str, password: &str) -> HeaderValue {
+		let auth = format!("Basic {}", to_base64(&format!("{}:{}", user, password)));
+		HeaderValue::from_str(&auth).unwrap()
+	}
+
+	#[test]
+	fn test_token_scopes() {
+		let secret_hash = to_hex(
+			digest::digest(&digest::SHA256, b"monitoring secret")
+				.as_ref()
+				.to_vec(),
+		);
+		let mut tokens = ApiTokens::new();
+		tokens.add_secret(
+			"api_secret",
+			header("mwc", "legacy secret").to_str().unwrap().to_owned(),
+		);
+		tokens
+			.add_token(&ApiToken {
+				name: "monitoring".to_owned(),
+				secret_hash,
+				allowed: vec!["get_status".to_owned(), "/v1/status".to_owned()],
+			})
+			.unwrap();
+
+		let legacy = tokens
+			.authenticate(Some(&header("mwc", "legacy secret")))
+			.unwrap();
+		assert_eq!(legacy.name, "api_secret");
+		assert!(legacy.allows_method("ban_peer"));
+		assert!(legacy.allows_route("/v1/peers/all"));
+
+		let monitoring = tokens
+			.authenticate(Some(&header("monitoring", "monitoring secret")))
+			.unwrap();
+		assert_eq!(monitoring.name, "monitoring");
+		assert!(monitoring.allows_method("get_status"));
+		assert!(monitoring.allows_method(RPC_DISCOVER));
+		assert!(!monitoring.allows_method("ban_peer"));
+		assert!(monitoring.allows_route("/v1/status"));
+		assert!(!monitoring.allows_route("/v1/chain/compact"));
+
+		assert!(tokens
+			.authenticate(Some(&header("monitoring", "wrong secret")))
+			.is_none());
+		assert!(tokens
+			.authenticate(Some(&header("other", "monitoring secret")))
+			.is_none());
+		assert!(tokens.authenticate(None).is_none());
+
+		let batch = br#"[{"method": "get_status"}, {"method": "ban_peer"}]"#;
+		assert_eq!(rpc_methods(batch), vec!["get_status", "ban_peer"]);
+		assert!(rpc_methods(b"not json").is_empty());
+	}
+}
@@ -20,14 +20,14 @@ use crate::core::core::hash::Hashed;
 use crate::core::core::transaction::Transaction;
 use crate::handlers::blocks_api::{BlockHandler, HeaderHandler};
 use crate::handlers::chain_api::{ChainHandler, KernelHandler, OutputHandler};
-use crate::handlers::pool_api::PoolHandler;
+use crate::handlers::pool_api::{FeeHandler, PoolHandler};
 use crate::handlers::transactions_api::TxHashSetHandler;
 use crate::handlers::version_api::VersionHandler;
 use crate::pool::{self, PoolEntry};
 use crate::rest::*;
 use crate::types::{
-	BlockHeaderPrintable, BlockPrintable, LocatedTxKernel, OutputListing, OutputPrintable, Tip,
-	Version,
+	BlockHeaderPrintable, BlockPrintable, FeeEstimate, LocatedTxKernel, Output, OutputHistory,
+	OutputListing, OutputPrintable, Tip, TxValidation, Version,
 };
 use crate::util::RwLock;
 use std::sync::Weak;
@@ -160,6 +160,8 @@ impl Foreign {
 	/// If not supplied, `min_height` will be set to 0 and `max_height` will be set to the head of the chain.
 	/// The method will start at the block height `max_height` and traverse the kernel MMR backwards,
 	/// until either the kernel is found or `min_height` is reached.
+	/// If the node maintains a kernel index (`kernel_index` in the server config) the kernel
+	/// is looked up directly without traversing the kernel MMR.
 	///
 	/// # Arguments
 	/// * `excess` - kernel excess to look for.
@@ -247,6 +249,55 @@ impl Foreign {
 		output_handler.get_unspent_outputs(start_index, end_index, max, include_proof)
 	}
 
+	/// Returns the creation and spend history of all outputs with the given commitment.
+	/// Requires the node to run in archive mode with the output history index enabled
+	/// (`output_history_index` in the server config).
+	/// A commitment may be returned multiple times if it was reused after being spent.
+	///
+	/// # Arguments
+	/// * `commit` - the output commitment (hex).
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A vec of [`OutputHistory`](types/struct.OutputHistory.html), oldest first
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_output_history(&self, commit: String) -> Result<Vec<OutputHistory>, Error> {
+		let output_handler = OutputHandler {
+			chain: self.chain.clone(),
+		};
+		output_handler.get_output_history(commit)
+	}
+
+	/// Returns the outputs that were unspent as of the block at the given height on
+	/// the current chain. Commitments that were not unspent at that height are
+	/// omitted from the result.
+	/// Archive nodes can answer for any height, faster with the output history index.
+	/// Pruned nodes can only answer for the last 60 blocks, an error is returned for
+	/// older heights.
+	///
+	/// # Arguments
+	/// * `commits` - the output commitments (hex) to check.
+	/// * `height` - the block height to check the outputs at.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A vec of [`Output`](types/struct.Output.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_outputs_at_height(
+		&self,
+		commits: Vec<String>,
+		height: u64,
+	) -> Result<Vec<Output>, Error> {
+		let output_handler = OutputHandler {
+			chain: self.chain.clone(),
+		};
+		output_handler.get_outputs_at_height(commits, height)
+	}
+
 	/// Retrieves the PMMR indices based on the provided block height(s).
 	///
 	/// # Arguments
@@ -316,6 +367,51 @@ impl Foreign {
 		pool_handler.get_unconfirmed_transactions()
 	}
 
+	/// Validates a transaction against the transaction pool and the current
+	/// chain state, running the same checks as
+	/// [`push_transaction`](struct.Foreign.html#method.push_transaction)
+	/// without adding the transaction to the pool or relaying it.
+	///
+	/// # Arguments
+	/// * `tx` - the Grin transaction to validate.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`TxValidation`](types/struct.TxValidation.html) with the pool error if the
+	/// transaction would be rejected
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn validate_transaction(&self, tx: Transaction) -> Result<TxValidation, Error> {
+		let pool_handler = PoolHandler {
+			tx_pool: self.tx_pool.clone(),
+		};
+		pool_handler.validate_transaction(tx)
+	}
+
+	/// Estimates the fee needed for a transaction to be mined within the
+	/// given number of blocks. Looks at the fee to weight distribution of
+	/// the txpool and at the fees paid in the last blocks. The recommended
+	/// fee is never lower than the minimum relay fee (`accept_fee_base`), so
+	/// `fee_per_weight * tx_weight` is always accepted by the pool.
+	///
+	/// # Arguments
+	/// * `target_blocks` - number of blocks the transaction should be mined within.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`FeeEstimate`](types/struct.FeeEstimate.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn estimate_fee(&self, target_blocks: u64) -> Result<FeeEstimate, Error> {
+		let fee_handler = FeeHandler {
+			chain: self.chain.clone(),
+			tx_pool: self.tx_pool.clone(),
+		};
+		fee_handler.estimate_fee(target_blocks)
+	}
+
 	/// Push new transaction to our local transaction pool.
 	///
 	/// # Arguments
@@ -20,8 +20,8 @@ use crate::foreign::Foreign;
 use crate::pool::PoolEntry;
 use crate::rest::ErrorKind;
 use crate::types::{
-	BlockHeaderPrintable, BlockPrintable, LocatedTxKernel, OutputListing, OutputPrintable, Tip,
-	Version,
+	BlockHeaderPrintable, BlockPrintable, FeeEstimate, LocatedTxKernel, Output, OutputHistory,
+	OutputListing, OutputPrintable, Tip, TxValidation, Version,
 };
 use crate::util;
 
@@ -29,6 +29,10 @@ use crate::util;
 /// * When running `grin` with defaults, the V2 api is available at
 /// `localhost:3413/v2/foreign`
 /// * The endpoint only supports POST operations, with the json-rpc request as the body
+/// * Failures are returned as json-rpc error objects, with a stable `code` (see
+/// [`ErrorKind::code`](enum.ErrorKind.html#method.code)) and the error details as `data`
+/// * JSON-RPC batches are accepted, up to `api_max_batch_size` calls per request
+/// * The `rpc.discover` method returns the OpenRPC document of the endpoint
 #[easy_jsonrpc_mw::rpc]
 pub trait ForeignRpc: Sync + Send {
 	/**
@@ -502,6 +506,91 @@ pub trait ForeignRpc: Sync + Send {
 		include_proof: Option<bool>,
 	) -> Result<OutputListing, ErrorKind>;
 
+	/**
+	Networked version of [Foreign::get_output_history](struct.Node.html#method.get_output_history).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_foreign_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_output_history",
+		"params": ["08f9f1bd5d3b3c4b7f1e5e1b5bd6be8b0ec5de7a3b2a5e7ba9e4c5d8b6e0f2b1c4"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": [
+				{
+					"commit": "08f9f1bd5d3b3c4b7f1e5e1b5bd6be8b0ec5de7a3b2a5e7ba9e4c5d8b6e0f2b1c4",
+					"mmr_index": 12,
+					"height": 7,
+					"spent": true,
+					"spent_height": 15,
+					"spent_block_hash": "0d6ba1c1f0b6a4fd3e67f2e1a04a8b8d6c6b1f2e0d5f4e9b1f2c0e5a3c4d7e81"
+				}
+			]
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_output_history(&self, commit: String) -> Result<Vec<OutputHistory>, ErrorKind>;
+
+	/**
+	Networked version of [Foreign::get_outputs_at_height](struct.Node.html#method.get_outputs_at_height).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_foreign_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_outputs_at_height",
+		"params": [
+			[
+				"08f9f1bd5d3b3c4b7f1e5e1b5bd6be8b0ec5de7a3b2a5e7ba9e4c5d8b6e0f2b1c4",
+				"09bab1ddad0f6fec1aedcd3830c5c647515ad543929e722344e4a8d390b6fdd51b"
+			],
+			10
+		],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": [
+				{
+					"commit": "08f9f1bd5d3b3c4b7f1e5e1b5bd6be8b0ec5de7a3b2a5e7ba9e4c5d8b6e0f2b1c4",
+					"height": 7,
+					"mmr_index": 12
+				}
+			]
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_outputs_at_height(
+		&self,
+		commits: Vec<String>,
+		height: u64,
+	) -> Result<Vec<Output>, ErrorKind>;
+
 	/**
 	Networked version of [Foreign::get_pmmr_indices](struct.Node.html#method.get_pmmr_indices).
 
@@ -736,6 +825,116 @@ pub trait ForeignRpc: Sync + Send {
 	```
 	 */
 	fn push_transaction(&self, tx: Transaction, fluff: Option<bool>) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Foreign::validate_transaction](struct.Node.html#method.validate_transaction).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_foreign_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "validate_transaction",
+		"params": [ {
+		"body": {
+				"inputs": [
+				{
+					"commit": "0904cbd34d0745eb00ffc3e95c9f4746738794d00268e243e9b57163a73b384102",
+					"features": "Coinbase"
+				}
+				],
+				"kernels": [
+				{
+					"excess": "08385257d22f1b8a758903f78ae12545245d620cffc50e7ee7bc852c5815513dc7",
+					"excess_sig": "e001a7349fd40d4a9dfc1df275d30906fb3b304f8c7892a20ed5c9b10923c871cbabedcf322511a9ce56f10113b48855441f681280133e121b25ea1ff7efad9e",
+					"features": {
+					"Plain": {
+						"fee": 8000000
+					}
+					}
+				}
+				],
+				"outputs": [
+				{
+					"commit": "087c3ca7419751e96cdae4908bb8a92fc2826f2ad36690420b905d51beb7409ca0",
+					"features": "Plain",
+					"proof": "379ae236937883c2e1e613fb30f1b18d2a44d4173360e94bcd07862aafaf81b3aaa1154d67287cc03efde0d3981c6da8a18e2e426f5c30afc0f2e3a75012448402d8d56df52b87f4815575a56d4da174f8187e4faae64bf883b249ceed694271f84ef62a3711d36c997dff7a11111419011e36e3a070b7552415a55faaa3999f99439edccdfe5313277147fdb42be1798442bb225c2b546f5347920584b365aa81a0365b4a706c97c89617b0e6218d2c9bc15805caab27c438ed06340cc4f8dc7bfca0e9d38864c88bb0c834372f6b662b9159134f3f8ec9b8a87878739a7e516b97419ac29e1d4a2b250321470a9a6b98d07065bb7e79afc25a5ab6fc47108f53223078a64502bd4af1a109641447dab82741ebe3fbdbd803ee7a42fe2554e78fa86bd1d1e6e3b913118e9419b0be6f976b2404447d943b5f1bac19a5809fd6834797945a62d21b1ecb6ddebbc5ef94ca9e704d033bd64afde67bd3e06e2cca3bb10190188afc0af80b48dd862b86753d8b4af314763324deb1c97cf020cb87285a47cd28874bb91c6cdf858965e8b9daafbcbc1b4817d334a97d7e25e01b2d072d8dcc6418e3dc7b8e7712632f939238e65ed0731c7af02d55a8884cd8f7f88dc0f63a21955a7364562532f5716c89e14f8f23ad78f6fe2f1649e13ea8f8185f3ee63cc174684d1ef8d8c33fb25bc802f8e05e53fe200b1ea5231f588a020942e6fd7eec67301700088dae8816c16a337120063c21e1604e009df932032812f88be6473af13f802b42d8ad6fc14230fbe13ede178319a7b6540656234ec1f2fcfa70f6faa9c4b6b8150b81fe0fdc273a9bb385d766a02041a5c3f58471d42059c17d84d13ad592aa0ccf337970e7eef06f306b13288795123c9c005b815d848f359b23450656b310f09cda9ad4b7b6931805d47dcd10a8745d834a984e2055168ac3"
+				},
+				{
+					"commit": "09a7b2c1d4b346c4ebe9c6c979e32e7740446624d5439d9d7abb82166c2545e5be",
+					"features": "Plain",
+					"proof": "5fb0ee4093a153e2ed173207dbfa02b4d185f1f313ea4cbf222558819074543f19e9bcdb595a23d4ee971aafcc614b6d2774e22cee6627bc4388297fe6ebf03e0d422f3eb8003cc8516417a6b32eb22f87e1745e0ae5bf1733f2ea253399719b1ef0067934dc548c58729604d24a44040165b32d05e82c9efc9a1f30151dd73ce893ae94709ec2fe5d0f409bb54a86604f0e92915b4f93e7adde823eccf87830ae91d71a7b99967dbcc8531fee44c20c24fb6fe2a34fe86ba5da3a9235cbcdcde033ead57d65c03903a9c9ed877bf0fab9f26d08552c64ea668d5408c84b74bc3ac8335aaaa04ebcf523d36d2207fb8770e976b6fde7d04e2148de5a4169c60b1958bb840b79a8c8f356e1f1fadc35a5a7e276fcd67c354cde546548c9bf788981f38edf5a406977826aa4524004e770b3d3cd6b26f0dc99729ffd9929fa4509b145ef0c3e4293e71b964da731a47cc9f082350acf32afb64b3b12f8383c8f2cc9880131a80ea957b2908c92f21d2db7aa5d67bafb11eb07674e52b920e67a86259dd9c5dcdd18bad182fd85ec4b659c47ea2e2e8a89c57e4d2cde87958fc2ab932e169f6805d2fb14549ac93807bc426eb4cf6d29ff6a4cf22e35dbb27f04211b06b65173501c17a3bb3ff0eecc9bb05dca23379abe457ca3010ebea69e1a2f7f3ed6531bf766007cdd1ac7d6c762785fb56f36194cc2ccaee76a499a7383288e84981b103d76cbe007f66c913eacb277746e78ae08627b279ac1f9a43ab284d8a3b32c6edcd2ea99e8ea836b31a1e2582be6c41f2282cf5fc7bdb95e4b412a5eeccad29670197873a888a100c4b2704ce75137fc997a5632d81001f9b57300a9bf99edd857065be83f835e4c49d852165ba18e1c96316c153459a913773d5d86ddc26c5cd1fff38a8fbb62506b0aef6076382674c0fa95a50a03b0c3df0a688a2cbf"
+				}
+				]
+			},
+			"offset": "0ec14d3875ad5a366418256fe65bad2a4d4ff1914e1b9488db72dd355138ca3a"
+			}
+		],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"valid": false,
+				"error": {
+					"kind": "InputNotFound",
+					"message": "Tx Pool Input not found Commitment(0904cbd34d0745eb00ffc3e95c9f4746738794d00268e243e9b57163a73b384102)",
+					"commitment": "0904cbd34d0745eb00ffc3e95c9f4746738794d00268e243e9b57163a73b384102",
+					"min_fee": null
+				}
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn validate_transaction(&self, tx: Transaction) -> Result<TxValidation, ErrorKind>;
+
+	/**
+	Networked version of [Foreign::estimate_fee](struct.Node.html#method.estimate_fee).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_foreign_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "estimate_fee",
+		"params": [3],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"target_blocks": 3,
+				"fee_per_weight": 1000000,
+				"min_fee_per_weight": 1000000,
+				"pool_fee_per_weight": 0,
+				"blocks_fee_per_weight": 0,
+				"blocks_considered": 10,
+				"pool_weight": 0
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn estimate_fee(&self, target_blocks: u64) -> Result<FeeEstimate, ErrorKind>;
 }
 
 impl ForeignRpc for Foreign {
@@ -815,6 +1014,18 @@ impl ForeignRpc for Foreign {
 			.map_err(|e| e.kind().clone())
 	}
 
+	fn get_output_history(&self, commit: String) -> Result<Vec<OutputHistory>, ErrorKind> {
+		Foreign::get_output_history(self, commit).map_err(|e| e.kind().clone())
+	}
+
+	fn get_outputs_at_height(
+		&self,
+		commits: Vec<String>,
+		height: u64,
+	) -> Result<Vec<Output>, ErrorKind> {
+		Foreign::get_outputs_at_height(self, commits, height).map_err(|e| e.kind().clone())
+	}
+
 	fn get_pmmr_indices(
 		&self,
 		start_block_height: u64,
@@ -838,6 +1049,14 @@ impl ForeignRpc for Foreign {
 	fn push_transaction(&self, tx: Transaction, fluff: Option<bool>) -> Result<(), ErrorKind> {
 		Foreign::push_transaction(self, tx, fluff).map_err(|e| e.kind().clone())
 	}
+
+	fn validate_transaction(&self, tx: Transaction) -> Result<TxValidation, ErrorKind> {
+		Foreign::validate_transaction(self, tx).map_err(|e| e.kind().clone())
+	}
+
+	fn estimate_fee(&self, target_blocks: u64) -> Result<FeeEstimate, ErrorKind> {
+		Foreign::estimate_fee(self, target_blocks).map_err(|e| e.kind().clone())
+	}
 }
 
 #[doc(hidden)]
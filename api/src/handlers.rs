@@ -17,6 +17,7 @@ pub mod chain_api;
 pub mod peers_api;
 pub mod pool_api;
 pub mod server_api;
+pub mod subscribe_api;
 pub mod transactions_api;
 pub mod utils;
 pub mod version_api;
@@ -36,10 +37,12 @@ use self::pool_api::PoolPushHandler;
 use self::server_api::IndexHandler;
 use self::server_api::KernelDownloadHandler;
 use self::server_api::StatusHandler;
+use self::subscribe_api::{EventBroadcaster, SubscribeHandler};
 use self::transactions_api::TxHashSetHandler;
 use self::version_api::VersionHandler;
 use crate::auth::{
-	BasicAuthMiddleware, BasicAuthURIMiddleware, MWC_BASIC_REALM, MWC_FOREIGN_BASIC_REALM,
+	ApiToken, ApiTokens, BasicAuthMiddleware, BasicAuthURIMiddleware, MWC_BASIC_REALM,
+	MWC_FOREIGN_BASIC_REALM,
 };
 use crate::chain;
 use crate::chain::{Chain, SyncState};
@@ -47,10 +50,12 @@ use crate::core::global;
 use crate::core::stratum;
 use crate::foreign::Foreign;
 use crate::foreign_rpc::ForeignRpc;
-use crate::owner::Owner;
+use crate::openrpc;
+use crate::owner::{BlockGenerator, Owner};
 use crate::owner_rpc::OwnerRpc;
 use crate::p2p;
 use crate::pool;
+use crate::rate_limit::{RateLimitConfig, RateLimitMiddleware, RateLimiter};
 use crate::rest::{ApiServer, Error, ErrorKind, TLSConfig};
 use crate::router::ResponseFuture;
 use crate::router::{Router, RouterError};
@@ -65,6 +70,9 @@ use serde::Serialize;
 use std::net::SocketAddr;
 use std::sync::{Arc, Weak};
 
+/// Default maximum number of calls accepted in a single JSON-RPC batch request.
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 100;
+
 /// Listener version, providing same API but listening for requests on a
 /// port and wrapping the calls
 pub fn node_apis(
@@ -78,6 +86,11 @@ pub fn node_apis(
 	tls_config: Option<TLSConfig>,
 	allow_to_stop: bool,
 	stratum_ip_pool: Arc<stratum::connections::StratumIpPool>,
+	event_broadcaster: Arc<EventBroadcaster>,
+	max_batch_size: usize,
+	api_tokens: Vec<ApiToken>,
+	rate_limit: Option<RateLimitConfig>,
+	block_generator: Option<Arc<dyn BlockGenerator>>,
 ) -> Result<(), Error> {
 	// Manually build router when getting rid of v1
 	//let mut router = Router::new();
@@ -98,29 +111,41 @@ pub fn node_apis(
 		"mwc"
 	};
 
-	// Add basic auth to v1 API and owner v2 API
+	// Add basic auth to v1 API and owner v2 API, scoped tokens are accepted
+	// along with the api secret
+	let mut owner_tokens = ApiTokens::new();
 	if let Some(api_secret) = api_secret {
 		let api_basic_auth = format!(
 			"Basic {}",
 			to_base64(&format!("{}:{}", basic_auth_key, api_secret))
 		);
-
-		let basic_auth_middleware = Arc::new(BasicAuthMiddleware::new(
-			api_basic_auth,
+		owner_tokens.add_secret("api_secret", api_basic_auth);
+	}
+	for token in &api_tokens {
+		owner_tokens.add_token(token)?;
+	}
+	if !owner_tokens.is_empty() {
+		let basic_auth_middleware = Arc::new(BasicAuthMiddleware::with_tokens(
+			owner_tokens,
 			&MWC_BASIC_REALM,
 			Some("/v2/foreign".into()),
 		));
 		router.add_middleware(basic_auth_middleware);
 	}
 
+	let rate_limiter = Arc::new(RateLimiter::new(rate_limit));
+
 	let api_handler_v2 = OwnerAPIHandlerV2::new(
 		Arc::downgrade(&chain),
 		Arc::downgrade(&peers),
 		Arc::downgrade(&sync_state),
+		Arc::downgrade(&rate_limiter),
+		block_generator,
+		max_batch_size,
 	);
 	router.add_route("/v2/owner", Arc::new(api_handler_v2))?;
 
-	let stratum_handler_v2 = StratumAPIHandlerV2::new(stratum_ip_pool);
+	let stratum_handler_v2 = StratumAPIHandlerV2::new(stratum_ip_pool, max_batch_size);
 	router.add_route("/v2/stratum", Arc::new(stratum_handler_v2))?;
 
 	// Add basic auth to v2 foreign API only
@@ -129,22 +154,38 @@ pub fn node_apis(
 			"Basic {}",
 			to_base64(&format!("{}:{}", basic_auth_key, api_secret))
 		);
+		let mut foreign_tokens = ApiTokens::new();
+		foreign_tokens.add_secret("foreign_api_secret", api_basic_auth);
+		for token in &api_tokens {
+			foreign_tokens.add_token(token)?;
+		}
 
-		let basic_auth_middleware = Arc::new(BasicAuthURIMiddleware::new(
-			api_basic_auth,
+		let basic_auth_middleware = Arc::new(BasicAuthURIMiddleware::with_tokens(
+			foreign_tokens,
 			&MWC_FOREIGN_BASIC_REALM,
 			"/v2/foreign".into(),
 		));
 		router.add_middleware(basic_auth_middleware);
 	}
 
+	// Rate limit the v2 foreign API, after the auth to know the API tokens
+	let rate_limit_middleware =
+		Arc::new(RateLimitMiddleware::new(rate_limiter, "/v2/foreign".into()));
+	router.add_middleware(rate_limit_middleware);
+
 	let api_handler_v2 = ForeignAPIHandlerV2::new(
 		Arc::downgrade(&chain),
 		Arc::downgrade(&tx_pool),
 		Arc::downgrade(&sync_state),
+		max_batch_size,
 	);
 	router.add_route("/v2/foreign", Arc::new(api_handler_v2))?;
 
+	let subscribe_handler = SubscribeHandler {
+		broadcaster: event_broadcaster,
+	};
+	router.add_route("/v2/foreign/subscribe", Arc::new(subscribe_handler))?;
+
 	let mut apis = ApiServer::new();
 	warn!("Starting HTTP Node APIs server at {}.", addr);
 	let socket_addr: SocketAddr = addr.parse().expect("unable to parse socket address");
@@ -166,15 +207,28 @@ pub struct OwnerAPIHandlerV2 {
 	pub chain: Weak<Chain>,
 	pub peers: Weak<p2p::Peers>,
 	pub sync_state: Weak<SyncState>,
+	pub rate_limiter: Weak<RateLimiter>,
+	pub block_generator: Option<Arc<dyn BlockGenerator>>,
+	pub max_batch_size: usize,
 }
 
 impl OwnerAPIHandlerV2 {
 	/// Create a new owner API handler for GET methods
-	pub fn new(chain: Weak<Chain>, peers: Weak<p2p::Peers>, sync_state: Weak<SyncState>) -> Self {
+	pub fn new(
+		chain: Weak<Chain>,
+		peers: Weak<p2p::Peers>,
+		sync_state: Weak<SyncState>,
+		rate_limiter: Weak<RateLimiter>,
+		block_generator: Option<Arc<dyn BlockGenerator>>,
+		max_batch_size: usize,
+	) -> Self {
 		OwnerAPIHandlerV2 {
 			chain,
 			peers,
 			sync_state,
+			rate_limiter,
+			block_generator,
+			max_batch_size,
 		}
 	}
 }
@@ -185,21 +239,27 @@ impl crate::router::Handler for OwnerAPIHandlerV2 {
 			self.chain.clone(),
 			self.peers.clone(),
 			self.sync_state.clone(),
+			self.rate_limiter.clone(),
+			self.block_generator.as_ref().map(Arc::downgrade),
 		);
+		let max_batch_size = self.max_batch_size;
 
 		Box::pin(async move {
 			match parse_body(req).await {
 				Ok(val) => {
 					let owner_api = &api as &dyn OwnerRpc;
-					let res = match owner_api.handle_request(val) {
-						MaybeReply::Reply(r) => r,
-						MaybeReply::DontReply => {
-							// Since it's http, we need to return something. We return [] because jsonrpc
-							// clients will parse it as an empty batch response.
-							serde_json::json!([])
-						}
-					};
-					Ok(json_response_pretty(&res))
+					let res = handle_rpc_request(
+						owner_api,
+						val,
+						openrpc::owner_document,
+						max_batch_size,
+						true,
+					);
+					// Since it's http, we need to return something. We return [] because jsonrpc
+					// clients will parse it as an empty batch response.
+					Ok(json_response_pretty(
+						&res.unwrap_or_else(|| serde_json::json!([])),
+					))
 				}
 				Err(e) => {
 					error!("Request Error: {:?}", e);
@@ -219,6 +279,7 @@ pub struct ForeignAPIHandlerV2 {
 	pub chain: Weak<Chain>,
 	pub tx_pool: Weak<RwLock<pool::TransactionPool>>,
 	pub sync_state: Weak<SyncState>,
+	pub max_batch_size: usize,
 }
 
 impl ForeignAPIHandlerV2 {
@@ -227,11 +288,13 @@ impl ForeignAPIHandlerV2 {
 		chain: Weak<Chain>,
 		tx_pool: Weak<RwLock<pool::TransactionPool>>,
 		sync_state: Weak<SyncState>,
+		max_batch_size: usize,
 	) -> Self {
 		ForeignAPIHandlerV2 {
 			chain,
 			tx_pool,
 			sync_state,
+			max_batch_size,
 		}
 	}
 }
@@ -243,20 +306,24 @@ impl crate::router::Handler for ForeignAPIHandlerV2 {
 			self.tx_pool.clone(),
 			self.sync_state.clone(),
 		);
+		let max_batch_size = self.max_batch_size;
 
 		Box::pin(async move {
 			match parse_body(req).await {
 				Ok(val) => {
 					let foreign_api = &api as &dyn ForeignRpc;
-					let res = match foreign_api.handle_request(val) {
-						MaybeReply::Reply(r) => r,
-						MaybeReply::DontReply => {
-							// Since it's http, we need to return something. We return [] because jsonrpc
-							// clients will parse it as an empty batch response.
-							serde_json::json!([])
-						}
-					};
-					Ok(json_response_pretty(&res))
+					let res = handle_rpc_request(
+						foreign_api,
+						val,
+						openrpc::foreign_document,
+						max_batch_size,
+						true,
+					);
+					// Since it's http, we need to return something. We return [] because jsonrpc
+					// clients will parse it as an empty batch response.
+					Ok(json_response_pretty(
+						&res.unwrap_or_else(|| serde_json::json!([])),
+					))
 				}
 				Err(e) => {
 					error!("Request Error: {:?}", e);
@@ -274,32 +341,43 @@ impl crate::router::Handler for ForeignAPIHandlerV2 {
 /// V2 API Handler/Wrapper for stratum
 pub struct StratumAPIHandlerV2 {
 	stratum_ip_pool: Arc<stratum::connections::StratumIpPool>,
+	max_batch_size: usize,
 }
 
 impl StratumAPIHandlerV2 {
 	/// Create a new owner API handler for GET methods
-	pub fn new(stratum_ip_pool: Arc<stratum::connections::StratumIpPool>) -> Self {
-		StratumAPIHandlerV2 { stratum_ip_pool }
+	pub fn new(
+		stratum_ip_pool: Arc<stratum::connections::StratumIpPool>,
+		max_batch_size: usize,
+	) -> Self {
+		StratumAPIHandlerV2 {
+			stratum_ip_pool,
+			max_batch_size,
+		}
 	}
 }
 
 impl crate::router::Handler for StratumAPIHandlerV2 {
 	fn post(&self, req: Request<Body>) -> ResponseFuture {
 		let api = Stratum::new(self.stratum_ip_pool.clone());
+		let max_batch_size = self.max_batch_size;
 
 		Box::pin(async move {
 			match parse_body(req).await {
 				Ok(val) => {
 					let stratum_api = &api as &dyn StratumRpc;
-					let res = match stratum_api.handle_request(val) {
-						MaybeReply::Reply(r) => r,
-						MaybeReply::DontReply => {
-							// Since it's http, we need to return something. We return [] because jsonrpc
-							// clients will parse it as an empty batch response.
-							serde_json::json!([])
-						}
-					};
-					Ok(json_response_pretty(&res))
+					let res = handle_rpc_request(
+						stratum_api,
+						val,
+						openrpc::stratum_document,
+						max_batch_size,
+						false,
+					);
+					// Since it's http, we need to return something. We return [] because jsonrpc
+					// clients will parse it as an empty batch response.
+					Ok(json_response_pretty(
+						&res.unwrap_or_else(|| serde_json::json!([])),
+					))
 				}
 				Err(e) => {
 					error!("Request Error: {:?}", e);
@@ -328,6 +406,102 @@ where
 	}
 }
 
+/// Handle a JSON-RPC request, either a single call or a batch of calls.
+/// Batch calls are handled one by one so a malformed or failing call only
+/// affects its own reply, replies keep the order of the calls and
+/// notifications (calls without an id) get no reply. Returns None if there
+/// is nothing to reply. With `rpc_errors` set, `Err` results are replied as
+/// JSON-RPC error objects (see `rpc_error_reply`).
+fn handle_rpc_request<H: Handler + ?Sized>(
+	api: &H,
+	request: serde_json::Value,
+	document: fn() -> serde_json::Value,
+	max_batch_size: usize,
+	rpc_errors: bool,
+) -> Option<serde_json::Value> {
+	let calls = match request {
+		serde_json::Value::Array(calls) => calls,
+		call => return handle_rpc_call(api, call, document, rpc_errors),
+	};
+	if calls.is_empty() {
+		return Some(invalid_rpc_request(
+			serde_json::Value::Null,
+			"empty batch".to_owned(),
+		));
+	}
+	if calls.len() > max_batch_size {
+		return Some(invalid_rpc_request(
+			serde_json::Value::Null,
+			format!(
+				"batch of {} calls exceeds the maximum of {}",
+				calls.len(),
+				max_batch_size
+			),
+		));
+	}
+	let replies: Vec<serde_json::Value> = calls
+		.into_iter()
+		.filter_map(|call| handle_rpc_call(api, call, document, rpc_errors))
+		.collect();
+	if replies.is_empty() {
+		None
+	} else {
+		Some(serde_json::Value::Array(replies))
+	}
+}
+
+fn handle_rpc_call<H: Handler + ?Sized>(
+	api: &H,
+	call: serde_json::Value,
+	document: fn() -> serde_json::Value,
+	rpc_errors: bool,
+) -> Option<serde_json::Value> {
+	if call.get("method").and_then(|m| m.as_str()).is_none() {
+		let id = call.get("id").cloned().unwrap_or(serde_json::Value::Null);
+		return Some(invalid_rpc_request(id, "invalid request".to_owned()));
+	}
+	let is_notification = call.get("id").is_none();
+	let reply = match openrpc::discover_reply(&call, document) {
+		Some(reply) => Some(reply),
+		None => match api.handle_request(call) {
+			MaybeReply::Reply(r) if rpc_errors => Some(rpc_error_reply(r)),
+			MaybeReply::Reply(r) => Some(r),
+			MaybeReply::DontReply => None,
+		},
+	};
+	// Notifications are processed but never replied to
+	if is_notification {
+		None
+	} else {
+		reply
+	}
+}
+
+fn invalid_rpc_request(id: serde_json::Value, message: String) -> serde_json::Value {
+	serde_json::json!({
+		"jsonrpc": "2.0",
+		"id": id,
+		"error": {
+			"code": -32600,
+			"message": message,
+		},
+	})
+}
+
+/// Replace an `Err` result of a JSON-RPC reply with a JSON-RPC error object
+/// carrying the stable error code and machine readable data of the error.
+fn rpc_error_reply(mut reply: serde_json::Value) -> serde_json::Value {
+	let kind = match reply.get("result").and_then(|r| r.get("Err")) {
+		Some(err) => serde_json::from_value::<ErrorKind>(err.clone()),
+		None => return reply,
+	};
+	if let (Ok(kind), Some(obj)) = (kind, reply.as_object_mut()) {
+		obj.remove("result");
+		obj.insert("error".to_owned(), kind.to_rpc_error());
+	}
+	reply
+}
+
 fn create_error_response(e: Error) -> Response<Body> {
 	Response::builder()
 		.status(StatusCode::INTERNAL_SERVER_ERROR)
@@ -483,3 +657,49 @@ pub fn build_router(
 	router.add_route("/v1/version", Arc::new(version_handler))?;
 	Ok(router)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn test_rpc_batch() {
+		let pool = Arc::new(stratum::connections::StratumIpPool::new(10, 1, 100));
+		let api = Stratum::new(pool);
+		let api = &api as &dyn StratumRpc;
+
+		let batch = json!([
+			{"jsonrpc": "2.0", "method": "get_ip_list", "params": [null], "id": 1},
+			{"jsonrpc": "2.0", "method": "clean_ip", "params": ["127.0.0.1"]},
+			42,
+			{"jsonrpc": "2.0", "method": "no_such_method", "params": [], "id": 3},
+			{"jsonrpc": "2.0", "method": "rpc.discover", "id": 4},
+		]);
+		let replies = handle_rpc_request(api, batch, openrpc::stratum_document, 5, false).unwrap();
+		let replies = replies.as_array().unwrap();
+		// Notification gets no reply, every other call gets its own in order
+		assert_eq!(replies.len(), 4);
+		assert_eq!(replies[0]["id"], json!(1));
+		assert_eq!(replies[0]["result"]["Ok"], json!([]));
+		assert_eq!(replies[1]["id"], json!(null));
+		assert_eq!(replies[1]["error"]["code"], json!(-32600));
+		assert_eq!(replies[2]["id"], json!(3));
+		assert!(replies[2].get("error").is_some());
+		assert_eq!(replies[3]["id"], json!(4));
+		assert!(replies[3]["result"]["methods"].is_array());
+
+		// Batch of notifications only
+		let batch = json!([{"jsonrpc": "2.0", "method": "get_ip_list", "params": [null]}]);
+		assert!(handle_rpc_request(api, batch, openrpc::stratum_document, 5, false).is_none());
+
+		// Empty and oversized batches are rejected as a whole
+		let reply =
+			handle_rpc_request(api, json!([]), openrpc::stratum_document, 5, false).unwrap();
+		assert_eq!(reply["error"]["code"], json!(-32600));
+		let call = json!({"jsonrpc": "2.0", "method": "get_ip_list", "params": [null], "id": 1});
+		let batch = json!(vec![call; 6]);
+		let reply = handle_rpc_request(api, batch, openrpc::stratum_document, 5, false).unwrap();
+		assert_eq!(reply["error"]["code"], json!(-32600));
+	}
+}
@@ -14,7 +14,7 @@
 
 use super::utils::{get_output, get_output_v2, w};
 use crate::chain;
-use crate::core::core::hash::Hashed;
+use crate::core::core::hash::{Hash, Hashed};
 use crate::rest::*;
 use crate::router::{Handler, ResponseFuture};
 use crate::types::*;
@@ -53,24 +53,42 @@ pub struct ChainValidationHandler {
 
 impl ChainValidationHandler {
 	pub fn validate_chain(&self) -> Result<(), Error> {
-		w(&self.chain)?
-			.validate(true)
-			.map_err(|e| ErrorKind::Internal(format!("chain fast validation error. {}", e)).into())
+		w(&self.chain)?.validate(true)?;
+		Ok(())
 	}
 }
 
 impl Handler for ChainValidationHandler {
 	fn get(&self, _req: Request<Body>) -> ResponseFuture {
-		match w_fut!(&self.chain).validate(true) {
+		match self.validate_chain() {
 			Ok(_) => response(StatusCode::OK, "{}"),
-			Err(e) => response(
-				StatusCode::INTERNAL_SERVER_ERROR,
-				format!("chain validation is failed, {}", e),
-			),
+			Err(e) => result_to_response::<()>(Err(e)),
 		}
 	}
 }
 
+/// Full chain validation handler. Runs the (resumable) full validation of
+/// the chain state as a background job.
+pub struct ChainFullValidationHandler {
+	pub chain: Weak<chain::Chain>,
+}
+
+impl ChainFullValidationHandler {
+	pub fn start_validation(&self) -> Result<(), Error> {
+		chain::Chain::start_full_validation(w(&self.chain)?)?;
+		Ok(())
+	}
+
+	pub fn get_validation_status(&self) -> Result<ValidationStatus, Error> {
+		let status = w(&self.chain)?.full_validation_status();
+		Ok(ValidationStatus::from_validation_status(status))
+	}
+
+	pub fn cancel_validation(&self) -> Result<bool, Error> {
+		Ok(w(&self.chain)?.cancel_full_validation())
+	}
+}
+
 /// Chain compaction handler. Trigger a compaction of the chain state to regain
 /// storage space.
 /// POST /v1/chain/compact
@@ -80,24 +98,101 @@ pub struct ChainCompactHandler {
 
 impl ChainCompactHandler {
 	pub fn compact_chain(&self) -> Result<(), Error> {
-		w(&self.chain)?
-			.compact()
-			.map_err(|e| ErrorKind::Internal(format!("compact chain error {}", e)).into())
+		w(&self.chain)?.compact()?;
+		Ok(())
 	}
 }
 
 impl Handler for ChainCompactHandler {
 	fn post(&self, _req: Request<Body>) -> ResponseFuture {
-		match w_fut!(&self.chain).compact() {
+		match self.compact_chain() {
 			Ok(_) => response(StatusCode::OK, "{}"),
-			Err(e) => response(
-				StatusCode::INTERNAL_SERVER_ERROR,
-				format!("chain compact failed: {}", e),
-			),
+			Err(e) => result_to_response::<()>(Err(e)),
 		}
 	}
 }
 
+fn parse_block_hash(hash: &str) -> Result<Hash, Error> {
+	let vec = util::from_hex(hash)
+		.map_err(|e| ErrorKind::Argument(format!("invalid block hash {}, {}", hash, e)))?;
+	if vec.len() != 32 {
+		return Err(ErrorKind::Argument(format!("invalid block hash {}", hash)).into());
+	}
+	Ok(Hash::from_vec(&vec))
+}
+
+/// Block invalidation handler. Marks a block as invalid (or valid again),
+/// rewinding the chain and switching to the best valid fork as needed.
+pub struct ChainInvalidationHandler {
+	pub chain: Weak<chain::Chain>,
+}
+
+impl ChainInvalidationHandler {
+	pub fn invalidate_block(&self, hash: &str) -> Result<(), Error> {
+		let hash = parse_block_hash(hash)?;
+		w(&self.chain)?.invalidate_block(hash)?;
+		Ok(())
+	}
+
+	pub fn reconsider_block(&self, hash: &str) -> Result<(), Error> {
+		let hash = parse_block_hash(hash)?;
+		w(&self.chain)?.reconsider_block(hash)?;
+		Ok(())
+	}
+}
+
+/// Known chain tips, the active one and the forks.
+pub struct ChainTipsHandler {
+	pub chain: Weak<chain::Chain>,
+}
+
+impl ChainTipsHandler {
+	pub fn get_chain_tips(&self) -> Result<Vec<ChainTip>, Error> {
+		let tips = w(&self.chain)?
+			.chain_tips()?
+			.into_iter()
+			.map(ChainTip::from_chain_tip)
+			.collect();
+		Ok(tips)
+	}
+}
+
+/// Forks refused for exceeding the maximum reorg depth, and manual
+/// acceptance of such a fork.
+pub struct ChainReorgHandler {
+	pub chain: Weak<chain::Chain>,
+}
+
+impl ChainReorgHandler {
+	pub fn refused_reorgs(&self) -> Result<Vec<RefusedReorg>, Error> {
+		let reorgs = w(&self.chain)?
+			.refused_reorgs()
+			.into_iter()
+			.map(RefusedReorg::from_refused_reorg)
+			.collect();
+		Ok(reorgs)
+	}
+
+	pub fn accept_reorg(&self, hash: &str) -> Result<(), Error> {
+		let hash = parse_block_hash(hash)?;
+		w(&self.chain)?.accept_reorg(hash)?;
+		Ok(())
+	}
+}
+
+/// Chain rewind handler. Rewinds the chain state to the block at a given
+/// height, the removed blocks are processed again by the normal sync.
+pub struct ChainRewindHandler {
+	pub chain: Weak<chain::Chain>,
+}
+
+impl ChainRewindHandler {
+	pub fn rewind_chain(&self, height: u64) -> Result<Tip, Error> {
+		let tip = w(&self.chain)?.rewind_chain(height)?;
+		Ok(Tip::from_tip(tip))
+	}
+}
+
 // Supports retrieval of multiple outputs in a single request -
 // GET /v1/chain/outputs/byids?id=xxx,yyy,zzz
 // GET /v1/chain/outputs/byids?id=xxx&id=yyy&id=zzz
@@ -122,6 +217,59 @@ impl OutputHandler {
 		Ok(res.0)
 	}
 
+	pub fn get_output_history(&self, commit: String) -> Result<Vec<OutputHistory>, Error> {
+		let c = util::from_hex(&commit).map_err(|e| {
+			ErrorKind::Argument(format!("Not a valid commitment {}, {}", commit, e))
+		})?;
+		let commit = Commitment::from_vec(c);
+		let history = w(&self.chain)?
+			.get_output_history(&commit)
+			.map_err(|e| match e.kind() {
+				chain::ErrorKind::IndexNotEnabled(_) => {
+					ErrorKind::RequestError(format!("{}", e.kind()))
+				}
+				_ => ErrorKind::Internal(format!("can't get output history: {}", e)),
+			})?;
+		Ok(history
+			.iter()
+			.map(|x| OutputHistory::from_history(&commit, x))
+			.collect())
+	}
+
+	pub fn get_outputs_at_height(
+		&self,
+		commits: Vec<String>,
+		height: u64,
+	) -> Result<Vec<Output>, Error> {
+		let mut parsed = vec![];
+		for commit in &commits {
+			if commit.len() != 66 {
+				return Err(ErrorKind::RequestError(format!(
+					"invalid commit length for {}, expected length 66",
+					commit
+				))
+				.into());
+			}
+			let c = util::from_hex(commit).map_err(|e| {
+				ErrorKind::Argument(format!("Not a valid commitment {}, {}", commit, e))
+			})?;
+			parsed.push(Commitment::from_vec(c));
+		}
+		let unspent = w(&self.chain)?
+			.get_unspent_at_height(&parsed, height)
+			.map_err(|e| match e.kind() {
+				chain::ErrorKind::BelowHorizon(_, _) | chain::ErrorKind::RewindTooDeep(_) => {
+					ErrorKind::RequestError(format!("{}", e.kind()))
+				}
+				_ => ErrorKind::Internal(format!("can't get outputs at height {}: {}", height, e)),
+			})?;
+		Ok(parsed
+			.iter()
+			.zip(unspent)
+			.filter_map(|(commit, pos)| pos.map(|pos| Output::new(commit, pos.height, pos.pos)))
+			.collect())
+	}
+
 	pub fn get_outputs_v2(
 		&self,
 		commits: Option<Vec<String>>,
@@ -13,8 +13,10 @@
 // limitations under the License.
 
 use super::utils::w;
+use crate::chain;
 use crate::core::core::hash::Hashed;
-use crate::core::core::Transaction;
+use crate::core::core::{Transaction, TransactionBody, Weighting};
+use crate::core::global;
 use crate::core::ser::{self, ProtocolVersion};
 use crate::pool::{self, PoolEntry};
 use crate::rest::*;
@@ -83,15 +85,123 @@ impl PoolHandler {
 			.blockchain
 			.chain_head()
 			.map_err(|e| ErrorKind::Internal(format!("Failed to get chain head, {}", e)))?;
-		tx_pool
-			.add_to_pool(source, tx, !fluff.unwrap_or(false), &header)
-			.map_err(|e| ErrorKind::Internal(format!("Failed to update pool, {}", e)))?;
+		tx_pool.add_to_pool(source, tx, !fluff.unwrap_or(false), &header)?;
 
 		info!("transaction {} was added to the pool", tx_hash);
 
 		Ok(())
 	}
+	pub fn validate_transaction(&self, tx: Transaction) -> Result<TxValidation, Error> {
+		let pool_arc = w(&self.tx_pool)?;
+		let tx_pool = pool_arc.read();
+		let header = tx_pool
+			.blockchain
+			.chain_head()
+			.map_err(|e| ErrorKind::Internal(format!("Failed to get chain head, {}", e)))?;
+		let res = tx_pool.validate_tx(&tx, &header);
+		debug!("validate_transaction {}: {:?}", tx.hash(), res);
+		Ok(TxValidation {
+			valid: res.is_ok(),
+			error: res.err().map(|e| PoolErrorInfo::from_error(&e)),
+		})
+	}
+}
+
+/// Number of recent blocks looked at when estimating fees.
+const FEE_ESTIMATE_BLOCKS: u64 = 10;
+
+/// Estimates the fee needed for a transaction to be mined within a number of
+/// blocks, based on the txpool backlog and the fees paid in recent blocks.
+pub struct FeeHandler {
+	pub chain: Weak<chain::Chain>,
+	pub tx_pool: Weak<RwLock<pool::TransactionPool>>,
+}
+
+impl FeeHandler {
+	pub fn estimate_fee(&self, target_blocks: u64) -> Result<FeeEstimate, Error> {
+		if target_blocks == 0 {
+			return Err(
+				ErrorKind::RequestError("target_blocks must be at least 1".to_owned()).into(),
+			);
+		}
+		let chain = w(&self.chain)?;
+		let pool_arc = w(&self.tx_pool)?;
+
+		let (min_fee_per_weight, pool_fee_per_weight, pool_weight) = {
+			let tx_pool = pool_arc.read();
+			let capacity =
+				(tx_pool.config.mineable_max_weight as u64).saturating_mul(target_blocks);
+
+			// Buckets come sorted by fee_to_weight, the first transaction
+			// that doesn't fit in the target blocks sets the fee to beat.
+			let txs = tx_pool.txpool.bucket_transactions(Weighting::NoLimit);
+			let mut pool_weight = 0u64;
+			let mut pool_fee_per_weight = 0;
+			for tx in &txs {
+				pool_weight += tx.tx_weight_as_block() as u64;
+				if pool_weight > capacity && pool_fee_per_weight == 0 {
+					pool_fee_per_weight = tx.fee() / tx.tx_weight() as u64 + 1;
+				}
+			}
+			(
+				tx_pool.config.accept_fee_base,
+				pool_fee_per_weight,
+				pool_weight,
+			)
+		};
+
+		// Lowest fee rate that made it into a full block recently.
+		let mut blocks_fee_per_weight = 0;
+		let mut blocks_considered = 0;
+		let max_weight = global::max_block_weight();
+		let mut header = chain
+			.head_header()
+			.map_err(|e| ErrorKind::Internal(format!("can't get head header, {}", e)))?;
+		while blocks_considered < FEE_ESTIMATE_BLOCKS && header.height > 0 {
+			let block = chain
+				.get_block(&header.hash())
+				.map_err(|e| ErrorKind::Internal(format!("can't get block, {}", e)))?;
+			blocks_considered += 1;
+
+			let weight = TransactionBody::weight_as_block(
+				block.inputs().len(),
+				block.outputs().len(),
+				block.kernels().len(),
+			);
+			// Anything but the coinbase output and kernel was paid for.
+			let tx_weight = TransactionBody::weight(
+				block.inputs().len(),
+				block.outputs().len().saturating_sub(1),
+				block.kernels().len().saturating_sub(1),
+			) as u64;
+			if weight * 10 >= max_weight * 9 {
+				let fee_per_weight = block.total_fees() / tx_weight;
+				if blocks_fee_per_weight == 0 || fee_per_weight < blocks_fee_per_weight {
+					blocks_fee_per_weight = fee_per_weight;
+				}
+			}
+
+			header = chain
+				.get_previous_header(&header)
+				.map_err(|e| ErrorKind::Internal(format!("can't get previous header, {}", e)))?;
+		}
+
+		let fee_per_weight = min_fee_per_weight
+			.max(pool_fee_per_weight)
+			.max(blocks_fee_per_weight);
+
+		Ok(FeeEstimate {
+			target_blocks,
+			fee_per_weight,
+			min_fee_per_weight,
+			pool_fee_per_weight,
+			blocks_fee_per_weight,
+			blocks_considered,
+			pool_weight,
+		})
+	}
 }
+
 /// Dummy wrapper for the hex-encoded serialized transaction.
 #[derive(Serialize, Deserialize)]
 struct TxWrapper {
@@ -144,9 +254,7 @@ async fn update_pool(
 		.blockchain
 		.chain_head()
 		.map_err(|e| ErrorKind::Internal(format!("Failed to get chain head, {}", e)))?;
-	tx_pool
-		.add_to_pool(source, tx, !fluff, &header)
-		.map_err(|e| ErrorKind::Internal(format!("Failed to update pool, {}", e)))?;
+	tx_pool.add_to_pool(source, tx, !fluff, &header)?;
 	Ok(())
 }
 
@@ -156,11 +264,124 @@ impl Handler for PoolPushHandler {
 		Box::pin(async move {
 			let res = match update_pool(pool, req).await {
 				Ok(_) => just_response(StatusCode::OK, ""),
-				Err(e) => {
-					just_response(StatusCode::INTERNAL_SERVER_ERROR, format!("failed: {}", e))
-				}
+				Err(e) => match e.kind() {
+					ErrorKind::Pool(_) => {
+						just_response(StatusCode::BAD_REQUEST, e.kind().to_rpc_error().to_string())
+					}
+					_ => just_response(StatusCode::INTERNAL_SERVER_ERROR, format!("failed: {}", e)),
+				},
 			};
 			Ok(res)
 		})
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::core::core::hash::Hash;
+	use crate::core::core::verifier_cache::LruVerifierCache;
+	use crate::core::core::{BlockHeader, BlockSums, KernelFeatures, TxKernel};
+	use crate::core::pow;
+	use crate::pool::{BlockChain, PoolConfig, PoolError, TxSource};
+	use chrono::Utc;
+	use std::fs;
+	use std::sync::Arc;
+
+	// The fee estimate never validates against the chain.
+	struct NoChain;
+
+	impl BlockChain for NoChain {
+		fn verify_coinbase_maturity(&self, _tx: &Transaction) -> Result<(), PoolError> {
+			Ok(())
+		}
+		fn verify_tx_lock_height(&self, _tx: &Transaction) -> Result<(), PoolError> {
+			Ok(())
+		}
+		fn validate_tx(&self, _tx: &Transaction) -> Result<(), PoolError> {
+			Ok(())
+		}
+		fn chain_head(&self) -> Result<BlockHeader, PoolError> {
+			Err(PoolError::Other("no chain".to_owned()))
+		}
+		fn get_block_header(&self, _hash: &Hash) -> Result<BlockHeader, PoolError> {
+			Err(PoolError::Other("no chain".to_owned()))
+		}
+		fn get_block_sums(&self, _hash: &Hash) -> Result<BlockSums, PoolError> {
+			Err(PoolError::Other("no chain".to_owned()))
+		}
+	}
+
+	fn kernel_tx(fee: u64) -> Transaction {
+		Transaction::empty().with_kernel(TxKernel::with_features(KernelFeatures::Plain { fee }))
+	}
+
+	#[test]
+	fn test_estimate_fee() {
+		global::set_mining_mode(global::ChainTypes::AutomatedTesting);
+		let db_root = ".mimble_fee_estimate";
+		let _ = fs::remove_dir_all(db_root);
+		{
+			let verifier_cache = Arc::new(RwLock::new(LruVerifierCache::new()));
+			let chain = Arc::new(
+				chain::Chain::init(
+					db_root.to_owned(),
+					Arc::new(chain::types::NoopAdapter {}),
+					pow::mine_genesis_block().unwrap(),
+					pow::verify_size,
+					verifier_cache.clone(),
+					chain::ChainOptions::default(),
+				)
+				.unwrap(),
+			);
+
+			// The pool holds three transactions of the same weight at decreasing
+			// fee rates, two of them fit in a block.
+			let tx_weight = kernel_tx(0).tx_weight() as u64;
+			let tx_weight_as_block = kernel_tx(0).tx_weight_as_block();
+			let config = PoolConfig {
+				accept_fee_base: 10,
+				mineable_max_weight: 2 * tx_weight_as_block,
+				..PoolConfig::default()
+			};
+			let tx_pool = Arc::new(RwLock::new(pool::TransactionPool::new(
+				config,
+				Arc::new(NoChain),
+				verifier_cache,
+				Arc::new(pool::types::NoopAdapter {}),
+			)));
+			for fee in &[3_000, 2_000, 1_000] {
+				tx_pool.write().txpool.entries.push(PoolEntry {
+					src: TxSource::PushApi,
+					tx_at: Utc::now(),
+					tx: kernel_tx(*fee),
+				});
+			}
+
+			let handler = FeeHandler {
+				chain: Arc::downgrade(&chain),
+				tx_pool: Arc::downgrade(&tx_pool),
+			};
+
+			// The lowest fee rate transaction doesn't make it in the next block.
+			let estimate = handler.estimate_fee(1).unwrap();
+			assert_eq!(estimate.target_blocks, 1);
+			assert_eq!(estimate.pool_weight, 3 * tx_weight_as_block as u64);
+			assert_eq!(estimate.min_fee_per_weight, 10);
+			assert_eq!(estimate.pool_fee_per_weight, 1_000 / tx_weight + 1);
+			assert_eq!(estimate.fee_per_weight, 1_000 / tx_weight + 1);
+
+			// Only the genesis block is on chain.
+			assert_eq!(estimate.blocks_considered, 0);
+			assert_eq!(estimate.blocks_fee_per_weight, 0);
+
+			// Everything fits in two blocks, the minimum fee rate applies.
+			let estimate = handler.estimate_fee(2).unwrap();
+			assert_eq!(estimate.pool_fee_per_weight, 0);
+			assert_eq!(estimate.fee_per_weight, 10);
+
+			assert!(handler.estimate_fee(0).is_err());
+		}
+		let _ = fs::remove_dir_all(db_root);
+	}
+}
@@ -0,0 +1,277 @@
+// Copyright 2020 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Server-sent events stream of node events for foreign API clients.
+
+use crate::router::{Handler, ResponseFuture};
+use crate::util::RwLock;
+use crate::web::*;
+use bytes::Bytes;
+use futures::channel::mpsc;
+use futures::future::ok;
+use hyper::header::{CACHE_CONTROL, CONTENT_TYPE};
+use hyper::{Body, Request, Response, StatusCode};
+use serde::Serialize;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Default number of pending events buffered per client before the client
+/// is considered too slow and gets disconnected.
+pub const DEFAULT_SUBSCRIBE_BUFFER_SIZE: usize = 64;
+
+/// Default maximum number of clients connected to the event stream at once.
+pub const DEFAULT_MAX_SUBSCRIBERS: usize = 100;
+
+/// Event topics a client can subscribe to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Topic {
+	/// Chain head moved (either extended or reorged)
+	NewTip,
+	/// Block accepted by the chain (next, reorg or fork)
+	BlockAccepted,
+	/// Transaction accepted into the txpool
+	TxPoolAdded,
+	/// Transaction evicted from the txpool
+	TxPoolEvicted,
+}
+
+impl Topic {
+	/// All known topics, used when a client does not specify any.
+	pub fn all() -> Vec<Topic> {
+		vec![
+			Topic::NewTip,
+			Topic::BlockAccepted,
+			Topic::TxPoolAdded,
+			Topic::TxPoolEvicted,
+		]
+	}
+
+	/// Name of the topic as used in the query string and in the event field.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Topic::NewTip => "new_tip",
+			Topic::BlockAccepted => "block_accepted",
+			Topic::TxPoolAdded => "tx_pool_added",
+			Topic::TxPoolEvicted => "tx_pool_evicted",
+		}
+	}
+
+	/// Parse a topic from its name.
+	pub fn from_name(name: &str) -> Option<Topic> {
+		match name {
+			"new_tip" => Some(Topic::NewTip),
+			"block_accepted" => Some(Topic::BlockAccepted),
+			"tx_pool_added" => Some(Topic::TxPoolAdded),
+			"tx_pool_evicted" => Some(Topic::TxPoolEvicted),
+			_ => None,
+		}
+	}
+}
+
+struct Subscriber {
+	id: u64,
+	topics: Vec<Topic>,
+	sender: mpsc::Sender<Result<Bytes, io::Error>>,
+}
+
+/// Fans node events out to all connected subscribers. Every subscriber has
+/// its own bounded buffer, a subscriber that can't keep up is dropped rather
+/// than slowing down the caller.
+pub struct EventBroadcaster {
+	buffer_size: usize,
+	max_subscribers: usize,
+	next_id: AtomicU64,
+	subscribers: RwLock<Vec<Subscriber>>,
+}
+
+impl EventBroadcaster {
+	/// Create a broadcaster with the given per-client buffer size, accepting
+	/// at most `max_subscribers` clients at once.
+	pub fn new(buffer_size: usize, max_subscribers: usize) -> EventBroadcaster {
+		EventBroadcaster {
+			buffer_size: buffer_size.max(1),
+			max_subscribers,
+			next_id: AtomicU64::new(0),
+			subscribers: RwLock::new(vec![]),
+		}
+	}
+
+	/// Number of currently connected subscribers.
+	pub fn subscriber_count(&self) -> usize {
+		self.subscribers.read().len()
+	}
+
+	/// Register a new subscriber for the provided topics, returns the receiving
+	/// end of its event stream or `None` if the subscriber limit is reached.
+	fn subscribe(&self, topics: Vec<Topic>) -> Option<mpsc::Receiver<Result<Bytes, io::Error>>> {
+		let mut subscribers = self.subscribers.write();
+		// Disconnected clients must not hold on to a slot until the next event
+		subscribers.retain(|s| !s.sender.is_closed());
+		if subscribers.len() >= self.max_subscribers {
+			warn!(
+				"subscribe: rejecting subscriber, limit of {} reached",
+				self.max_subscribers
+			);
+			return None;
+		}
+		let (sender, receiver) = mpsc::channel(self.buffer_size);
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+		debug!("subscribe: new subscriber {} for {:?}", id, topics);
+		subscribers.push(Subscriber { id, topics, sender });
+		Some(receiver)
+	}
+
+	/// Publish an event to every subscriber of the topic.
+	pub fn publish<T: Serialize>(&self, topic: Topic, payload: &T) {
+		{
+			let subscribers = self.subscribers.read();
+			if !subscribers.iter().any(|s| s.topics.contains(&topic)) {
+				return;
+			}
+		}
+		let data = match serde_json::to_string(payload) {
+			Ok(data) => data,
+			Err(e) => {
+				error!(
+					"subscribe: failed to serialize {} event: {}",
+					topic.as_str(),
+					e
+				);
+				return;
+			}
+		};
+		let event = Bytes::from(format!("event: {}\ndata: {}\n\n", topic.as_str(), data));
+
+		let mut subscribers = self.subscribers.write();
+		let mut kept = Vec::with_capacity(subscribers.len());
+		for mut s in subscribers.drain(..) {
+			if s.topics.contains(&topic) {
+				if let Err(e) = s.sender.try_send(Ok(event.clone())) {
+					if e.is_full() {
+						warn!("subscribe: subscriber {} is too slow, dropping it", s.id);
+					} else {
+						debug!("subscribe: subscriber {} disconnected", s.id);
+					}
+					continue;
+				}
+			}
+			kept.push(s);
+		}
+		*subscribers = kept;
+	}
+}
+
+/// Stream of node events as server-sent events.
+/// GET /v2/foreign/subscribe?topics=new_tip,block_accepted,tx_pool_added,tx_pool_evicted
+pub struct SubscribeHandler {
+	pub broadcaster: Arc<EventBroadcaster>,
+}
+
+impl SubscribeHandler {
+	fn parse_topics(req: &Request<Body>) -> Result<Vec<Topic>, String> {
+		let params = QueryParams::from(req.uri().query());
+		let mut topics = vec![];
+		let mut unknown = vec![];
+		params.process_multival_param("topics", |name| match Topic::from_name(name) {
+			Some(topic) => {
+				if !topics.contains(&topic) {
+					topics.push(topic);
+				}
+			}
+			None => unknown.push(name.to_string()),
+		});
+		if !unknown.is_empty() {
+			return Err(format!("unknown topics: {}", unknown.join(",")));
+		}
+		if topics.is_empty() {
+			topics = Topic::all();
+		}
+		Ok(topics)
+	}
+}
+
+impl Handler for SubscribeHandler {
+	fn get(&self, req: Request<Body>) -> ResponseFuture {
+		let topics = match SubscribeHandler::parse_topics(&req) {
+			Ok(topics) => topics,
+			Err(e) => return response(StatusCode::BAD_REQUEST, e),
+		};
+		let receiver = match self.broadcaster.subscribe(topics) {
+			Some(receiver) => receiver,
+			None => {
+				return response(
+					StatusCode::SERVICE_UNAVAILABLE,
+					"too many subscribers, try again later",
+				)
+			}
+		};
+		// Comment line so clients get the headers and first bytes right away
+		let hello = futures::stream::once(futures::future::ready(Ok::<_, io::Error>(Bytes::from(
+			": subscribed\n\n",
+		))));
+		let body = Body::wrap_stream(futures::StreamExt::chain(hello, receiver));
+
+		let res = Response::builder()
+			.status(StatusCode::OK)
+			.header(CONTENT_TYPE, "text/event-stream")
+			.header(CACHE_CONTROL, "no-cache")
+			.body(body)
+			.unwrap();
+		Box::pin(ok(res))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_topic_filter_and_slow_client() {
+		let broadcaster = EventBroadcaster::new(1, 10);
+		let mut tips = broadcaster.subscribe(vec![Topic::NewTip]).unwrap();
+		let mut txs = broadcaster.subscribe(vec![Topic::TxPoolAdded]).unwrap();
+		assert_eq!(broadcaster.subscriber_count(), 2);
+
+		broadcaster.publish(Topic::NewTip, &1);
+		let event = tips.try_next().unwrap().unwrap().unwrap();
+		assert_eq!(event, Bytes::from("event: new_tip\ndata: 1\n\n"));
+		assert!(txs.try_next().is_err());
+
+		// Nobody reads the tip stream, once its buffer is full it gets dropped
+		for i in 0..3 {
+			broadcaster.publish(Topic::NewTip, &i);
+		}
+		assert_eq!(broadcaster.subscriber_count(), 1);
+
+		// Disconnected clients are removed on the next event for their topic
+		drop(txs);
+		broadcaster.publish(Topic::TxPoolAdded, &0);
+		assert_eq!(broadcaster.subscriber_count(), 0);
+	}
+
+	#[test]
+	fn test_subscriber_limit() {
+		let broadcaster = EventBroadcaster::new(1, 2);
+		let first = broadcaster.subscribe(Topic::all()).unwrap();
+		let _second = broadcaster.subscribe(Topic::all()).unwrap();
+		assert!(broadcaster.subscribe(Topic::all()).is_none());
+		assert_eq!(broadcaster.subscriber_count(), 2);
+
+		// A disconnected client frees its slot right away
+		drop(first);
+		assert!(broadcaster.subscribe(Topic::all()).is_some());
+		assert_eq!(broadcaster.subscriber_count(), 2);
+	}
+}
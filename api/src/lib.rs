@@ -38,8 +38,10 @@ pub mod client;
 mod foreign;
 mod foreign_rpc;
 mod handlers;
+mod openrpc;
 mod owner;
 mod owner_rpc;
+mod rate_limit;
 mod rest;
 mod router;
 mod stratum;
@@ -47,13 +49,21 @@ mod stratum_rpc;
 mod types;
 
 pub use crate::auth::{
-	BasicAuthMiddleware, BasicAuthURIMiddleware, MWC_BASIC_REALM, MWC_FOREIGN_BASIC_REALM,
+	ApiToken, ApiTokens, BasicAuthMiddleware, BasicAuthURIMiddleware, ALLOW_ALL, MWC_BASIC_REALM,
+	MWC_FOREIGN_BASIC_REALM, MWC_METRICS_BASIC_REALM,
 };
 pub use crate::foreign::Foreign;
 pub use crate::foreign_rpc::ForeignRpc;
-pub use crate::handlers::node_apis;
-pub use crate::owner::Owner;
+pub use crate::handlers::subscribe_api::{
+	EventBroadcaster, Topic, DEFAULT_MAX_SUBSCRIBERS, DEFAULT_SUBSCRIBE_BUFFER_SIZE,
+};
+pub use crate::handlers::{node_apis, DEFAULT_MAX_BATCH_SIZE};
+pub use crate::openrpc::RPC_DISCOVER;
+pub use crate::owner::{BlockGenerator, Owner, MAX_GENERATE_BLOCKS};
 pub use crate::owner_rpc::OwnerRpc;
+pub use crate::rate_limit::{
+	RateLimitClient, RateLimitConfig, RateLimitMiddleware, RateLimitStatus, RateLimiter,
+};
 pub use crate::rest::*;
 pub use crate::router::*;
 pub use crate::types::*;
@@ -0,0 +1,1050 @@
+// Copyright 2020 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! OpenRPC documents describing the V2 JSON-RPC APIs, served through the
+//! `rpc.discover` method on every V2 endpoint.
+
+use serde_json::{json, Map, Value};
+
+/// Name of the JSON-RPC method returning the OpenRPC document of an endpoint.
+pub const RPC_DISCOVER: &str = "rpc.discover";
+
+const OPENRPC_VERSION: &str = "1.2.6";
+
+/// If the request is a `rpc.discover` call, build its reply carrying the
+/// provided document. Any other request is left to the API itself.
+pub fn discover_reply(request: &Value, document: fn() -> Value) -> Option<Value> {
+	if request.get("method").and_then(|m| m.as_str()) != Some(RPC_DISCOVER) {
+		return None;
+	}
+	let id = request.get("id").cloned().unwrap_or(Value::Null);
+	Some(json!({
+		"jsonrpc": "2.0",
+		"id": id,
+		"result": document(),
+	}))
+}
+
+/// OpenRPC document of the foreign API (/v2/foreign).
+pub fn foreign_document() -> Value {
+	let header_query = || {
+		vec![
+			param("height", uint(), false),
+			param("hash", hex(), false),
+			param("commit", hex(), false),
+		]
+	};
+	document(
+		"Node Foreign API",
+		"Public node API, safe to expose to wallets and other clients.",
+		vec![
+			method(
+				"get_header",
+				"Block header by height, hash or output commitment.",
+				header_query(),
+				schema_ref("BlockHeaderPrintable"),
+			),
+			method(
+				"get_block",
+				"Full block by height, hash or output commitment.",
+				header_query(),
+				schema_ref("BlockPrintable"),
+			),
+			method(
+				"get_version",
+				"Node version and current block header version.",
+				vec![],
+				schema_ref("Version"),
+			),
+			method("get_tip", "Current chain head.", vec![], schema_ref("Tip")),
+			method(
+				"get_kernel",
+				"Kernel with the given excess, optionally within a height range.",
+				vec![
+					param("excess", hex(), true),
+					param("min_height", uint(), false),
+					param("max_height", uint(), false),
+				],
+				schema_ref("LocatedTxKernel"),
+			),
+			method(
+				"get_outputs",
+				"Outputs by commitment and/or block height range.",
+				vec![
+					param("commits", array(hex()), false),
+					param("start_height", uint(), false),
+					param("end_height", uint(), false),
+					param("include_proof", boolean(), false),
+					param("include_merkle_proof", boolean(), false),
+				],
+				array(schema_ref("OutputPrintable")),
+			),
+			method(
+				"get_unspent_outputs",
+				"Unspent outputs by output MMR index range.",
+				vec![
+					param("start_index", uint(), true),
+					param("end_index", uint(), false),
+					param("max", uint(), true),
+					param("include_proof", boolean(), false),
+				],
+				schema_ref("OutputListing"),
+			),
+			method(
+				"get_output_history",
+				"Every occurrence of a commitment on the chain, spent or not.",
+				vec![param("commit", hex(), true)],
+				array(schema_ref("OutputHistory")),
+			),
+			method(
+				"get_outputs_at_height",
+				"Unspent outputs among the commitments that exist at the given height.",
+				vec![
+					param("commits", array(hex()), true),
+					param("height", uint(), true),
+				],
+				array(schema_ref("Output")),
+			),
+			method(
+				"get_pmmr_indices",
+				"Output MMR index range of a block height range.",
+				vec![
+					param("start_block_height", uint(), true),
+					param("end_block_height", uint(), false),
+				],
+				schema_ref("OutputListing"),
+			),
+			method(
+				"get_pool_size",
+				"Number of transactions in the transaction pool.",
+				vec![],
+				uint(),
+			),
+			method(
+				"get_stempool_size",
+				"Number of transactions in the stem pool.",
+				vec![],
+				uint(),
+			),
+			method(
+				"get_unconfirmed_transactions",
+				"Transactions currently in the transaction pool.",
+				vec![],
+				array(schema_ref("PoolEntry")),
+			),
+			method(
+				"push_transaction",
+				"Push a transaction to the pool, stemmed unless fluff is set.",
+				vec![
+					param("tx", schema_ref("Transaction"), true),
+					param("fluff", boolean(), false),
+				],
+				null(),
+			),
+			method(
+				"validate_transaction",
+				"Dry run of the pool acceptance checks, the pool is left untouched.",
+				vec![param("tx", schema_ref("Transaction"), true)],
+				schema_ref("TxValidation"),
+			),
+			method(
+				"estimate_fee",
+				"Fee per weight unit needed to be mined within the target number of blocks.",
+				vec![param("target_blocks", uint(), true)],
+				schema_ref("FeeEstimate"),
+			),
+		],
+		foreign_schemas(),
+	)
+}
+
+/// OpenRPC document of the owner API (/v2/owner).
+pub fn owner_document() -> Value {
+	document(
+		"Node Owner API",
+		"Node administration API, should only be reachable by the node owner.",
+		vec![
+			method(
+				"get_status",
+				"Node status: connections, chain head and sync state.",
+				vec![],
+				schema_ref("Status"),
+			),
+			method(
+				"validate_chain",
+				"Run a full validation of the chain state.",
+				vec![],
+				null(),
+			),
+			method(
+				"start_chain_validation",
+				"Start a resumable full validation of the chain state in the background.",
+				vec![],
+				null(),
+			),
+			method(
+				"get_chain_validation_status",
+				"Progress of the full chain validation.",
+				vec![],
+				schema_ref("ValidationStatus"),
+			),
+			method(
+				"cancel_chain_validation",
+				"Stop the full chain validation, returns whether one was running.",
+				vec![],
+				boolean(),
+			),
+			method(
+				"compact_chain",
+				"Trigger a compaction of the chain state.",
+				vec![],
+				null(),
+			),
+			method(
+				"invalidate_block",
+				"Mark a block as invalid, rewinding the chain if it includes the block.",
+				vec![param("hash", hex(), true)],
+				null(),
+			),
+			method(
+				"reconsider_block",
+				"Remove the invalid mark of a block and switch to the best valid fork.",
+				vec![param("hash", hex(), true)],
+				null(),
+			),
+			method(
+				"get_chain_tips",
+				"Known chain tips (headers without children), highest first.",
+				vec![],
+				array(schema_ref("ChainTip")),
+			),
+			method(
+				"get_refused_reorgs",
+				"Forks recently refused for being deeper than the maximum reorg depth.",
+				vec![],
+				array(schema_ref("RefusedReorg")),
+			),
+			method(
+				"accept_reorg",
+				"Accept a fork regardless of the maximum reorg depth.",
+				vec![param("hash", hex(), true)],
+				null(),
+			),
+			method(
+				"rewind_chain",
+				"Rewind the chain to the block at the given height, removed blocks are kept as fork blocks.",
+				vec![param("height", uint(), true)],
+				schema_ref("Tip"),
+			),
+			method(
+				"get_peers",
+				"Stored peers, or the single peer with the given address.",
+				vec![param("peer_addr", socket_addr(), false)],
+				array(schema_ref("PeerData")),
+			),
+			method(
+				"get_connected_peers",
+				"Currently connected peers.",
+				vec![],
+				array(schema_ref("PeerInfoDisplayLegacy")),
+			),
+			method(
+				"ban_peer",
+				"Ban the peer with the given address.",
+				vec![param("peer_addr", socket_addr(), true)],
+				null(),
+			),
+			method(
+				"unban_peer",
+				"Unban the peer with the given address.",
+				vec![param("peer_addr", socket_addr(), true)],
+				null(),
+			),
+			method(
+				"get_rate_limits",
+				"State of the foreign API rate limiter and of its clients.",
+				vec![],
+				schema_ref("RateLimitStatus"),
+			),
+			method(
+				"generate_blocks",
+				"Mine blocks with the test proof of work and apply them, testing chains only.",
+				vec![
+					param("count", uint(), true),
+					param("reward_destination", string(), false),
+				],
+				array(hex()),
+			),
+			method(
+				"set_mock_time",
+				"Set the node clock to a fixed unix timestamp, or back to the system clock if null, testing chains only.",
+				vec![param("timestamp", int(), false)],
+				null(),
+			),
+		],
+		owner_schemas(),
+	)
+}
+
+/// OpenRPC document of the stratum API (/v2/stratum).
+pub fn stratum_document() -> Value {
+	document(
+		"Stratum API",
+		"Management of the stratum server IP pool.",
+		vec![
+			method(
+				"get_ip_list",
+				"Stratum IP pool entries, optionally only banned or active ones.",
+				vec![param("banned", boolean(), false)],
+				array(schema_ref("StratumIpPrintable")),
+			),
+			method(
+				"clean_ip",
+				"Reset the data of an IP, lifting its ban if any.",
+				vec![param("ip", string(), true)],
+				null(),
+			),
+			method(
+				"get_ip_info",
+				"Stratum IP pool data of a single IP.",
+				vec![param("ip", string(), true)],
+				schema_ref("StratumIpPrintable"),
+			),
+		],
+		vec![(
+			"StratumIpPrintable",
+			object(
+				&[
+					"ip",
+					"ban",
+					"workers",
+					"ok_shares",
+					"ok_logins",
+					"failed_login",
+					"failed_requests",
+				],
+				vec![
+					("ip", string()),
+					("ban", boolean()),
+					("last_connect_time_ms", nullable(int())),
+					("workers", int()),
+					("ok_shares", uint()),
+					("ok_logins", uint()),
+					("failed_login", uint()),
+					("failed_requests", uint()),
+				],
+			),
+		)],
+	)
+}
+
+fn foreign_schemas() -> Vec<(&'static str, Value)> {
+	let mut schemas = vec![
+		(
+			"Version",
+			object(
+				&["node_version", "block_header_version"],
+				vec![("node_version", string()), ("block_header_version", uint())],
+			),
+		),
+		(
+			"BlockHeaderPrintable",
+			object(
+				&[
+					"hash",
+					"version",
+					"height",
+					"previous",
+					"prev_root",
+					"timestamp",
+					"output_root",
+					"range_proof_root",
+					"kernel_root",
+					"nonce",
+					"edge_bits",
+					"cuckoo_solution",
+					"total_difficulty",
+					"secondary_scaling",
+					"total_kernel_offset",
+				],
+				vec![
+					("hash", hex()),
+					("version", uint()),
+					("height", uint()),
+					("previous", hex()),
+					("prev_root", hex()),
+					("timestamp", date_time()),
+					("output_root", hex()),
+					("range_proof_root", hex()),
+					("kernel_root", hex()),
+					("nonce", uint()),
+					("edge_bits", uint()),
+					("cuckoo_solution", array(uint())),
+					("total_difficulty", uint()),
+					("secondary_scaling", uint()),
+					("total_kernel_offset", hex()),
+				],
+			),
+		),
+		(
+			"BlockPrintable",
+			object(
+				&["header", "inputs", "outputs", "kernels"],
+				vec![
+					("header", schema_ref("BlockHeaderPrintable")),
+					("inputs", array(hex())),
+					("outputs", array(schema_ref("OutputPrintable"))),
+					("kernels", array(schema_ref("TxKernelPrintable"))),
+				],
+			),
+		),
+		(
+			"OutputPrintable",
+			object(
+				&["output_type", "commit", "spent", "proof_hash", "mmr_index"],
+				vec![
+					("output_type", string_enum(&["Coinbase", "Transaction"])),
+					("commit", hex()),
+					("spent", boolean()),
+					("proof", nullable(hex())),
+					("proof_hash", hex()),
+					("block_height", nullable(uint())),
+					("merkle_proof", nullable(hex())),
+					("mmr_index", uint()),
+				],
+			),
+		),
+		(
+			"TxKernelPrintable",
+			object(
+				&["features", "fee", "lock_height", "excess", "excess_sig"],
+				vec![
+					("features", string()),
+					("fee", uint()),
+					("lock_height", uint()),
+					("excess", hex()),
+					("excess_sig", hex()),
+				],
+			),
+		),
+		(
+			"Output",
+			object(
+				&["commit", "height", "mmr_index"],
+				vec![("commit", hex()), ("height", uint()), ("mmr_index", uint())],
+			),
+		),
+		(
+			"OutputListing",
+			object(
+				&["highest_index", "last_retrieved_index", "outputs"],
+				vec![
+					("highest_index", uint()),
+					("last_retrieved_index", uint()),
+					("outputs", array(schema_ref("OutputPrintable"))),
+				],
+			),
+		),
+		(
+			"OutputHistory",
+			object(
+				&["commit", "mmr_index", "height", "spent"],
+				vec![
+					("commit", hex()),
+					("mmr_index", uint()),
+					("height", uint()),
+					("spent", boolean()),
+					("spent_height", nullable(uint())),
+					("spent_block_hash", nullable(hex())),
+				],
+			),
+		),
+		(
+			"LocatedTxKernel",
+			object(
+				&["tx_kernel", "height", "mmr_index"],
+				vec![
+					("tx_kernel", schema_ref("TxKernel")),
+					("height", uint()),
+					("mmr_index", uint()),
+				],
+			),
+		),
+		(
+			"PoolEntry",
+			object(
+				&["src", "tx_at", "tx"],
+				vec![
+					(
+						"src",
+						string_enum(&[
+							"PushApi",
+							"Broadcast",
+							"Fluff",
+							"EmbargoExpired",
+							"Deaggregate",
+						]),
+					),
+					("tx_at", date_time()),
+					("tx", schema_ref("Transaction")),
+				],
+			),
+		),
+		(
+			"PoolErrorInfo",
+			object(
+				&["code", "kind", "message"],
+				vec![
+					("code", int()),
+					("kind", string()),
+					("message", string()),
+					("commitment", nullable(hex())),
+					("min_fee", nullable(uint())),
+				],
+			),
+		),
+		(
+			"TxValidation",
+			object(
+				&["valid"],
+				vec![
+					("valid", boolean()),
+					("error", nullable(schema_ref("PoolErrorInfo"))),
+				],
+			),
+		),
+		(
+			"FeeEstimate",
+			object(
+				&[
+					"target_blocks",
+					"fee_per_weight",
+					"min_fee_per_weight",
+					"pool_fee_per_weight",
+					"blocks_fee_per_weight",
+					"blocks_considered",
+					"pool_weight",
+				],
+				vec![
+					("target_blocks", uint()),
+					("fee_per_weight", uint()),
+					("min_fee_per_weight", uint()),
+					("pool_fee_per_weight", uint()),
+					("blocks_fee_per_weight", uint()),
+					("blocks_considered", uint()),
+					("pool_weight", uint()),
+				],
+			),
+		),
+	];
+	schemas.push(tip_schema());
+	schemas.extend(transaction_schemas());
+	schemas
+}
+
+fn owner_schemas() -> Vec<(&'static str, Value)> {
+	let capabilities = object(&["bits"], vec![("bits", uint())]);
+	vec![
+		tip_schema(),
+		(
+			"Status",
+			object(
+				&[
+					"protocol_version",
+					"user_agent",
+					"connections",
+					"tip",
+					"sync_status",
+				],
+				vec![
+					("protocol_version", uint()),
+					("user_agent", string()),
+					("connections", uint()),
+					("tip", schema_ref("Tip")),
+					("sync_status", string()),
+					("sync_info", json!({ "type": "object" })),
+					("active_rules", array(schema_ref("ConsensusRule"))),
+					("pending_rules", array(schema_ref("ConsensusRule"))),
+				],
+			),
+		),
+		(
+			"ConsensusRule",
+			object(
+				&["name", "activation_height"],
+				vec![
+					("name", string_enum(&["header_version_2", "nrd_kernels"])),
+					("activation_height", uint()),
+				],
+			),
+		),
+		(
+			"PeerData",
+			object(
+				&[
+					"addr",
+					"capabilities",
+					"user_agent",
+					"flags",
+					"last_banned",
+					"ban_reason",
+					"last_connected",
+				],
+				vec![
+					("addr", socket_addr()),
+					("capabilities", capabilities.clone()),
+					("user_agent", string()),
+					("flags", string_enum(&["Healthy", "Banned", "Defunct"])),
+					("last_banned", int()),
+					(
+						"ban_reason",
+						string_enum(&[
+							"None",
+							"BadBlock",
+							"BadCompactBlock",
+							"BadBlockHeader",
+							"BadTxHashSet",
+							"ManualBan",
+							"FraudHeight",
+							"BadHandshake",
+							"ReorgTooDeep",
+						]),
+					),
+					("last_connected", int()),
+				],
+			),
+		),
+		(
+			"PeerInfoDisplayLegacy",
+			object(
+				&[
+					"capabilities",
+					"user_agent",
+					"version",
+					"addr",
+					"direction",
+					"total_difficulty",
+					"height",
+				],
+				vec![
+					("capabilities", capabilities),
+					("user_agent", string()),
+					("version", uint()),
+					("addr", socket_addr()),
+					(
+						"direction",
+						string_enum(&["Inbound", "Outbound", "InboundTor", "OutboundTor"]),
+					),
+					("total_difficulty", uint()),
+					("height", uint()),
+				],
+			),
+		),
+		(
+			"RateLimitStatus",
+			object(
+				&["enabled", "capacity", "refill_per_sec", "clients"],
+				vec![
+					("enabled", boolean()),
+					("capacity", uint()),
+					("refill_per_sec", uint()),
+					("clients", array(schema_ref("RateLimitClient"))),
+				],
+			),
+		),
+		(
+			"RateLimitClient",
+			object(
+				&["key", "tokens", "accepted", "throttled", "last_throttled"],
+				vec![
+					("key", string()),
+					("tokens", uint()),
+					("accepted", uint()),
+					("throttled", uint()),
+					("last_throttled", int()),
+				],
+			),
+		),
+		(
+			"ChainTip",
+			object(
+				&[
+					"height",
+					"hash",
+					"total_difficulty",
+					"branch_length",
+					"status",
+				],
+				vec![
+					("height", uint()),
+					("hash", hex()),
+					("total_difficulty", uint()),
+					("branch_length", uint()),
+					(
+						"status",
+						string_enum(&["active", "valid-fork", "headers-only", "invalid"]),
+					),
+				],
+			),
+		),
+		(
+			"ValidationStatus",
+			object(
+				&["phase", "height", "done", "total", "resumed"],
+				vec![
+					(
+						"phase",
+						string_enum(&[
+							"idle",
+							"sums",
+							"rangeproofs",
+							"kernels",
+							"done",
+							"cancelled",
+							"failed",
+						]),
+					),
+					("height", uint()),
+					("done", uint()),
+					("total", uint()),
+					("resumed", boolean()),
+					("error", nullable(string())),
+				],
+			),
+		),
+		(
+			"RefusedReorg",
+			object(
+				&[
+					"hash",
+					"height",
+					"total_difficulty",
+					"max_depth",
+					"refused_at",
+				],
+				vec![
+					("hash", hex()),
+					("height", uint()),
+					("total_difficulty", uint()),
+					("max_depth", uint()),
+					("refused_at", int()),
+				],
+			),
+		),
+	]
+}
+
+fn tip_schema() -> (&'static str, Value) {
+	(
+		"Tip",
+		object(
+			&[
+				"height",
+				"last_block_pushed",
+				"prev_block_to_last",
+				"total_difficulty",
+			],
+			vec![
+				("height", uint()),
+				("last_block_pushed", hex()),
+				("prev_block_to_last", hex()),
+				("total_difficulty", uint()),
+			],
+		),
+	)
+}
+
+/// Schemas of the core transaction types, as serialized by serde.
+fn transaction_schemas() -> Vec<(&'static str, Value)> {
+	let output_features = string_enum(&["Plain", "Coinbase"]);
+	let fee = object(&["fee"], vec![("fee", uint())]);
+	let height_locked = object(
+		&["fee", "lock_height"],
+		vec![("fee", uint()), ("lock_height", uint())],
+	);
+	let no_recent_duplicate = object(
+		&["fee", "relative_height"],
+		vec![("fee", uint()), ("relative_height", uint())],
+	);
+	vec![
+		(
+			"Transaction",
+			object(
+				&["offset", "body"],
+				vec![("offset", hex()), ("body", schema_ref("TransactionBody"))],
+			),
+		),
+		(
+			"TransactionBody",
+			object(
+				&["inputs", "outputs", "kernels"],
+				vec![
+					("inputs", array(schema_ref("Input"))),
+					("outputs", array(schema_ref("TxOutput"))),
+					("kernels", array(schema_ref("TxKernel"))),
+				],
+			),
+		),
+		(
+			"Input",
+			object(
+				&["features", "commit"],
+				vec![("features", output_features.clone()), ("commit", hex())],
+			),
+		),
+		(
+			"TxOutput",
+			object(
+				&["features", "commit", "proof"],
+				vec![
+					("features", output_features),
+					("commit", hex()),
+					("proof", hex()),
+				],
+			),
+		),
+		(
+			"KernelFeatures",
+			json!({
+				"oneOf": [
+					object(&["Plain"], vec![("Plain", fee)]),
+					string_enum(&["Coinbase"]),
+					object(&["HeightLocked"], vec![("HeightLocked", height_locked)]),
+					object(
+						&["NoRecentDuplicate"],
+						vec![("NoRecentDuplicate", no_recent_duplicate)],
+					),
+				]
+			}),
+		),
+		(
+			"TxKernel",
+			object(
+				&["features", "excess", "excess_sig"],
+				vec![
+					("features", schema_ref("KernelFeatures")),
+					("excess", hex()),
+					("excess_sig", hex()),
+				],
+			),
+		),
+	]
+}
+
+fn document(
+	title: &str,
+	description: &str,
+	methods: Vec<Value>,
+	schemas: Vec<(&'static str, Value)>,
+) -> Value {
+	let schemas: Map<String, Value> = schemas
+		.into_iter()
+		.map(|(name, schema)| (name.to_owned(), schema))
+		.collect();
+	json!({
+		"openrpc": OPENRPC_VERSION,
+		"info": {
+			"title": title,
+			"description": description,
+			"version": env!("CARGO_PKG_VERSION"),
+		},
+		"methods": methods,
+		"components": {
+			"schemas": schemas,
+		},
+	})
+}
+
+/// Method entry. Results are wrapped into `{"Ok": ...}` as that's how the
+/// JSON-RPC layer serializes the `Result` returned by the API, errors are
+/// reported as JSON-RPC error objects.
+fn method(name: &str, summary: &str, params: Vec<Value>, result: Value) -> Value {
+	json!({
+		"name": name,
+		"summary": summary,
+		"paramStructure": "by-position",
+		"params": params,
+		"result": {
+			"name": format!("{}_result", name),
+			"schema": object(&["Ok"], vec![("Ok", result)]),
+		},
+	})
+}
+
+fn param(name: &str, schema: Value, required: bool) -> Value {
+	let schema = if required { schema } else { nullable(schema) };
+	json!({
+		"name": name,
+		"required": required,
+		"schema": schema,
+	})
+}
+
+fn schema_ref(name: &str) -> Value {
+	json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn object(required: &[&str], properties: Vec<(&str, Value)>) -> Value {
+	let properties: Map<String, Value> = properties
+		.into_iter()
+		.map(|(name, schema)| (name.to_owned(), schema))
+		.collect();
+	json!({
+		"type": "object",
+		"required": required,
+		"properties": properties,
+	})
+}
+
+fn array(items: Value) -> Value {
+	json!({ "type": "array", "items": items })
+}
+
+fn nullable(schema: Value) -> Value {
+	json!({ "oneOf": [schema, null()] })
+}
+
+fn string_enum(values: &[&str]) -> Value {
+	json!({ "type": "string", "enum": values })
+}
+
+fn null() -> Value {
+	json!({ "type": "null" })
+}
+
+fn string() -> Value {
+	json!({ "type": "string" })
+}
+
+fn hex() -> Value {
+	json!({ "type": "string", "pattern": "^[0-9a-fA-F]*$" })
+}
+
+fn date_time() -> Value {
+	json!({ "type": "string", "format": "date-time" })
+}
+
+fn socket_addr() -> Value {
+	json!({ "type": "string", "description": "ip:port" })
+}
+
+fn boolean() -> Value {
+	json!({ "type": "boolean" })
+}
+
+fn int() -> Value {
+	json!({ "type": "integer" })
+}
+
+fn uint() -> Value {
+	json!({ "type": "integer", "minimum": 0 })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Names of the methods declared in the RPC trait of the given source file.
+	fn trait_methods(source: &str, trait_name: &str) -> Vec<String> {
+		let start = source
+			.find(&format!("pub trait {}", trait_name))
+			.expect("trait not found");
+		let end = source[start..]
+			.find("\nimpl ")
+			.map(|i| start + i)
+			.unwrap_or(source.len());
+		source[start..end]
+			.lines()
+			.map(|l| l.trim_start())
+			.filter(|l| l.starts_with("fn "))
+			.map(|l| l[3..].split('(').next().unwrap().to_owned())
+			.collect()
+	}
+
+	fn documented_methods(document: &Value) -> Vec<String> {
+		document["methods"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|m| m["name"].as_str().unwrap().to_owned())
+			.collect()
+	}
+
+	/// Every `$ref` must point to a schema defined in the document.
+	fn check_refs(value: &Value, schemas: &Value) {
+		match value {
+			Value::Object(obj) => {
+				if let Some(r) = obj.get("$ref") {
+					let name = r
+						.as_str()
+						.unwrap()
+						.trim_start_matches("#/components/schemas/");
+					assert!(schemas.get(name).is_some(), "missing schema {}", name);
+				}
+				obj.values().for_each(|v| check_refs(v, schemas));
+			}
+			Value::Array(arr) => arr.iter().for_each(|v| check_refs(v, schemas)),
+			_ => {}
+		}
+	}
+
+	fn check_document(document: Value, source: &str, trait_name: &str) {
+		let expected = trait_methods(source, trait_name);
+		assert!(!expected.is_empty());
+		let documented = documented_methods(&document);
+		for name in &expected {
+			assert!(
+				documented.contains(name),
+				"{}::{} is missing from the OpenRPC document",
+				trait_name,
+				name
+			);
+		}
+		for name in &documented {
+			assert!(
+				expected.contains(name),
+				"{} is documented but not part of {}",
+				name,
+				trait_name
+			);
+		}
+		check_refs(&document, &document["components"]["schemas"]);
+	}
+
+	#[test]
+	fn test_documents_cover_rpc_traits() {
+		check_document(
+			foreign_document(),
+			include_str!("foreign_rpc.rs"),
+			"ForeignRpc",
+		);
+		check_document(owner_document(), include_str!("owner_rpc.rs"), "OwnerRpc");
+		check_document(
+			stratum_document(),
+			include_str!("stratum_rpc.rs"),
+			"StratumRpc",
+		);
+	}
+
+	#[test]
+	fn test_discover_reply() {
+		let req = json!({"jsonrpc": "2.0", "method": "rpc.discover", "id": 7});
+		let reply = discover_reply(&req, owner_document).unwrap();
+		assert_eq!(reply["id"], json!(7));
+		assert_eq!(reply["result"]["openrpc"], json!(OPENRPC_VERSION));
+
+		let req = json!({"jsonrpc": "2.0", "method": "get_status", "id": 7});
+		assert!(discover_reply(&req, owner_document).is_none());
+	}
+}
@@ -15,16 +15,39 @@
 //! Owner API External Definition
 
 use crate::chain::{Chain, SyncState};
-use crate::handlers::chain_api::{ChainCompactHandler, ChainValidationHandler};
+use crate::core::core::hash::Hash;
+use crate::core::global::{self, ChainTypes};
+use crate::handlers::chain_api::{
+	ChainCompactHandler, ChainFullValidationHandler, ChainInvalidationHandler, ChainReorgHandler,
+	ChainRewindHandler, ChainTipsHandler, ChainValidationHandler,
+};
 use crate::handlers::peers_api::{PeerHandler, PeersConnectedHandler};
 use crate::handlers::server_api::StatusHandler;
+use crate::handlers::utils::w;
 use crate::p2p::{self, PeerData};
+use crate::rate_limit::{RateLimitStatus, RateLimiter};
 use crate::rest::*;
-use crate::types::Status;
+use crate::types::{ChainTip, RefusedReorg, Status, Tip, ValidationStatus};
+use chrono::prelude::{TimeZone, Utc};
 use mimble_p2p::types::PeerInfoDisplayLegacy;
 use std::net::SocketAddr;
 use std::sync::Weak;
 
+/// Maximum number of blocks generated by a single `generate_blocks` call.
+pub const MAX_GENERATE_BLOCKS: u64 = 1_000;
+
+/// Builds, mines and applies blocks on demand. Provided by the server on the
+/// testing chain types only, see [`Owner::generate_blocks`](struct.Owner.html#method.generate_blocks).
+pub trait BlockGenerator: Sync + Send {
+	/// Mine `count` blocks on top of the chain head, including the mineable
+	/// pool transactions, and return their hashes.
+	fn generate_blocks(
+		&self,
+		count: u64,
+		reward_destination: Option<String>,
+	) -> Result<Vec<Hash>, Error>;
+}
+
 /// Main interface into all node API functions.
 /// Node APIs are split into two seperate blocks of functionality
 /// called the ['Owner'](struct.Owner.html) and ['Foreign'](struct.Foreign.html) APIs
@@ -36,6 +59,8 @@ pub struct Owner {
 	pub chain: Weak<Chain>,
 	pub peers: Weak<p2p::Peers>,
 	pub sync_state: Weak<SyncState>,
+	pub rate_limiter: Weak<RateLimiter>,
+	pub block_generator: Option<Weak<dyn BlockGenerator>>,
 }
 
 impl Owner {
@@ -47,16 +72,26 @@ impl Owner {
 	/// * `tx_pool` - A non-owning reference of the transaction pool.
 	/// * `peers` - A non-owning reference of the peers.
 	/// * `sync_state` - A non-owning reference of the `sync_state`.
+	/// * `rate_limiter` - A non-owning reference of the foreign API rate limiter.
+	/// * `block_generator` - A non-owning reference of the block generator, testing chains only.
 	///
 	/// # Returns
 	/// * An instance of the Node holding references to the current chain, transaction pool, peers and sync_state.
 	///
 
-	pub fn new(chain: Weak<Chain>, peers: Weak<p2p::Peers>, sync_state: Weak<SyncState>) -> Self {
+	pub fn new(
+		chain: Weak<Chain>,
+		peers: Weak<p2p::Peers>,
+		sync_state: Weak<SyncState>,
+		rate_limiter: Weak<RateLimiter>,
+		block_generator: Option<Weak<dyn BlockGenerator>>,
+	) -> Self {
 		Owner {
 			chain,
 			peers,
 			sync_state,
+			rate_limiter,
+			block_generator,
 		}
 	}
 
@@ -93,6 +128,55 @@ impl Owner {
 		chain_validation_handler.validate_chain()
 	}
 
+	/// Start a full validation of the chain state (including all rangeproofs
+	/// and kernel signatures) as a background job. A validation interrupted by
+	/// a cancellation or a restart resumes from its last checkpoint.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `Ok(())` if the validation was started
+	/// * or [`Error`](struct.Error.html) if an error is encountered (including
+	/// a validation already running).
+	///
+
+	pub fn start_chain_validation(&self) -> Result<(), Error> {
+		let full_validation_handler = ChainFullValidationHandler {
+			chain: self.chain.clone(),
+		};
+		full_validation_handler.start_validation()
+	}
+
+	/// Retrieves the progress of the full chain validation.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`ValidationStatus`](types/struct.ValidationStatus.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_chain_validation_status(&self) -> Result<ValidationStatus, Error> {
+		let full_validation_handler = ChainFullValidationHandler {
+			chain: self.chain.clone(),
+		};
+		full_validation_handler.get_validation_status()
+	}
+
+	/// Stop the running full chain validation, it can be resumed later from
+	/// its last checkpoint.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `true` if a validation was running
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn cancel_chain_validation(&self) -> Result<bool, Error> {
+		let full_validation_handler = ChainFullValidationHandler {
+			chain: self.chain.clone(),
+		};
+		full_validation_handler.cancel_validation()
+	}
+
 	/// Trigger a compaction of the chain state to regain storage space.
 	///
 	/// # Returns
@@ -108,6 +192,120 @@ impl Owner {
 		chain_compact_handler.compact_chain()
 	}
 
+	/// Marks a block as invalid. The block and its descendants are rejected from
+	/// now on, if the block is on the current chain the node rewinds to its parent
+	/// and switches to the valid fork with the most work.
+	///
+	/// # Arguments
+	/// * `hash` - the hash of the block to invalidate.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `Ok(())` if the block was invalidated successfully
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn invalidate_block(&self, hash: String) -> Result<(), Error> {
+		let invalidation_handler = ChainInvalidationHandler {
+			chain: self.chain.clone(),
+		};
+		invalidation_handler.invalidate_block(&hash)
+	}
+
+	/// Removes the invalid mark of a block previously invalidated, the node
+	/// switches back to its chain if it has the most work.
+	///
+	/// # Arguments
+	/// * `hash` - the hash of the invalidated block.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `Ok(())` if the block was reconsidered successfully
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn reconsider_block(&self, hash: String) -> Result<(), Error> {
+		let invalidation_handler = ChainInvalidationHandler {
+			chain: self.chain.clone(),
+		};
+		invalidation_handler.reconsider_block(&hash)
+	}
+
+	/// Retrieves all the known chain tips, the headers without any known child.
+	/// Besides the active one, each tip is the head of a fork.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A vector of [`ChainTip`](types/struct.ChainTip.html), highest first
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_chain_tips(&self) -> Result<Vec<ChainTip>, Error> {
+		let chain_tips_handler = ChainTipsHandler {
+			chain: self.chain.clone(),
+		};
+		chain_tips_handler.get_chain_tips()
+	}
+
+	/// Retrieves the forks recently refused for being deeper than the
+	/// configured `max_reorg_depth`, most recent last.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A vector of [`RefusedReorg`](types/struct.RefusedReorg.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_refused_reorgs(&self) -> Result<Vec<RefusedReorg>, Error> {
+		let reorg_handler = ChainReorgHandler {
+			chain: self.chain.clone(),
+		};
+		reorg_handler.refused_reorgs()
+	}
+
+	/// Accepts a fork regardless of the configured `max_reorg_depth`. The block
+	/// and its descendants are no longer refused, the node switches to the fork
+	/// once it is received again. Peers banned for sending it can be unbanned
+	/// with [`unban_peer`](struct.Owner.html#method.unban_peer).
+	///
+	/// # Arguments
+	/// * `hash` - the hash of the refused block, as reported by
+	/// [`get_refused_reorgs`](struct.Owner.html#method.get_refused_reorgs).
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `Ok(())` if the fork was accepted successfully
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn accept_reorg(&self, hash: String) -> Result<(), Error> {
+		let reorg_handler = ChainReorgHandler {
+			chain: self.chain.clone(),
+		};
+		reorg_handler.accept_reorg(&hash)
+	}
+
+	/// Rewinds the chain state, head and header head to the block at the given
+	/// height. An archive node can rewind to any height, a pruned node only down
+	/// to the cut-through horizon. The removed blocks are kept in the db as
+	/// fork blocks and the node resumes its normal sync.
+	///
+	/// # Arguments
+	/// * `height` - the height of the block to rewind to.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`Tip`](types/struct.Tip.html) of the new chain head
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn rewind_chain(&self, height: u64) -> Result<Tip, Error> {
+		let rewind_handler = ChainRewindHandler {
+			chain: self.chain.clone(),
+		};
+		rewind_handler.rewind_chain(height)
+	}
+
 	/// Retrieves information about stored peers.
 	/// If `None` is provided, will list all stored peers.
 	///
@@ -177,4 +375,93 @@ impl Owner {
 		};
 		peer_handler.unban_peer(addr)
 	}
+
+	/// Returns the state of the foreign API rate limiter, with the clients
+	/// being tracked and how often they were throttled.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`RateLimitStatus`](struct.RateLimitStatus.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_rate_limits(&self) -> Result<RateLimitStatus, Error> {
+		Ok(w(&self.rate_limiter)?.status())
+	}
+
+	/// Mines blocks on top of the chain head with the test proof of work and
+	/// applies them synchronously, including the mineable pool transactions.
+	/// Only available on the AutomatedTesting and UserTesting chain types.
+	///
+	/// # Arguments
+	/// * `count` - the number of blocks to generate, at most
+	/// [`MAX_GENERATE_BLOCKS`](constant.MAX_GENERATE_BLOCKS.html).
+	/// * `reward_destination` - the wallet listener URL to get the coinbase
+	/// from, if `None` the coinbase goes to a burn key.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A vector of the hashes of the generated blocks
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn generate_blocks(
+		&self,
+		count: u64,
+		reward_destination: Option<String>,
+	) -> Result<Vec<String>, Error> {
+		check_testing_chain("generate_blocks")?;
+		if count > MAX_GENERATE_BLOCKS {
+			return Err(ErrorKind::Argument(format!(
+				"cannot generate more than {} blocks at once",
+				MAX_GENERATE_BLOCKS
+			))
+			.into());
+		}
+		let block_generator = self
+			.block_generator
+			.as_ref()
+			.ok_or_else(|| ErrorKind::Internal("block generator is not available".to_owned()))?;
+		let hashes = w(block_generator)?.generate_blocks(count, reward_destination)?;
+		Ok(hashes.iter().map(|h| h.to_hex()).collect())
+	}
+
+	/// Sets the node clock to a fixed mock time, used instead of the system
+	/// clock by the block timestamps and future time limit, the Dandelion
+	/// epochs, the pool reorg cache expiry and the peer ban, expiry and stuck
+	/// detection times.
+	/// Only available on the AutomatedTesting and UserTesting chain types.
+	///
+	/// # Arguments
+	/// * `timestamp` - the mock time in seconds since the unix epoch, if
+	/// `None` the node goes back to the system clock.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `Ok(())` if the clock was set
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn set_mock_time(&self, timestamp: Option<i64>) -> Result<(), Error> {
+		check_testing_chain("set_mock_time")?;
+		let time = match timestamp {
+			Some(ts) => Some(
+				Utc.timestamp_opt(ts, 0)
+					.single()
+					.ok_or_else(|| ErrorKind::Argument(format!("invalid timestamp {}", ts)))?,
+			),
+			None => None,
+		};
+		global::set_mock_time(time);
+		Ok(())
+	}
+}
+
+fn check_testing_chain(method: &str) -> Result<(), Error> {
+	match global::get_chain_type() {
+		ChainTypes::AutomatedTesting | ChainTypes::UserTesting => Ok(()),
+		_ => Err(
+			ErrorKind::Forbidden(format!("{} is only available on testing chains", method)).into(),
+		),
+	}
 }
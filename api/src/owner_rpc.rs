@@ -16,8 +16,9 @@
 
 use crate::owner::Owner;
 use crate::p2p::PeerData;
+use crate::rate_limit::RateLimitStatus;
 use crate::rest::ErrorKind;
-use crate::types::Status;
+use crate::types::{ChainTip, RefusedReorg, Status, Tip, ValidationStatus};
 use mimble_p2p::types::PeerInfoDisplayLegacy;
 use std::net::SocketAddr;
 
@@ -25,6 +26,10 @@ use std::net::SocketAddr;
 /// * When running `mimble` with defaults, the V2 api is available at
 /// `localhost:3413/v2/owner`
 /// * The endpoint only supports POST operations, with the json-rpc request as the body
+/// * Failures are returned as json-rpc error objects, with a stable `code` (see
+/// [`ErrorKind::code`](enum.ErrorKind.html#method.code)) and the error details as `data`
+/// * JSON-RPC batches are accepted, up to `api_max_batch_size` calls per request
+/// * The `rpc.discover` method returns the OpenRPC document of the endpoint
 #[easy_jsonrpc_mw::rpc]
 pub trait OwnerRpc: Sync + Send {
 	/**
@@ -62,7 +67,9 @@ pub trait OwnerRpc: Sync + Send {
 			"sync_info": {
 				"current_height": 371553,
 				"highest_height": 0
-			}
+			},
+			"active_rules": [],
+			"pending_rules": []
 			}
 		}
 	}
@@ -102,6 +109,103 @@ pub trait OwnerRpc: Sync + Send {
 	 */
 	fn validate_chain(&self) -> Result<(), ErrorKind>;
 
+	/**
+	Networked version of [Owner::start_chain_validation](struct.Node.html#method.start_chain_validation).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "start_chain_validation",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn start_chain_validation(&self) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::get_chain_validation_status](struct.Node.html#method.get_chain_validation_status).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_chain_validation_status",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"phase": "rangeproofs",
+				"height": 374336,
+				"done": 180000,
+				"total": 412876,
+				"resumed": false,
+				"error": null
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_chain_validation_status(&self) -> Result<ValidationStatus, ErrorKind>;
+
+	/**
+	Networked version of [Owner::cancel_chain_validation](struct.Node.html#method.cancel_chain_validation).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "cancel_chain_validation",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": true
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn cancel_chain_validation(&self) -> Result<bool, ErrorKind>;
+
 	/**
 	Networked version of [Owner::compact_chain](struct.Node.html#method.compact_chain).
 
@@ -132,6 +236,214 @@ pub trait OwnerRpc: Sync + Send {
 	 */
 	fn compact_chain(&self) -> Result<(), ErrorKind>;
 
+	/**
+	Networked version of [Owner::invalidate_block](struct.Node.html#method.invalidate_block).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "invalidate_block",
+		"params": ["0001d1ac4bda3bd2d6a1f5ac4b0d7e3b87d5bde0bc4d2b7ae1d0c8e2a9b7a3f1"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn invalidate_block(&self, hash: String) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::reconsider_block](struct.Node.html#method.reconsider_block).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "reconsider_block",
+		"params": ["0001d1ac4bda3bd2d6a1f5ac4b0d7e3b87d5bde0bc4d2b7ae1d0c8e2a9b7a3f1"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn reconsider_block(&self, hash: String) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::get_chain_tips](struct.Node.html#method.get_chain_tips).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_chain_tips",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": [
+				{
+					"height": 374336,
+					"hash": "0a2b5d0e8c6f3b6b1c1e2f7d3a7d0b4c2e9f1a6b5c4d3e2f1a0b9c8d7e6f5a4b",
+					"total_difficulty": 1133438031814,
+					"branch_length": 0,
+					"status": "active"
+				},
+				{
+					"height": 374102,
+					"hash": "00055a3b8d6a9e4f7c2b1d0e3f6a9c8b7d4e1f2a3b6c5d8e9f0a1b2c3d4e5f6a",
+					"total_difficulty": 1132691238113,
+					"branch_length": 1,
+					"status": "valid-fork"
+				}
+			]
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_chain_tips(&self) -> Result<Vec<ChainTip>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::get_refused_reorgs](struct.Node.html#method.get_refused_reorgs).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_refused_reorgs",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": [
+				{
+					"hash": "0a2b5d0e8c6f3b6b1c1e2f7d3a7d0b4c2e9f1a6b5c4d3e2f1a0b9c8d7e6f5a4b",
+					"height": 374336,
+					"total_difficulty": 1133438031814,
+					"max_depth": 100,
+					"refused_at": 1602870123
+				}
+			]
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_refused_reorgs(&self) -> Result<Vec<RefusedReorg>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::accept_reorg](struct.Node.html#method.accept_reorg).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "accept_reorg",
+		"params": ["0a2b5d0e8c6f3b6b1c1e2f7d3a7d0b4c2e9f1a6b5c4d3e2f1a0b9c8d7e6f5a4b"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn accept_reorg(&self, hash: String) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::rewind_chain](struct.Node.html#method.rewind_chain).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "rewind_chain",
+		"params": [374300],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"height": 374300,
+				"last_block_pushed": "000000543c69a0306b5463b92939643442a44a6d9be5bef72bea9fc1d718d310",
+				"prev_block_to_last": "000001237c6bac162f1add2b122fab6a254b9fcc2c4b4c8c632a8c39855521f1",
+				"total_difficulty": 1133299831842114
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn rewind_chain(&self, height: u64) -> Result<Tip, ErrorKind>;
+
 	/**
 	Networked version of [Owner::get_peers](struct.Node.html#method.get_peers).
 
@@ -352,6 +664,123 @@ pub trait OwnerRpc: Sync + Send {
 	```
 	 */
 	fn unban_peer(&self, peer_addr: SocketAddr) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::get_rate_limits](struct.Node.html#method.get_rate_limits).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_rate_limits",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"enabled": true,
+				"capacity": 100,
+				"refill_per_sec": 10,
+				"clients": [
+					{
+						"key": "70.50.33.130",
+						"tokens": 3,
+						"accepted": 412,
+						"throttled": 27,
+						"last_throttled": 1602870123
+					},
+					{
+						"key": "token:explorer",
+						"tokens": 100,
+						"accepted": 35,
+						"throttled": 0,
+						"last_throttled": 0
+					}
+				]
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_rate_limits(&self) -> Result<RateLimitStatus, ErrorKind>;
+
+	/**
+	Networked version of [Owner::generate_blocks](struct.Node.html#method.generate_blocks).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "generate_blocks",
+		"params": [2, "http://127.0.0.1:23415"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": [
+				"0004a0e2b5c1e71a5e4f0bd5ab0c3c73e7d1cfa3c2b0ffb9f36bcc1ce53ed7fc",
+				"0001f3c2d7ab1cbd4b3f7a1a06f0a93ae2e0bd4b11d81c6ec64b0a5f4a8ef2d1"
+			]
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn generate_blocks(
+		&self,
+		count: u64,
+		reward_destination: Option<String>,
+	) -> Result<Vec<String>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::set_mock_time](struct.Node.html#method.set_mock_time).
+
+	# Json rpc example
+
+	```
+	# mimble_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "set_mock_time",
+		"params": [1602870123],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn set_mock_time(&self, timestamp: Option<i64>) -> Result<(), ErrorKind>;
 }
 
 impl OwnerRpc for Owner {
@@ -363,10 +792,46 @@ impl OwnerRpc for Owner {
 		Owner::validate_chain(self).map_err(|e| e.kind().clone())
 	}
 
+	fn start_chain_validation(&self) -> Result<(), ErrorKind> {
+		Owner::start_chain_validation(self).map_err(|e| e.kind().clone())
+	}
+
+	fn get_chain_validation_status(&self) -> Result<ValidationStatus, ErrorKind> {
+		Owner::get_chain_validation_status(self).map_err(|e| e.kind().clone())
+	}
+
+	fn cancel_chain_validation(&self) -> Result<bool, ErrorKind> {
+		Owner::cancel_chain_validation(self).map_err(|e| e.kind().clone())
+	}
+
 	fn compact_chain(&self) -> Result<(), ErrorKind> {
 		Owner::compact_chain(self).map_err(|e| e.kind().clone())
 	}
 
+	fn invalidate_block(&self, hash: String) -> Result<(), ErrorKind> {
+		Owner::invalidate_block(self, hash).map_err(|e| e.kind().clone())
+	}
+
+	fn reconsider_block(&self, hash: String) -> Result<(), ErrorKind> {
+		Owner::reconsider_block(self, hash).map_err(|e| e.kind().clone())
+	}
+
+	fn get_chain_tips(&self) -> Result<Vec<ChainTip>, ErrorKind> {
+		Owner::get_chain_tips(self).map_err(|e| e.kind().clone())
+	}
+
+	fn get_refused_reorgs(&self) -> Result<Vec<RefusedReorg>, ErrorKind> {
+		Owner::get_refused_reorgs(self).map_err(|e| e.kind().clone())
+	}
+
+	fn accept_reorg(&self, hash: String) -> Result<(), ErrorKind> {
+		Owner::accept_reorg(self, hash).map_err(|e| e.kind().clone())
+	}
+
+	fn rewind_chain(&self, height: u64) -> Result<Tip, ErrorKind> {
+		Owner::rewind_chain(self, height).map_err(|e| e.kind().clone())
+	}
+
 	fn get_peers(&self, addr: Option<SocketAddr>) -> Result<Vec<PeerData>, ErrorKind> {
 		Owner::get_peers(self, addr).map_err(|e| e.kind().clone())
 	}
@@ -382,6 +847,22 @@ impl OwnerRpc for Owner {
 	fn unban_peer(&self, addr: SocketAddr) -> Result<(), ErrorKind> {
 		Owner::unban_peer(self, addr).map_err(|e| e.kind().clone())
 	}
+
+	fn get_rate_limits(&self) -> Result<RateLimitStatus, ErrorKind> {
+		Owner::get_rate_limits(self).map_err(|e| e.kind().clone())
+	}
+
+	fn generate_blocks(
+		&self,
+		count: u64,
+		reward_destination: Option<String>,
+	) -> Result<Vec<String>, ErrorKind> {
+		Owner::generate_blocks(self, count, reward_destination).map_err(|e| e.kind().clone())
+	}
+
+	fn set_mock_time(&self, timestamp: Option<i64>) -> Result<(), ErrorKind> {
+		Owner::set_mock_time(self, timestamp).map_err(|e| e.kind().clone())
+	}
 }
 
 #[doc(hidden)]
@@ -0,0 +1,407 @@
+// Copyright 2020 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Token bucket rate limiting of the API clients. Every client (scoped API
+//! token if authenticated with one, remote IP otherwise) has a bucket refilled
+//! at a constant rate, every call takes its cost out of the bucket.
+
+use crate::auth::{path_matches, TokenName};
+use crate::rest::ErrorKind;
+use crate::router::{Handler, HandlerObj, RemoteAddr, ResponseFuture};
+use crate::util::Mutex;
+use crate::web::response;
+use futures::future::ok;
+use hyper::header::{CONTENT_TYPE, RETRY_AFTER};
+use hyper::{body, Body, Method, Request, Response, StatusCode};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Default number of tokens in a full bucket (burst size).
+pub const DEFAULT_RATE_LIMIT_CAPACITY: u64 = 100;
+/// Default number of tokens added to the buckets every second.
+pub const DEFAULT_RATE_LIMIT_REFILL: u64 = 10;
+/// Default number of outputs requested per token of cost.
+pub const DEFAULT_OUTPUTS_PER_TOKEN: u64 = 100;
+
+/// Clients tracked before the idle (full) or least recently used buckets get
+/// dropped.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Rate limiting configuration of the foreign API.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+	/// Size of the bucket of every client, i.e. the cost allowed in a burst
+	pub capacity: u64,
+	/// Tokens added to the bucket every second
+	pub refill_per_sec: u64,
+	/// Cost of the methods, 1 if not listed
+	#[serde(default)]
+	pub method_costs: HashMap<String, u64>,
+	/// Outputs requested by `get_unspent_outputs` per additional token of cost
+	#[serde(default = "default_outputs_per_token")]
+	pub outputs_per_token: u64,
+}
+
+fn default_outputs_per_token() -> u64 {
+	DEFAULT_OUTPUTS_PER_TOKEN
+}
+
+impl Default for RateLimitConfig {
+	fn default() -> RateLimitConfig {
+		RateLimitConfig {
+			capacity: DEFAULT_RATE_LIMIT_CAPACITY,
+			refill_per_sec: DEFAULT_RATE_LIMIT_REFILL,
+			method_costs: HashMap::new(),
+			outputs_per_token: DEFAULT_OUTPUTS_PER_TOKEN,
+		}
+	}
+}
+
+impl RateLimitConfig {
+	/// Cost of a single JSON-RPC call.
+	pub fn call_cost(&self, call: &Value) -> u64 {
+		let method = call.get("method").and_then(|m| m.as_str()).unwrap_or("");
+		let mut cost = self.method_costs.get(method).cloned().unwrap_or(1);
+		if method == "get_unspent_outputs" {
+			// Full scans are requested with a large max, 3rd param
+			let params = call.get("params");
+			let max = params
+				.and_then(|p| p.get(2).or_else(|| p.get("max")))
+				.and_then(|m| m.as_u64())
+				.unwrap_or(0);
+			cost = cost.saturating_add(max / self.outputs_per_token.max(1));
+		}
+		cost
+	}
+
+	/// Cost of a request body, single call or batch.
+	pub fn body_cost(&self, body: &[u8]) -> u64 {
+		match serde_json::from_slice::<Value>(body) {
+			Ok(Value::Array(calls)) => calls
+				.iter()
+				.fold(0u64, |acc, c| acc.saturating_add(self.call_cost(c)))
+				.max(1),
+			Ok(call) => self.call_cost(&call),
+			Err(_) => 1,
+		}
+	}
+}
+
+/// Rate limiting state of a client.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitClient {
+	/// Scoped API token (`token:<name>`) or remote IP of the client
+	pub key: String,
+	/// Tokens currently available in the bucket, 0 while paying off a call
+	/// costing more than the capacity
+	pub tokens: u64,
+	/// Requests accepted
+	pub accepted: u64,
+	/// Requests rejected because the bucket was empty
+	pub throttled: u64,
+	/// Time of the last rejected request (seconds since epoch), 0 if none
+	pub last_throttled: i64,
+}
+
+/// Rate limiting state of the foreign API.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitStatus {
+	/// Whether rate limiting is enabled
+	pub enabled: bool,
+	/// Size of the bucket of every client
+	pub capacity: u64,
+	/// Tokens added to the buckets every second
+	pub refill_per_sec: u64,
+	/// Tracked clients, most throttled first
+	pub clients: Vec<RateLimitClient>,
+}
+
+struct Bucket {
+	tokens: f64,
+	last_refill: Instant,
+	last_used: Instant,
+	accepted: u64,
+	throttled: u64,
+	last_throttled: i64,
+}
+
+impl Bucket {
+	fn refill(&mut self, config: &RateLimitConfig, now: Instant) {
+		let elapsed = now
+			.saturating_duration_since(self.last_refill)
+			.as_secs_f64();
+		self.tokens =
+			(self.tokens + elapsed * config.refill_per_sec as f64).min(config.capacity as f64);
+		self.last_refill = now;
+	}
+
+	fn is_idle(&self, config: &RateLimitConfig) -> bool {
+		self.tokens >= config.capacity as f64
+	}
+}
+
+/// Per client token buckets, disabled if created without config.
+pub struct RateLimiter {
+	config: Option<RateLimitConfig>,
+	buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+	/// Create a rate limiter, `None` disables rate limiting.
+	pub fn new(config: Option<RateLimitConfig>) -> RateLimiter {
+		RateLimiter {
+			config,
+			buckets: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Whether rate limiting is enabled.
+	pub fn is_enabled(&self) -> bool {
+		self.config.is_some()
+	}
+
+	/// Take `cost` tokens from the bucket of the client. If the bucket doesn't
+	/// have enough tokens, returns the time after which it will.
+	pub fn acquire(&self, key: &str, cost: u64) -> Result<(), Duration> {
+		let config = match self.config.as_ref() {
+			Some(config) => config,
+			None => return Ok(()),
+		};
+		let now = Instant::now();
+		let mut buckets = self.buckets.lock();
+		if !buckets.contains_key(key) && buckets.len() >= MAX_TRACKED_CLIENTS {
+			buckets.retain(|_, b| {
+				b.refill(config, now);
+				!b.is_idle(config)
+			});
+			if buckets.len() >= MAX_TRACKED_CLIENTS {
+				let lru = buckets
+					.iter()
+					.min_by_key(|(_, b)| b.last_used)
+					.map(|(k, _)| k.clone());
+				if let Some(lru) = lru {
+					buckets.remove(&lru);
+				}
+			}
+		}
+		let bucket = buckets.entry(key.to_owned()).or_insert_with(|| Bucket {
+			tokens: config.capacity as f64,
+			last_refill: now,
+			last_used: now,
+			accepted: 0,
+			throttled: 0,
+			last_throttled: 0,
+		});
+		bucket.refill(config, now);
+		bucket.last_used = now;
+
+		// A call costing more than the capacity can be made with a full bucket,
+		// the balance then goes negative and the client waits for the excess too
+		let cost = cost as f64;
+		let required = cost.min(config.capacity as f64);
+		if bucket.tokens >= required {
+			bucket.tokens -= cost;
+			bucket.accepted += 1;
+			return Ok(());
+		}
+		bucket.throttled += 1;
+		bucket.last_throttled = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|d| d.as_secs() as i64)
+			.unwrap_or(0);
+		let missing = required - bucket.tokens;
+		Err(Duration::from_secs_f64(
+			missing / (config.refill_per_sec.max(1) as f64),
+		))
+	}
+
+	/// Current state of the limiter and of the tracked clients.
+	pub fn status(&self) -> RateLimitStatus {
+		let config = match self.config.as_ref() {
+			Some(config) => config,
+			None => {
+				return RateLimitStatus {
+					enabled: false,
+					capacity: 0,
+					refill_per_sec: 0,
+					clients: vec![],
+				}
+			}
+		};
+		let now = Instant::now();
+		let mut buckets = self.buckets.lock();
+		let mut clients: Vec<RateLimitClient> = buckets
+			.iter_mut()
+			.map(|(key, b)| {
+				b.refill(config, now);
+				RateLimitClient {
+					key: key.clone(),
+					tokens: b.tokens as u64,
+					accepted: b.accepted,
+					throttled: b.throttled,
+					last_throttled: b.last_throttled,
+				}
+			})
+			.collect();
+		clients.sort_by(|a, b| b.throttled.cmp(&a.throttled).then(a.key.cmp(&b.key)));
+		RateLimitStatus {
+			enabled: true,
+			capacity: config.capacity,
+			refill_per_sec: config.refill_per_sec,
+			clients,
+		}
+	}
+}
+
+/// Rate limits the requests to the target uri (and its sub-paths), must be
+/// added after the auth middlewares to key the clients by API token.
+pub struct RateLimitMiddleware {
+	limiter: Arc<RateLimiter>,
+	target_uri: String,
+}
+
+impl RateLimitMiddleware {
+	pub fn new(limiter: Arc<RateLimiter>, target_uri: String) -> RateLimitMiddleware {
+		RateLimitMiddleware {
+			limiter,
+			target_uri,
+		}
+	}
+}
+
+impl Handler for RateLimitMiddleware {
+	fn call(
+		&self,
+		req: Request<Body>,
+		mut handlers: Box<dyn Iterator<Item = HandlerObj>>,
+	) -> ResponseFuture {
+		let next_handler = match handlers.next() {
+			Some(h) => h,
+			None => return response(StatusCode::INTERNAL_SERVER_ERROR, "no handler found"),
+		};
+		if !path_matches(req.uri().path(), &self.target_uri)
+			|| req.method() == Method::OPTIONS
+			|| !self.limiter.is_enabled()
+		{
+			return next_handler.call(req, handlers);
+		}
+
+		let key = match (
+			req.extensions().get::<TokenName>(),
+			req.extensions().get::<RemoteAddr>(),
+		) {
+			(Some(token), _) => format!("token:{}", token.0),
+			(None, Some(addr)) => addr.0.ip().to_string(),
+			(None, None) => "unknown".to_owned(),
+		};
+
+		if req.method() != Method::POST {
+			return match self.limiter.acquire(&key, 1) {
+				Ok(()) => next_handler.call(req, handlers),
+				Err(retry_after) => too_many_requests(&key, Value::Null, retry_after),
+			};
+		}
+
+		// The body has to be read to know the cost of the calls
+		let limiter = self.limiter.clone();
+		let handlers: Vec<HandlerObj> = handlers.collect();
+		Box::pin(async move {
+			let (parts, req_body) = req.into_parts();
+			let bytes = body::to_bytes(req_body).await?;
+			let cost = limiter.config.as_ref().map_or(1, |c| c.body_cost(&bytes));
+			if let Err(retry_after) = limiter.acquire(&key, cost) {
+				let id = serde_json::from_slice::<Value>(&bytes)
+					.ok()
+					.and_then(|v| v.get("id").cloned())
+					.unwrap_or(Value::Null);
+				return too_many_requests(&key, id, retry_after).await;
+			}
+			let req = Request::from_parts(parts, Body::from(bytes));
+			next_handler.call(req, Box::new(handlers.into_iter())).await
+		})
+	}
+}
+
+/// 429 response, with the JSON-RPC error as body.
+fn too_many_requests(key: &str, id: Value, retry_after: Duration) -> ResponseFuture {
+	let retry_after = retry_after.as_secs() + 1;
+	debug!(
+		"api: rate limit exceeded by {}, retry in {}s",
+		key, retry_after
+	);
+	let error = ErrorKind::TooManyRequests(format!("retry in {}s", retry_after));
+	let body = json!({
+		"id": id,
+		"jsonrpc": "2.0",
+		"error": error.to_rpc_error(),
+	});
+	let res = Response::builder()
+		.status(StatusCode::TOO_MANY_REQUESTS)
+		.header(CONTENT_TYPE, "application/json")
+		.header(RETRY_AFTER, retry_after)
+		.body(body.to_string().into())
+		.unwrap();
+	Box::pin(ok(res))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_bucket_and_costs() {
+		let mut config = RateLimitConfig {
+			capacity: 10,
+			refill_per_sec: 1,
+			..RateLimitConfig::default()
+		};
+		config.method_costs.insert("push_transaction".to_owned(), 4);
+
+		let scan = br#"{"jsonrpc":"2.0","method":"get_unspent_outputs","params":[1,null,500,true],"id":1}"#;
+		assert_eq!(config.body_cost(scan), 6);
+		let named =
+			json!({"method": "get_unspent_outputs", "params": {"start_index": 1, "max": 1000}});
+		assert_eq!(config.call_cost(&named), 11);
+		let batch = br#"[{"method":"get_tip"},{"method":"push_transaction"}]"#;
+		assert_eq!(config.body_cost(batch), 5);
+		assert_eq!(config.body_cost(b"not json"), 1);
+
+		let limiter = RateLimiter::new(Some(config));
+		assert!(limiter.acquire("1.2.3.4", 6).is_ok());
+		assert!(limiter.acquire("1.2.3.4", 4).is_ok());
+		let retry = limiter.acquire("1.2.3.4", 2).unwrap_err();
+		assert!(retry > Duration::from_millis(1000) && retry <= Duration::from_secs(2));
+		// Other clients have their own bucket
+		assert!(limiter.acquire("token:wallet", 10).is_ok());
+
+		let status = limiter.status();
+		assert!(status.enabled);
+		assert_eq!(status.clients.len(), 2);
+		assert_eq!(status.clients[0].key, "1.2.3.4");
+		assert_eq!(status.clients[0].accepted, 2);
+		assert_eq!(status.clients[0].throttled, 1);
+		assert!(status.clients[0].last_throttled > 0);
+
+		// A call over the capacity empties a full bucket and then some
+		assert!(limiter.acquire("5.6.7.8", 25).is_ok());
+		let retry = limiter.acquire("5.6.7.8", 1).unwrap_err();
+		assert!(retry > Duration::from_secs(15) && retry <= Duration::from_secs(16));
+
+		let disabled = RateLimiter::new(None);
+		assert!(disabled.acquire("1.2.3.4", 1000).is_ok());
+		assert!(!disabled.status().enabled);
+	}
+}
@@ -18,13 +18,17 @@
 //! To use it, just have your service(s) implement the ApiEndpoint trait and
 //! register them on a ApiServer.
 
+use crate::chain;
 use crate::p2p::Error as P2pError;
+use crate::pool::PoolError;
 use crate::router::{Handler, HandlerObj, ResponseFuture, Router, RouterError};
+use crate::types::{ChainErrorInfo, PoolErrorInfo};
 use crate::web::response;
 use failure::{Backtrace, Context, Fail};
 use futures::channel::oneshot;
 use futures::TryStreamExt;
 use hyper::server::accept;
+use hyper::server::conn::AddrStream;
 use hyper::service::make_service_fn;
 use hyper::{Body, Request, Server, StatusCode};
 use rustls;
@@ -36,8 +40,9 @@ use std::fs::File;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::{io, thread};
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, TcpStream};
 use tokio::runtime::Runtime;
+use tokio_rustls::server::TlsStream;
 use tokio_rustls::TlsAcceptor;
 
 /// Errors that can be returned by an ApiEndpoint implementation.
@@ -62,6 +67,56 @@ pub enum ErrorKind {
 	Router(RouterError),
 	#[fail(display = "API P2P error: {}", _0)]
 	P2pError(String),
+	#[fail(display = "API Pool error: {}", _0)]
+	Pool(PoolErrorInfo),
+	#[fail(display = "API Chain error: {}", _0)]
+	Chain(ChainErrorInfo),
+	#[fail(display = "API Forbidden: {}", _0)]
+	Forbidden(String),
+	#[fail(display = "API Too many requests: {}", _0)]
+	TooManyRequests(String),
+}
+
+impl ErrorKind {
+	/// Stable error code, returned as the JSON-RPC error code.
+	/// API errors use the -32000 range (reserved for implementation defined
+	/// server errors), pool errors the 1000 range and chain errors the 2000
+	/// range.
+	pub fn code(&self) -> i64 {
+		match self {
+			ErrorKind::Internal(_) => -32603,
+			ErrorKind::Argument(_) => -32602,
+			ErrorKind::NotFound(_) => -32001,
+			ErrorKind::RequestError(_) => -32002,
+			ErrorKind::ResponseError(_) => -32003,
+			ErrorKind::Router(_) => -32004,
+			ErrorKind::P2pError(_) => -32005,
+			ErrorKind::Pool(info) => info.code,
+			ErrorKind::Chain(info) => info.code,
+			ErrorKind::Forbidden(_) => -32006,
+			ErrorKind::TooManyRequests(_) => -32007,
+		}
+	}
+
+	/// Machine readable details of the error, returned as the JSON-RPC
+	/// error data.
+	pub fn data(&self) -> serde_json::Value {
+		let data = match self {
+			ErrorKind::Pool(info) => serde_json::to_value(info),
+			ErrorKind::Chain(info) => serde_json::to_value(info),
+			_ => serde_json::to_value(self),
+		};
+		data.unwrap_or(serde_json::Value::Null)
+	}
+
+	/// JSON-RPC error object for this error.
+	pub fn to_rpc_error(&self) -> serde_json::Value {
+		serde_json::json!({
+			"code": self.code(),
+			"message": self.to_string(),
+			"data": self.data(),
+		})
+	}
 }
 
 impl Fail for Error {
@@ -116,6 +171,22 @@ impl From<P2pError> for Error {
 	}
 }
 
+impl From<PoolError> for Error {
+	fn from(error: PoolError) -> Error {
+		Error {
+			inner: Context::new(ErrorKind::Pool(PoolErrorInfo::from_error(&error))),
+		}
+	}
+}
+
+impl From<chain::Error> for Error {
+	fn from(error: chain::Error) -> Error {
+		Error {
+			inner: Context::new(ErrorKind::Chain(ChainErrorInfo::from_error(&error))),
+		}
+	}
+}
+
 /// TLS config
 #[derive(Clone)]
 pub struct TLSConfig {
@@ -220,10 +291,11 @@ impl ApiServer {
 			.name("apis".to_string())
 			.spawn(move || {
 				let server = async move {
-					let server = Server::bind(&addr).serve(make_service_fn(move |_| {
-						let router = router.clone();
-						async move { Ok::<_, Infallible>(router) }
-					}));
+					let server =
+						Server::bind(&addr).serve(make_service_fn(move |conn: &AddrStream| {
+							let router = router.for_connection(Some(conn.remote_addr()));
+							async move { Ok::<_, Infallible>(router) }
+						}));
 					// TODO graceful shutdown is unstable, investigate
 					//.with_graceful_shutdown(rx)
 
@@ -272,8 +344,8 @@ impl ApiServer {
 					let listener = listener.incoming().and_then(move |s| acceptor.accept(s));
 
 					let server = Server::builder(accept::from_stream(listener)).serve(
-						make_service_fn(move |_| {
-							let router = router.clone();
+						make_service_fn(move |conn: &TlsStream<TcpStream>| {
+							let router = router.for_connection(conn.get_ref().0.peer_addr().ok());
 							async move { Ok::<_, Infallible>(router) }
 						}),
 					);
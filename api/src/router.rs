@@ -18,6 +18,7 @@ use hyper::service::Service;
 use hyper::{Body, Method, Request, Response, StatusCode};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
@@ -100,8 +101,14 @@ pub enum RouterError {
 #[derive(Clone)]
 pub struct Router {
 	nodes: Vec<Node>,
+	remote_addr: Option<SocketAddr>,
 }
 
+/// Address of the client, set in the request extensions by the router of the
+/// connection.
+#[derive(Clone, Copy, Debug)]
+pub struct RemoteAddr(pub SocketAddr);
+
 #[derive(Debug, Clone, Copy)]
 struct NodeId(usize);
 
@@ -123,7 +130,18 @@ impl Router {
 		let root = Node::new(calculate_hash(&""), None);
 		let mut nodes = vec![];
 		nodes.push(root);
-		Router { nodes }
+		Router {
+			nodes,
+			remote_addr: None,
+		}
+	}
+
+	/// Router of a single connection, tagging the requests with the address
+	/// of the client.
+	pub fn for_connection(&self, remote_addr: Option<SocketAddr>) -> Router {
+		let mut router = self.clone();
+		router.remote_addr = remote_addr;
+		router
 	}
 
 	pub fn add_middleware(&mut self, mw: HandlerObj) {
@@ -216,7 +234,10 @@ impl Service<Request<Body>> for Router {
 		Poll::Ready(Ok(()))
 	}
 
-	fn call(&mut self, req: Request<Body>) -> Self::Future {
+	fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+		if let Some(addr) = self.remote_addr {
+			req.extensions_mut().insert(RemoteAddr(addr));
+		}
 		match self.get(req.uri().path()) {
 			Err(_) => not_found(),
 			Ok(mut handlers) => match handlers.next() {
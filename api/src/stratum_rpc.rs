@@ -22,6 +22,8 @@ use crate::stratum::Stratum;
 /// * When running `grin` with defaults, the V2 api is available at
 /// `localhost:3413/v2/stratum`
 /// * The endpoint only supports POST operations, with the json-rpc request as the body
+/// * JSON-RPC batches are accepted, up to `api_max_batch_size` calls per request
+/// * The `rpc.discover` method returns the OpenRPC document of the endpoint
 #[easy_jsonrpc_mw::rpc]
 pub trait StratumRpc: Sync + Send {
 	/**
@@ -18,8 +18,9 @@ use crate::chain;
 use crate::core::core::hash::Hashed;
 use crate::core::core::merkle_proof::MerkleProof;
 use crate::core::core::{KernelFeatures, TxKernel};
-use crate::core::{core, ser};
+use crate::core::{core, global, ser};
 use crate::p2p;
+use crate::pool::PoolError;
 use crate::util;
 use crate::util::secp::pedersen;
 use serde;
@@ -68,6 +69,105 @@ impl Tip {
 	}
 }
 
+/// A known chain tip, a header without any known child
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChainTip {
+	/// Height of the tip
+	pub height: u64,
+	/// Hash of the tip
+	pub hash: String,
+	/// Total difficulty of the tip
+	pub total_difficulty: u64,
+	/// Number of blocks since the fork point with the header chain
+	pub branch_length: u64,
+	/// One of "active", "valid-fork", "headers-only" or "invalid"
+	pub status: String,
+}
+
+impl ChainTip {
+	pub fn from_chain_tip(chain_tip: chain::ChainTip) -> ChainTip {
+		let status = match chain_tip.status {
+			chain::ChainTipStatus::Active => "active",
+			chain::ChainTipStatus::ValidFork => "valid-fork",
+			chain::ChainTipStatus::HeadersOnly => "headers-only",
+			chain::ChainTipStatus::Invalid => "invalid",
+		};
+		ChainTip {
+			height: chain_tip.tip.height,
+			hash: util::to_hex(chain_tip.tip.last_block_h.to_vec()),
+			total_difficulty: chain_tip.tip.total_difficulty.to_num(),
+			branch_length: chain_tip.branch_length,
+			status: status.to_owned(),
+		}
+	}
+}
+
+/// A fork refused for being deeper than the maximum reorg depth
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RefusedReorg {
+	/// Hash of the block that would have made the fork our chain
+	pub hash: String,
+	/// Height of that block
+	pub height: u64,
+	/// Total difficulty of the fork
+	pub total_difficulty: u64,
+	/// Maximum reorg depth at the time
+	pub max_depth: u64,
+	/// When the fork was first refused (unix timestamp)
+	pub refused_at: i64,
+}
+
+impl RefusedReorg {
+	pub fn from_refused_reorg(reorg: chain::RefusedReorg) -> RefusedReorg {
+		RefusedReorg {
+			hash: util::to_hex(reorg.hash.to_vec()),
+			height: reorg.height,
+			total_difficulty: reorg.total_difficulty.to_num(),
+			max_depth: reorg.max_depth,
+			refused_at: reorg.refused_at,
+		}
+	}
+}
+
+/// Progress of the full chain validation
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ValidationStatus {
+	/// One of "idle", "sums", "rangeproofs", "kernels", "done", "cancelled" or "failed"
+	pub phase: String,
+	/// Height of the chain head validated against
+	pub height: u64,
+	/// Number of rangeproofs or kernel signatures verified in the current phase
+	pub done: u64,
+	/// Number of rangeproofs or kernel signatures to verify in the current phase
+	pub total: u64,
+	/// Whether the validation resumed from a checkpoint
+	pub resumed: bool,
+	/// Error the validation failed with
+	pub error: Option<String>,
+}
+
+impl ValidationStatus {
+	pub fn from_validation_status(status: chain::ValidationStatus) -> ValidationStatus {
+		let phase = match status.phase {
+			chain::ValidationPhase::Idle => "idle",
+			chain::ValidationPhase::Sums => "sums",
+			chain::ValidationPhase::Rangeproofs => "rangeproofs",
+			chain::ValidationPhase::Kernels => "kernels",
+			chain::ValidationPhase::Done => "done",
+			chain::ValidationPhase::Cancelled => "cancelled",
+			chain::ValidationPhase::Failed => "failed",
+		};
+		ValidationStatus {
+			phase: phase.to_owned(),
+			height: status.height,
+			done: status.done,
+			total: status.total,
+			resumed: status.resumed,
+			error: status.error,
+		}
+	}
+}
+
 /// Status page containing different server information
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Status {
@@ -84,6 +184,12 @@ pub struct Status {
 	// Additional sync information
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub sync_info: Option<serde_json::Value>,
+	// Consensus rules active at the current chain height
+	#[serde(default)]
+	pub active_rules: Vec<ConsensusRule>,
+	// Consensus rules scheduled to activate above the current chain height
+	#[serde(default)]
+	pub pending_rules: Vec<ConsensusRule>,
 }
 
 impl Status {
@@ -93,6 +199,10 @@ impl Status {
 		sync_status: String,
 		sync_info: Option<serde_json::Value>,
 	) -> Status {
+		let (active_rules, pending_rules) = global::consensus_rules()
+			.into_iter()
+			.map(|(rule, height)| ConsensusRule::from_rule(rule, height))
+			.partition(|x| x.activation_height <= current_tip.height);
 		Status {
 			protocol_version: ser::ProtocolVersion::local().into(),
 			user_agent: p2p::msg::USER_AGENT.to_string(),
@@ -100,6 +210,26 @@ impl Status {
 			tip: Tip::from_tip(current_tip),
 			sync_status,
 			sync_info,
+			active_rules,
+			pending_rules,
+		}
+	}
+}
+
+/// A consensus rule (hard-fork) scheduled on this chain
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConsensusRule {
+	/// Name of the rule
+	pub name: String,
+	/// Height from which the rule applies
+	pub activation_height: u64,
+}
+
+impl ConsensusRule {
+	pub fn from_rule(rule: global::ConsensusRule, activation_height: u64) -> ConsensusRule {
+		ConsensusRule {
+			name: rule.name().to_string(),
+			activation_height,
 		}
 	}
 }
@@ -535,6 +665,10 @@ impl TxKernelPrintable {
 			KernelFeatures::Plain { fee } => (fee, 0),
 			KernelFeatures::Coinbase => (0, 0),
 			KernelFeatures::HeightLocked { fee, lock_height } => (fee, lock_height),
+			KernelFeatures::NoRecentDuplicate {
+				fee,
+				relative_height,
+			} => (fee, relative_height.into()),
 		};
 		TxKernelPrintable {
 			features,
@@ -739,6 +873,39 @@ pub struct OutputListing {
 	pub outputs: Vec<OutputPrintable>,
 }
 
+/// Creation and spend details of an output, from the output history index
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutputHistory {
+	/// The output commitment
+	pub commit: String,
+	/// MMR position of the output
+	pub mmr_index: u64,
+	/// Height of the block that created the output
+	pub height: u64,
+	/// Whether the output has been spent
+	pub spent: bool,
+	/// Height of the block that spent the output
+	pub spent_height: Option<u64>,
+	/// Hash of the block that spent the output
+	pub spent_block_hash: Option<String>,
+}
+
+impl OutputHistory {
+	pub fn from_history(
+		commit: &pedersen::Commitment,
+		history: &chain::OutputHistory,
+	) -> OutputHistory {
+		OutputHistory {
+			commit: util::to_hex(commit.0.to_vec()),
+			mmr_index: history.pos,
+			height: history.height,
+			spent: history.is_spent(),
+			spent_height: history.spent_height,
+			spent_block_hash: history.spent_block.map(|h| h.to_hex()),
+		}
+	}
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LocatedTxKernel {
 	pub tx_kernel: TxKernel,
@@ -752,6 +919,167 @@ pub struct PoolInfo {
 	pub pool_size: usize,
 }
 
+/// Details of a pool error, with the commitment or fee at fault if any.
+/// The code is stable and returned as the JSON-RPC error code.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PoolErrorInfo {
+	/// Stable error code (1000 range)
+	pub code: i64,
+	/// Pool error variant, e.g. `InputNotFound`
+	pub kind: String,
+	/// Error message
+	pub message: String,
+	/// Missing input, duplicate output or duplicate kernel excess
+	pub commitment: Option<String>,
+	/// Minimum fee the transaction needs to pay
+	pub min_fee: Option<u64>,
+}
+
+impl PoolErrorInfo {
+	pub fn from_error(e: &PoolError) -> PoolErrorInfo {
+		let (code, kind, commitment, min_fee) = match e {
+			PoolError::InvalidTx(_) => (1001, "InvalidTx", None, None),
+			PoolError::InvalidBlock(_) => (1002, "InvalidBlock", None, None),
+			PoolError::Keychain(_) => (1003, "Keychain", None, None),
+			PoolError::Committed(_) => (1004, "Committed", None, None),
+			PoolError::ImmatureTransaction => (1005, "ImmatureTransaction", None, None),
+			PoolError::ImmatureCoinbase => (1006, "ImmatureCoinbase", None, None),
+			PoolError::DandelionError => (1007, "DandelionError", None, None),
+			PoolError::OverCapacity => (1008, "OverCapacity", None, None),
+			PoolError::LowFeeTransaction(fee) => (1009, "LowFeeTransaction", None, Some(*fee)),
+			PoolError::DuplicateCommitment(c) => (1010, "DuplicateCommitment", Some(c), None),
+			PoolError::DuplicateTx => (1011, "DuplicateTx", None, None),
+			PoolError::InputNotFound(c) => (1012, "InputNotFound", Some(c), None),
+			PoolError::DuplicateKernel(c) => (1013, "DuplicateKernel", Some(c), None),
+			PoolError::NRDKernelNotEnabled => (1014, "NRDKernelNotEnabled", None, None),
+			PoolError::NRDKernelRelativeHeight => (1015, "NRDKernelRelativeHeight", None, None),
+			PoolError::Other(_) => (1099, "Other", None, None),
+		};
+		PoolErrorInfo {
+			code,
+			kind: kind.to_owned(),
+			message: e.to_string(),
+			commitment: commitment.map(|c| util::to_hex(c.0.to_vec())),
+			min_fee,
+		}
+	}
+}
+
+impl fmt::Display for PoolErrorInfo {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.message)
+	}
+}
+
+/// Details of a chain error. The code is stable and returned as the
+/// JSON-RPC error code.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ChainErrorInfo {
+	/// Stable error code (2000 range)
+	pub code: i64,
+	/// Chain error variant, e.g. `Orphan`
+	pub kind: String,
+	/// Error message
+	pub message: String,
+	/// Commitment at fault (already spent or duplicate)
+	pub commitment: Option<String>,
+}
+
+impl ChainErrorInfo {
+	pub fn from_error(e: &chain::Error) -> ChainErrorInfo {
+		use crate::chain::ErrorKind::*;
+		let kind = e.kind();
+		let (code, name, commitment) = match &kind {
+			Unfit(_) => (2001, "Unfit", None),
+			Orphan(_) => (2002, "Orphan", None),
+			DifficultyTooLow => (2003, "DifficultyTooLow", None),
+			WrongTotalDifficulty => (2004, "WrongTotalDifficulty", None),
+			LowEdgebits => (2005, "LowEdgebits", None),
+			InvalidHash => (2006, "InvalidHash", None),
+			InvalidScaling => (2007, "InvalidScaling", None),
+			InvalidPow => (2008, "InvalidPow", None),
+			OldBlock => (2009, "OldBlock", None),
+			InvalidBlockProof(_) => (2010, "InvalidBlockProof", None),
+			InvalidBlockTime => (2011, "InvalidBlockTime", None),
+			InvalidBlockHeight => (2012, "InvalidBlockHeight", None),
+			InvalidRoot(_) => (2013, "InvalidRoot", None),
+			InvalidMMRSize => (2014, "InvalidMMRSize", None),
+			Keychain(_) => (2015, "Keychain", None),
+			Secp(_) => (2016, "Secp", None),
+			AlreadySpent(c) => (2017, "AlreadySpent", Some(c)),
+			DuplicateCommitment(c) => (2018, "DuplicateCommitment", Some(c)),
+			ImmatureCoinbase => (2019, "ImmatureCoinbase", None),
+			MerkleProof(_) => (2020, "MerkleProof", None),
+			OutputNotFound(_) => (2021, "OutputNotFound", None),
+			RangeproofNotFound(_) => (2022, "RangeproofNotFound", None),
+			TxKernelNotFound => (2023, "TxKernelNotFound", None),
+			OutputSpent => (2024, "OutputSpent", None),
+			InvalidBlockVersion(_) => (2025, "InvalidBlockVersion", None),
+			InvalidTxHashSet(_) => (2026, "InvalidTxHashSet", None),
+			StoreErr(_, _) => (2027, "StoreErr", None),
+			FileReadErr(_) => (2028, "FileReadErr", None),
+			SerErr(_) => (2029, "SerErr", None),
+			TxHashSetErr(_) => (2030, "TxHashSetErr", None),
+			TxLockHeight => (2031, "TxLockHeight", None),
+			GenesisBlockRequired => (2032, "GenesisBlockRequired", None),
+			Transaction(_) => (2033, "Transaction", None),
+			Committed(_) => (2034, "Committed", None),
+			Stopped => (2035, "Stopped", None),
+			Bitmap => (2036, "Bitmap", None),
+			SyncError(_) => (2037, "SyncError", None),
+			IndexNotEnabled(_) => (2038, "IndexNotEnabled", None),
+			BelowHorizon(_, _) => (2039, "BelowHorizon", None),
+			ReorgTooDeep(_) => (2040, "ReorgTooDeep", None),
+			ValidationRunning => (2041, "ValidationRunning", None),
+			NRDRelativeHeight => (2042, "NRDRelativeHeight", None),
+			RewindTooDeep(_) => (2043, "RewindTooDeep", None),
+			Other(_) => (2099, "Other", None),
+		};
+		ChainErrorInfo {
+			code,
+			kind: name.to_owned(),
+			message: kind.to_string(),
+			commitment: commitment.map(|c| util::to_hex(c.0.to_vec())),
+		}
+	}
+}
+
+impl fmt::Display for ChainErrorInfo {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.message)
+	}
+}
+
+/// Outcome of validating a transaction against the pool without adding it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TxValidation {
+	/// Whether the pool would accept the transaction
+	pub valid: bool,
+	/// Why the transaction would be rejected
+	pub error: Option<PoolErrorInfo>,
+}
+
+/// Recommended fee for a transaction to be mined within a number of blocks.
+/// All fees are per unit of transaction weight, the same weight used by the
+/// pool to check `accept_fee_base`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FeeEstimate {
+	/// Number of blocks the estimate is for
+	pub target_blocks: u64,
+	/// Recommended fee per unit of weight
+	pub fee_per_weight: u64,
+	/// Minimum fee per unit of weight the pool will relay (accept_fee_base)
+	pub min_fee_per_weight: u64,
+	/// Fee per unit of weight needed to get ahead of the txpool backlog
+	pub pool_fee_per_weight: u64,
+	/// Lowest fee per unit of weight paid in recent full blocks
+	pub blocks_fee_per_weight: u64,
+	/// Number of recent blocks looked at
+	pub blocks_considered: u64,
+	/// Total block weight of the transactions in the txpool
+	pub pool_weight: u64,
+}
+
 #[cfg(test)]
 mod test {
 	use super::*;
@@ -48,6 +48,21 @@ where
 				StatusCode::INTERNAL_SERVER_ERROR,
 				format!("P2P Error, {}", err),
 			),
+			// Same code and data as the JSON-RPC error object of the v2 API
+			ErrorKind::Pool(_) => {
+				response(StatusCode::BAD_REQUEST, e.kind().to_rpc_error().to_string())
+			}
+			ErrorKind::Chain(_) => response(
+				StatusCode::INTERNAL_SERVER_ERROR,
+				e.kind().to_rpc_error().to_string(),
+			),
+			ErrorKind::Forbidden(_) => {
+				response(StatusCode::FORBIDDEN, e.kind().to_rpc_error().to_string())
+			}
+			ErrorKind::TooManyRequests(_) => response(
+				StatusCode::TOO_MANY_REQUESTS,
+				e.kind().to_rpc_error().to_string(),
+			),
 		},
 	}
 }
@@ -17,30 +17,34 @@
 
 use crate::core::core::hash::{Hash, Hashed, ZERO_HASH};
 use crate::core::core::merkle_proof::MerkleProof;
+use crate::core::core::pmmr;
 use crate::core::core::verifier_cache::VerifierCache;
 use crate::core::core::{
 	Block, BlockHeader, BlockSums, Committed, Output, OutputIdentifier, Transaction, TxKernel,
 };
 use crate::core::global;
 use crate::core::pow;
-use crate::core::ser::{ProtocolVersion, Readable, StreamingReader};
+use crate::core::ser::{self, ProtocolVersion, Readable, StreamingReader};
 use crate::error::{Error, ErrorKind};
 use crate::pipe;
 use crate::store;
 use crate::txhashset;
 use crate::txhashset::{PMMRHandle, TxHashSet};
 use crate::types::{
-	BlockStatus, ChainAdapter, CommitPos, NoStatus, Options, Tip, TxHashsetWriteStatus,
+	BlockStatus, ChainAdapter, ChainOptions, ChainTip, ChainTipStatus, CommitPos, DbIssue,
+	NoStatus, Options, OutputHistory, RefusedReorg, Tip, TxHashsetWriteStatus,
+	ValidationCheckpoint, ValidationPhase, ValidationStatus,
 };
 use crate::util::secp::pedersen::{Commitment, RangeProof};
-use crate::util::RwLock;
+use crate::util::{zip, RwLock};
 use mimble_store::Error::NotFoundErr;
 use std::collections::HashMap;
 use std::fs::{self, File};
-use std::io::Read;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::thread;
 use std::time::{Duration, Instant};
 
 /// Orphan pool size is limited by MAX_ORPHAN_SIZE
@@ -49,6 +53,26 @@ pub const MAX_ORPHAN_SIZE: usize = 200;
 /// When evicting, very old orphans are evicted first
 const MAX_ORPHAN_AGE_SECS: u64 = 300;
 
+/// Number of refused reorgs we keep track of
+const MAX_REFUSED_REORGS: usize = 100;
+
+/// Number of rangeproofs or kernel signatures verified per step of the full
+/// chain validation, the chain is locked for the duration of a step
+const FULL_VALIDATION_STEP_SIZE: usize = 5_000;
+
+/// Maximum number of blocks get_unspent_at_height rewinds on a pruned node,
+/// the chain is locked for the duration of the rewind
+pub const MAX_UNSPENT_AT_HEIGHT_REWIND: u64 = 60;
+
+/// Txhashset archive within a snapshot file
+const SNAPSHOT_TXHASHSET_FILE: &str = "txhashset.zip";
+
+/// Serialized header chain within a snapshot file
+const SNAPSHOT_HEADERS_FILE: &str = "headers.bin";
+
+/// Number of headers processed at once when importing a snapshot
+const SNAPSHOT_HEADERS_BATCH: usize = 512;
+
 #[derive(Debug, Clone)]
 struct Orphan {
 	block: Block,
@@ -154,7 +178,23 @@ pub struct Chain {
 	// POW verification function
 	pow_verifier: fn(&BlockHeader) -> Result<(), pow::Error>,
 	archive_mode: bool,
+	kernel_index: bool,
+	output_history_index: bool,
 	genesis: BlockHeader,
+	// blocks explicitly invalidated, persisted in the store
+	invalid_blocks: RwLock<Vec<Hash>>,
+	// maximum number of blocks a reorg may rewind, if any
+	max_reorg_depth: Option<u64>,
+	// blocks accepted regardless of the maximum reorg depth
+	accepted_reorgs: RwLock<Vec<Hash>>,
+	// most recent forks refused for exceeding the maximum reorg depth
+	refused_reorgs: RwLock<Vec<RefusedReorg>>,
+	// progress of the full chain validation
+	validation_status: RwLock<ValidationStatus>,
+	// whether the full chain validation is running
+	validation_running: AtomicBool,
+	// stop request for the running full chain validation
+	validation_cancelled: AtomicBool,
 }
 
 impl Chain {
@@ -167,12 +207,27 @@ impl Chain {
 		genesis: Block,
 		pow_verifier: fn(&BlockHeader) -> Result<(), pow::Error>,
 		verifier_cache: Arc<RwLock<dyn VerifierCache>>,
-		archive_mode: bool,
+		options: ChainOptions,
 	) -> Result<Chain, Error> {
+		let ChainOptions {
+			archive_mode,
+			kernel_index,
+			output_history_index,
+			max_reorg_depth,
+		} = options;
+
+		// The output history index requires the full block history.
+		if output_history_index && !archive_mode {
+			warn!("output history index requires archive mode, index disabled");
+		}
+		let output_history_index = output_history_index && archive_mode;
+
 		let store = Arc::new(store::ChainStore::new(&db_root)?);
 
 		// open the txhashset, creating a new one if necessary
 		let mut txhashset = txhashset::TxHashSet::open(db_root.clone(), store.clone(), None)?;
+		txhashset.set_kernel_pos_index(kernel_index);
+		txhashset.set_output_history_index(output_history_index);
 
 		let mut header_pmmr = PMMRHandle::new(
 			&db_root,
@@ -208,6 +263,55 @@ impl Chain {
 			batch.commit()?;
 		}
 
+		// Build the NRD kernel index if it does not exist yet.
+		// This index is required to validate NRD kernels and is always maintained.
+		{
+			let batch = store.batch()?;
+			txhashset.init_nrd_kernel_index(&header_pmmr, &batch)?;
+			batch.commit()?;
+		}
+
+		// Build the (optional) kernel_pos index if it does not exist yet.
+		// If the index is disabled flag it as stale so it is fully rebuilt
+		// should it be enabled again later.
+		{
+			let batch = store.batch()?;
+			if kernel_index {
+				txhashset.init_kernel_pos_index(&header_pmmr, &batch)?;
+			} else if batch.is_kernel_pos_index_built()? {
+				batch.delete_kernel_pos_index_built()?;
+			}
+			batch.commit()?;
+		}
+
+		// Build the (optional) output history index if it does not exist yet.
+		{
+			let batch = store.batch()?;
+			if output_history_index {
+				txhashset.init_output_history_index(&header_pmmr, &batch)?;
+			} else if batch.is_output_history_index_built()? {
+				batch.delete_output_history_index_built()?;
+			}
+			batch.commit()?;
+		}
+
+		// Track the chain tips if the db predates them.
+		if store.chain_tips()?.is_empty() {
+			let batch = store.batch()?;
+			let count = batch.rebuild_chain_tips()?;
+			batch.commit()?;
+			info!("init: rebuilt {} chain tips", count);
+		}
+
+		let invalid_blocks = store.invalid_blocks()?;
+		if !invalid_blocks.is_empty() {
+			info!("init: invalidated blocks {:?}", invalid_blocks);
+		}
+		let accepted_reorgs = store.accepted_reorgs()?;
+		if !accepted_reorgs.is_empty() {
+			info!("init: accepted reorgs {:?}", accepted_reorgs);
+		}
+
 		let chain = Chain {
 			db_root,
 			store,
@@ -219,7 +323,16 @@ impl Chain {
 			pow_verifier,
 			verifier_cache,
 			archive_mode,
+			kernel_index,
+			output_history_index,
 			genesis: genesis.header,
+			invalid_blocks: RwLock::new(invalid_blocks),
+			max_reorg_depth,
+			accepted_reorgs: RwLock::new(accepted_reorgs),
+			refused_reorgs: RwLock::new(vec![]),
+			validation_status: RwLock::new(ValidationStatus::default()),
+			validation_running: AtomicBool::new(false),
+			validation_cancelled: AtomicBool::new(false),
 		};
 
 		// DB migrations to be run prior to the chain being used.
@@ -300,7 +413,13 @@ impl Chain {
 			if head.prev_block_h == prev_head.last_block_h {
 				is_next_block = true;
 			} else {
-				reorg_depth = Some(prev_head.height.saturating_sub(head.height) + 1);
+				reorg_depth = match self.reorg_depth(&prev_head, &head) {
+					Ok(depth) => Some(depth),
+					Err(e) => {
+						warn!("determine_status: failed to find the fork point, {}", e);
+						None
+					}
+				};
 			}
 		}
 
@@ -311,6 +430,36 @@ impl Chain {
 		}
 	}
 
+	// Number of blocks of the previous head rewound when switching to the new
+	// head, walking back both chains to their fork point.
+	fn reorg_depth(&self, prev_head: &Tip, head: &Tip) -> Result<u64, Error> {
+		let mut prev = self.get_block_header(&prev_head.last_block_h)?;
+		let mut current = self.get_block_header(&head.last_block_h)?;
+		while prev.hash() != current.hash() {
+			if prev.height >= current.height {
+				prev = self.get_previous_header(&prev)?;
+			} else {
+				current = self.get_previous_header(&current)?;
+			}
+		}
+		Ok(prev_head.height - prev.height)
+	}
+
+	// Notify the adapter of a head change made outside of block processing
+	// (invalidation, reconsideration or rewind) the same way as for a block.
+	fn notify_head_change(&self, prev_head: Tip) -> Result<(), Error> {
+		let head = self.head()?;
+		if head == prev_head {
+			return Ok(());
+		}
+		if let BlockStatus::Reorg(depth) =
+			self.determine_status(Some(head.clone()), prev_head.clone())
+		{
+			self.adapter.reorg(depth, &prev_head, &head);
+		}
+		Ok(())
+	}
+
 	/// Attempt to add a new block to the chain.
 	/// Returns true if it has been added to the longest chain
 	/// or false if it has added to a fork (or orphan?).
@@ -350,10 +499,13 @@ impl Chain {
 
 		match maybe_new_head {
 			Ok(head) => {
-				let status = self.determine_status(head.clone(), prev_head);
+				let status = self.determine_status(head.clone(), prev_head.clone());
 
 				// notifying other parts of the system of the update
-				self.adapter.block_accepted(&b, status, opts);
+				self.adapter.block_accepted(&b, status.clone(), opts);
+				if let (BlockStatus::Reorg(depth), Some(head)) = (&status, &head) {
+					self.adapter.reorg(*depth, &prev_head, head);
+				}
 
 				Ok(head)
 			}
@@ -381,6 +533,10 @@ impl Chain {
 					);
 					Err(ErrorKind::Orphan(msg).into())
 				}
+				ErrorKind::ReorgTooDeep(max_depth) => {
+					self.reorg_refused(&b.header, max_depth);
+					Err(ErrorKind::ReorgTooDeep(max_depth).into())
+				}
 				ErrorKind::Unfit(ref msg) => {
 					debug!(
 						"Block {} at {} is unfit at this time: {}",
@@ -422,7 +578,7 @@ impl Chain {
 			&mut txhashset,
 			invalid_block_hashes,
 		)?;
-		pipe::process_block_header(bh, &mut ctx)?;
+		pipe::process_block_header(bh, &mut ctx).map_err(|e| self.track_refused_reorg(bh, e))?;
 		ctx.batch.commit()?;
 		Ok(())
 	}
@@ -450,7 +606,10 @@ impl Chain {
 				&mut txhashset,
 				invalid_block_hashes.clone(),
 			)?;
-			pipe::sync_block_headers(headers, &mut ctx)?;
+			pipe::sync_block_headers(headers, &mut ctx).map_err(|e| match headers.last() {
+				Some(header) => self.track_refused_reorg(header, e),
+				None => e,
+			})?;
 			ctx.batch.commit()?;
 		}
 
@@ -464,7 +623,8 @@ impl Chain {
 				&mut txhashset,
 				invalid_block_hashes,
 			)?;
-			pipe::process_block_header(header, &mut ctx)?;
+			pipe::process_block_header(header, &mut ctx)
+				.map_err(|e| self.track_refused_reorg(header, e))?;
 			ctx.batch.commit()?;
 		}
 
@@ -477,8 +637,9 @@ impl Chain {
 		batch: store::Batch<'a>,
 		header_pmmr: &'a mut txhashset::PMMRHandle<BlockHeader>,
 		txhashset: &'a mut txhashset::TxHashSet,
-		invalid_block_hashes: Vec<Hash>,
+		mut invalid_block_hashes: Vec<Hash>,
 	) -> Result<pipe::BlockContext<'a>, Error> {
+		invalid_block_hashes.extend(self.invalid_blocks.read().iter());
 		Ok(pipe::BlockContext {
 			opts,
 			pow_verifier: self.pow_verifier,
@@ -487,9 +648,409 @@ impl Chain {
 			txhashset,
 			batch,
 			invalid_block_hashes,
+			max_reorg_depth: self.max_reorg_depth,
+			accepted_reorgs: self.accepted_reorgs.read().clone(),
 		})
 	}
 
+	// Keep track of the fork if the error is a refused reorg.
+	fn track_refused_reorg(&self, header: &BlockHeader, e: Error) -> Error {
+		if let ErrorKind::ReorgTooDeep(max_depth) = e.kind() {
+			self.reorg_refused(header, max_depth);
+		}
+		e
+	}
+
+	// Record a refused fork, notifying the adapter the first time we see it.
+	fn reorg_refused(&self, header: &BlockHeader, max_depth: u64) {
+		let reorg = {
+			let mut refused_reorgs = self.refused_reorgs.write();
+			if refused_reorgs.iter().any(|r| r.hash == header.hash()) {
+				return;
+			}
+			let reorg = RefusedReorg {
+				hash: header.hash(),
+				height: header.height,
+				total_difficulty: header.total_difficulty(),
+				max_depth,
+				refused_at: global::now().timestamp(),
+			};
+			refused_reorgs.push(reorg.clone());
+			if refused_reorgs.len() > MAX_REFUSED_REORGS {
+				refused_reorgs.remove(0);
+			}
+			reorg
+		};
+		self.adapter.reorg_refused(&reorg);
+	}
+
+	/// Maximum number of blocks a fork may rewind from our head, if any.
+	pub fn max_reorg_depth(&self) -> Option<u64> {
+		self.max_reorg_depth
+	}
+
+	/// Forks refused for exceeding the maximum reorg depth, most recent last.
+	pub fn refused_reorgs(&self) -> Vec<RefusedReorg> {
+		self.refused_reorgs.read().clone()
+	}
+
+	/// Accept a fork regardless of the maximum reorg depth. The block and its
+	/// descendants are no longer refused, we switch to the fork as soon as it
+	/// is received again (if it has the most work). The acceptance is
+	/// persisted in the store.
+	pub fn accept_reorg(&self, hash: Hash) -> Result<(), Error> {
+		if self.max_reorg_depth.is_none() {
+			return Err(ErrorKind::Other("no maximum reorg depth configured".to_owned()).into());
+		}
+		let mut accepted_reorgs = self.accepted_reorgs.write();
+		if !accepted_reorgs.contains(&hash) {
+			let batch = self.store.batch()?;
+			batch.save_accepted_reorg(&hash)?;
+			batch.commit()?;
+			accepted_reorgs.push(hash);
+		}
+		self.refused_reorgs.write().retain(|r| r.hash != hash);
+		info!("accept_reorg: fork at {} accepted", hash);
+		Ok(())
+	}
+
+	/// Mark a block as invalid, it will be rejected from now on along with all
+	/// its descendants. The invalid mark is persisted in the store.
+	/// If the block is on the current chain we rewind to its parent and switch
+	/// to the known fork with the most work.
+	pub fn invalidate_block(&self, hash: Hash) -> Result<(), Error> {
+		if hash == self.genesis.hash() {
+			return Err(ErrorKind::Other("cannot invalidate the genesis block".to_owned()).into());
+		}
+
+		let prev_head = {
+			let mut sync_pmmr = self.sync_pmmr.write();
+			let mut header_pmmr = self.header_pmmr.write();
+			let mut txhashset = self.txhashset.write();
+			let mut batch = self.store.batch()?;
+			let prev_head = batch.head()?;
+
+			batch.save_invalid_block(&hash)?;
+			let mut invalid_blocks = self.invalid_blocks.read().clone();
+			if !invalid_blocks.contains(&hash) {
+				invalid_blocks.push(hash);
+			}
+
+			// We may not know about the block yet, it will be rejected once received.
+			if let Ok(header) = batch.get_block_header(&hash) {
+				let prev = batch.get_previous_header(&header)?;
+
+				// Rewind the block chain if the block is part of it.
+				let head_header = batch.head_header()?;
+				if head_header.height >= header.height
+					&& self.is_ancestor(&header, &head_header, &batch)?
+				{
+					txhashset::extending(
+						&mut header_pmmr,
+						&mut txhashset,
+						&mut batch,
+						|ext, batch| pipe::rewind_and_apply_fork(&prev, ext, batch),
+					)?;
+					batch.save_body_head(&Tip::from_header(&prev))?;
+					info!(
+						"invalidate_block: rewound head from {} to {} at {}",
+						head_header.height,
+						prev.hash(),
+						prev.height
+					);
+				}
+
+				// Rewind the header chains if the block is part of them.
+				for pmmr in vec![&mut *header_pmmr, &mut *sync_pmmr] {
+					if pmmr.get_header_hash_by_height(header.height).ok() == Some(hash) {
+						txhashset::header_extending(pmmr, &mut batch, |ext, batch| {
+							pipe::rewind_and_apply_header_fork(&prev, ext, batch)
+						})?;
+					}
+				}
+			}
+
+			self.switch_to_best_fork(
+				&invalid_blocks,
+				&mut header_pmmr,
+				&mut txhashset,
+				&mut batch,
+			)?;
+			batch.commit()?;
+
+			*self.invalid_blocks.write() = invalid_blocks;
+			prev_head
+		};
+		info!("invalidate_block: block {} marked as invalid", hash);
+		self.notify_head_change(prev_head)
+	}
+
+	/// Remove the invalid mark of a block previously invalidated with
+	/// `invalidate_block` and switch to the known fork with the most work,
+	/// possibly the one including that block.
+	pub fn reconsider_block(&self, hash: Hash) -> Result<(), Error> {
+		let prev_head = {
+			let mut header_pmmr = self.header_pmmr.write();
+			let mut txhashset = self.txhashset.write();
+			let mut batch = self.store.batch()?;
+			let prev_head = batch.head()?;
+
+			if !batch.is_invalid_block(&hash)? {
+				return Err(ErrorKind::Other(format!("block {} is not invalidated", hash)).into());
+			}
+			batch.delete_invalid_block(&hash)?;
+			let mut invalid_blocks = self.invalid_blocks.read().clone();
+			invalid_blocks.retain(|h| h != &hash);
+
+			self.switch_to_best_fork(
+				&invalid_blocks,
+				&mut header_pmmr,
+				&mut txhashset,
+				&mut batch,
+			)?;
+			batch.commit()?;
+
+			*self.invalid_blocks.write() = invalid_blocks;
+			prev_head
+		};
+		info!("reconsider_block: block {} no longer invalid", hash);
+		self.notify_head_change(prev_head)
+	}
+
+	/// Rewind the txhashset, head and header head to the block at the
+	/// provided height. An archive node can rewind to any height, a pruned
+	/// node only down to the cut-through horizon. The removed blocks are kept
+	/// in the db as fork blocks, they are processed again once normal sync
+	/// gets to them (or switched back to as any other fork).
+	pub fn rewind_chain(&self, height: u64) -> Result<Tip, Error> {
+		let head = self.head()?;
+		if height > head.height {
+			return Err(ErrorKind::Other(format!(
+				"cannot rewind to {}, head is at {}",
+				height, head.height
+			))
+			.into());
+		}
+		if !self.archive_mode {
+			let tail_height = self.tail().map(|x| x.height).unwrap_or(0);
+			let horizon_height = head
+				.height
+				.saturating_sub(global::cut_through_horizon() as u64);
+			let min_height = tail_height.max(horizon_height);
+			if height < min_height {
+				return Err(ErrorKind::BelowHorizon(height, min_height).into());
+			}
+		}
+
+		let header = {
+			let mut sync_pmmr = self.sync_pmmr.write();
+			let mut header_pmmr = self.header_pmmr.write();
+			let mut txhashset = self.txhashset.write();
+			let mut batch = self.store.batch()?;
+
+			let mut header = batch.head_header()?;
+			while header.height > height {
+				header = batch.get_previous_header(&header)?;
+			}
+
+			txhashset::extending(
+				&mut header_pmmr,
+				&mut txhashset,
+				&mut batch,
+				|ext, batch| pipe::rewind_and_apply_fork(&header, ext, batch),
+			)?;
+			batch.save_body_head(&Tip::from_header(&header))?;
+			for pmmr in vec![&mut *header_pmmr, &mut *sync_pmmr] {
+				txhashset::header_extending(pmmr, &mut batch, |ext, batch| {
+					pipe::rewind_and_apply_header_fork(&header, ext, batch)
+				})?;
+			}
+			batch.commit()?;
+			header
+		};
+
+		info!(
+			"rewind_chain: rewound {} blocks from {} to {} at {}",
+			head.height - header.height,
+			head.height,
+			header.hash(),
+			header.height
+		);
+		self.notify_head_change(head)?;
+		Ok(Tip::from_header(&header))
+	}
+
+	/// All known chain tips (headers without any known child), highest first.
+	/// The branch length is the number of blocks since the fork point with
+	/// the header chain.
+	pub fn chain_tips(&self) -> Result<Vec<ChainTip>, Error> {
+		let header_pmmr = self.header_pmmr.read();
+		let head = self.head()?;
+		let invalid_blocks = self.invalid_blocks.read().clone();
+		let batch = self.store.batch()?;
+
+		// The body head isn't a tip while syncing, the header head is ahead of it.
+		let mut all_tips = self.store.chain_tips()?;
+		if !all_tips.iter().any(|t| t.last_block_h == head.last_block_h) {
+			all_tips.push(head.clone());
+		}
+
+		let mut tips = vec![];
+		for tip in all_tips {
+			let header = batch.get_block_header(&tip.last_block_h)?;
+			let mut fork_point = header.clone();
+			while header_pmmr
+				.get_header_hash_by_height(fork_point.height)
+				.ok() != Some(fork_point.hash())
+			{
+				fork_point = batch.get_previous_header(&fork_point)?;
+			}
+
+			let status = if tip.last_block_h == head.last_block_h {
+				ChainTipStatus::Active
+			} else if pipe::invalid_ancestor(&header, &invalid_blocks, &header_pmmr, &batch)
+				.is_some()
+			{
+				ChainTipStatus::Invalid
+			} else if batch.get_block_sums(&tip.last_block_h).is_ok() {
+				ChainTipStatus::ValidFork
+			} else {
+				ChainTipStatus::HeadersOnly
+			};
+			tips.push(ChainTip {
+				branch_length: header.height - fork_point.height,
+				tip,
+				status,
+			});
+		}
+		tips.sort_by(|a, b| {
+			(b.tip.height, b.tip.total_difficulty).cmp(&(a.tip.height, a.tip.total_difficulty))
+		});
+		Ok(tips)
+	}
+
+	/// Blocks explicitly invalidated with `invalidate_block`.
+	pub fn invalid_blocks(&self) -> Vec<Hash> {
+		self.invalid_blocks.read().clone()
+	}
+
+	// Whether the header is an ancestor of (or the same as) the descendant.
+	fn is_ancestor(
+		&self,
+		header: &BlockHeader,
+		descendant: &BlockHeader,
+		batch: &store::Batch<'_>,
+	) -> Result<bool, Error> {
+		let mut current = descendant.clone();
+		while current.height > header.height {
+			current = batch.get_previous_header(&current)?;
+		}
+		Ok(current.hash() == header.hash())
+	}
+
+	// Fork choice among the fully processed blocks we know about: switch the
+	// block chain to the one with the most work not including an invalid block.
+	// Forks we can't rebuild (missing or failing blocks) are skipped.
+	fn switch_to_best_fork(
+		&self,
+		invalid_blocks: &[Hash],
+		header_pmmr: &mut txhashset::PMMRHandle<BlockHeader>,
+		txhashset: &mut txhashset::TxHashSet,
+		batch: &mut store::Batch<'_>,
+	) -> Result<(), Error> {
+		let head = batch.head()?;
+		let mut candidates = vec![];
+		for tip in batch.chain_tips()? {
+			if let Some(header) = self.best_processed_block(&tip, &head, header_pmmr, batch)? {
+				if !candidates.contains(&header) {
+					candidates.push(header);
+				}
+			}
+		}
+		candidates.sort_by(|a, b| b.total_difficulty().cmp(&a.total_difficulty()));
+
+		for candidate in candidates {
+			if pipe::invalid_ancestor(&candidate, invalid_blocks, header_pmmr, batch).is_some() {
+				continue;
+			}
+			let res = txhashset::extending(header_pmmr, txhashset, batch, |ext, batch| {
+				pipe::rewind_and_apply_fork(&candidate, ext, batch)
+			});
+			if let Err(e) = res {
+				debug!(
+					"switch_to_best_fork: skipping fork at {} {}, {}",
+					candidate.hash(),
+					candidate.height,
+					e
+				);
+				continue;
+			}
+			batch.save_body_head(&Tip::from_header(&candidate))?;
+
+			// Keep the header chain at least as far as the block chain.
+			let header_head = batch.get_block_header(&header_pmmr.head_hash()?)?;
+			if candidate.total_difficulty() > header_head.total_difficulty() {
+				txhashset::header_extending(header_pmmr, batch, |ext, batch| {
+					pipe::rewind_and_apply_header_fork(&candidate, ext, batch)
+				})?;
+			}
+			info!(
+				"switch_to_best_fork: head now {} at {}",
+				candidate.hash(),
+				candidate.height
+			);
+			break;
+		}
+		Ok(())
+	}
+
+	// The most recent fully processed block on the branch ending at the tip,
+	// if it has more work than the head. Processed blocks are contiguous on a
+	// branch, so on the header chain we binary search for the last one instead
+	// of walking back through a possibly long run of headers only.
+	fn best_processed_block(
+		&self,
+		tip: &Tip,
+		head: &Tip,
+		header_pmmr: &txhashset::PMMRHandle<BlockHeader>,
+		batch: &store::Batch<'_>,
+	) -> Result<Option<BlockHeader>, Error> {
+		let mut header = batch.get_block_header(&tip.last_block_h)?;
+		while header.total_difficulty() > head.total_difficulty {
+			if batch.get_block_sums(&header.hash()).is_ok() {
+				return Ok(Some(header));
+			}
+			if header_pmmr.get_header_hash_by_height(header.height).ok() == Some(header.hash()) {
+				// The fork point of the head with the header chain is processed.
+				let mut fork_point = batch.get_block_header(&head.last_block_h)?;
+				while header_pmmr
+					.get_header_hash_by_height(fork_point.height)
+					.ok() != Some(fork_point.hash())
+				{
+					fork_point = batch.get_previous_header(&fork_point)?;
+				}
+				let (mut low, mut high) = (fork_point.height, header.height);
+				while high - low > 1 {
+					let mid = low + (high - low) / 2;
+					let hash = header_pmmr.get_header_hash_by_height(mid)?;
+					if batch.get_block_sums(&hash).is_ok() {
+						low = mid;
+					} else {
+						high = mid;
+					}
+				}
+				let hash = header_pmmr.get_header_hash_by_height(low)?;
+				let best = batch.get_block_header(&hash)?;
+				if best.total_difficulty() > head.total_difficulty {
+					return Ok(Some(best));
+				}
+				return Ok(None);
+			}
+			header = batch.get_previous_header(&header)?;
+		}
+		Ok(None)
+	}
+
 	/// Check if hash is for a known orphan.
 	pub fn is_orphan(&self, hash: &Hash) -> bool {
 		self.orphans.contains(hash)
@@ -578,8 +1139,15 @@ impl Chain {
 		})
 	}
 
-	/// Validate the tx against the current UTXO set.
+	/// Validate the tx against the current UTXO set (and any NRD kernels against
+	/// the NRD kernel index for inclusion in the next block).
 	pub fn validate_tx(&self, tx: &Transaction) -> Result<(), Error> {
+		self.validate_tx_against_utxo(tx)?;
+		self.validate_tx_kernels(tx)?;
+		Ok(())
+	}
+
+	fn validate_tx_against_utxo(&self, tx: &Transaction) -> Result<(), Error> {
 		let header_pmmr = self.header_pmmr.read();
 		let txhashset = self.txhashset.read();
 		txhashset::utxo_view(&header_pmmr, &txhashset, |utxo, batch| {
@@ -588,6 +1156,21 @@ impl Chain {
 		})
 	}
 
+	/// NRD kernels are applied in order in a readonly extension at the next
+	/// block height, so each one is checked against the NRD kernel index and
+	/// against any earlier NRD kernel in the same tx sharing its excess.
+	fn validate_tx_kernels(&self, tx: &Transaction) -> Result<(), Error> {
+		if !tx.kernels().iter().any(|x| x.is_nrd()) {
+			return Ok(());
+		}
+		let height = self.next_block_height()?;
+		let mut header_pmmr = self.header_pmmr.write();
+		let mut txhashset = self.txhashset.write();
+		txhashset::extending_readonly(&mut header_pmmr, &mut txhashset, |ext, batch| {
+			ext.extension.apply_kernels(tx.kernels(), height, batch)
+		})
+	}
+
 	fn next_block_height(&self) -> Result<u64, Error> {
 		let bh = self.head_header()?;
 		Ok(bh.height + 1)
@@ -639,6 +1222,522 @@ impl Chain {
 		})
 	}
 
+	/// Start a full validation of the chain state in the background: the MMRs,
+	/// roots and kernel sums, then the rangeproofs of all unspent outputs and
+	/// the signatures of all kernels, verified in parallel. The validation
+	/// resumes from its checkpoint if a previous run was interrupted, its
+	/// progress is available through `full_validation_status`.
+	pub fn start_full_validation(chain: Arc<Chain>) -> Result<(), Error> {
+		chain.claim_full_validation()?;
+		let job = chain.clone();
+		let res = thread::Builder::new()
+			.name("chain_validation".to_string())
+			.spawn(move || {
+				if let Err(e) = job.do_full_validation() {
+					error!("full chain validation failed: {}", e);
+				}
+				job.validation_running.store(false, Ordering::SeqCst);
+			});
+		if let Err(e) = res {
+			chain.validation_running.store(false, Ordering::SeqCst);
+			return Err(
+				ErrorKind::Other(format!("unable to start chain validation, {}", e)).into(),
+			);
+		}
+		Ok(())
+	}
+
+	/// Run the full validation of the chain state on the current thread, see
+	/// `start_full_validation`.
+	pub fn run_full_validation(&self) -> Result<(), Error> {
+		self.claim_full_validation()?;
+		let res = self.do_full_validation();
+		self.validation_running.store(false, Ordering::SeqCst);
+		res
+	}
+
+	/// Request the running full chain validation to stop. It resumes from its
+	/// last checkpoint when started again (even after a restart).
+	/// Returns whether a validation was running.
+	pub fn cancel_full_validation(&self) -> bool {
+		let running = self.validation_running.load(Ordering::SeqCst);
+		if running {
+			self.validation_cancelled.store(true, Ordering::SeqCst);
+		}
+		running
+	}
+
+	/// Progress of the full chain validation.
+	pub fn full_validation_status(&self) -> ValidationStatus {
+		self.validation_status.read().clone()
+	}
+
+	/// Whether an interrupted full chain validation is waiting to be resumed.
+	pub fn has_full_validation_checkpoint(&self) -> Result<bool, Error> {
+		Ok(self.store.get_validation_checkpoint()?.is_some())
+	}
+
+	fn claim_full_validation(&self) -> Result<(), Error> {
+		if self
+			.validation_running
+			.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+			.is_err()
+		{
+			return Err(ErrorKind::ValidationRunning.into());
+		}
+		self.validation_cancelled.store(false, Ordering::SeqCst);
+		Ok(())
+	}
+
+	fn do_full_validation(&self) -> Result<(), Error> {
+		*self.validation_status.write() = ValidationStatus::default();
+		let res = self.full_validation_steps();
+		let mut status = self.validation_status.write();
+		match res {
+			Ok(true) => {
+				info!("full chain validation done at {}", status.height);
+				status.phase = ValidationPhase::Done;
+				Ok(())
+			}
+			Ok(false) => {
+				info!("full chain validation cancelled in {:?}", status.phase);
+				status.phase = ValidationPhase::Cancelled;
+				Ok(())
+			}
+			Err(e) => {
+				status.phase = ValidationPhase::Failed;
+				status.error = Some(e.to_string());
+				Err(e)
+			}
+		}
+	}
+
+	/// Runs the validation steps, saving a checkpoint after each of them.
+	/// Returns false if the validation was cancelled.
+	fn full_validation_steps(&self) -> Result<bool, Error> {
+		let mut checkpoint = match self.store.get_validation_checkpoint()? {
+			Some(checkpoint) => {
+				info!(
+					"full chain validation resuming {:?} from pos {}",
+					checkpoint.phase, checkpoint.pos
+				);
+				self.validation_status.write().resumed = true;
+				checkpoint
+			}
+			None => self.new_validation_checkpoint()?,
+		};
+
+		while checkpoint.phase != ValidationPhase::Done {
+			{
+				let batch = self.store.batch()?;
+				batch.save_validation_checkpoint(&checkpoint)?;
+				batch.commit()?;
+			}
+			if self.validation_cancelled.load(Ordering::SeqCst) {
+				return Ok(false);
+			}
+			checkpoint = match self.clamp_validation_checkpoint(&checkpoint)? {
+				Some(checkpoint) => self.full_validation_step(&checkpoint)?,
+				None => {
+					info!("full chain validation restarting, checkpoint header unknown");
+					self.new_validation_checkpoint()?
+				}
+			};
+		}
+
+		let batch = self.store.batch()?;
+		batch.delete_validation_checkpoint()?;
+		batch.commit()?;
+		Ok(true)
+	}
+
+	fn new_validation_checkpoint(&self) -> Result<ValidationCheckpoint, Error> {
+		let header = self.store.head_header()?;
+		// Lets just treat an "empty" node that just got started up as valid.
+		let phase = if header.height == 0 {
+			ValidationPhase::Done
+		} else {
+			ValidationPhase::Sums
+		};
+		Ok(ValidationCheckpoint {
+			header: header.hash(),
+			phase,
+			pos: 0,
+			done: 0,
+		})
+	}
+
+	// A reorg since the last step only invalidates the positions verified
+	// above the fork point, clamp them to the MMR sizes at the fork point.
+	// None if the checkpoint header is unknown (pruned).
+	fn clamp_validation_checkpoint(
+		&self,
+		checkpoint: &ValidationCheckpoint,
+	) -> Result<Option<ValidationCheckpoint>, Error> {
+		let mut header = match self.get_block_header(&checkpoint.header) {
+			Ok(header) => header,
+			Err(_) => return Ok(None),
+		};
+		let head = self.head()?;
+		if header.height <= head.height && self.is_on_current_chain(&header).is_ok() {
+			return Ok(Some(checkpoint.clone()));
+		}
+		while header.height > head.height || self.is_on_current_chain(&header).is_err() {
+			header = match self.get_previous_header(&header) {
+				Ok(header) => header,
+				Err(_) => return Ok(None),
+			};
+		}
+
+		let mut clamped = checkpoint.clone();
+		clamped.header = header.hash();
+		let max_pos = match checkpoint.phase {
+			ValidationPhase::Rangeproofs => Some(header.output_mmr_size),
+			ValidationPhase::Kernels => Some(header.kernel_mmr_size),
+			_ => None,
+		};
+		if let Some(max_pos) = max_pos {
+			if clamped.pos > max_pos {
+				clamped.pos = max_pos;
+				clamped.done = clamped.done.min(pmmr::n_leaves(max_pos));
+			}
+		}
+		info!(
+			"full chain validation: reorg, resuming {:?} from pos {} at {} {}",
+			clamped.phase,
+			clamped.pos,
+			header.hash(),
+			header.height
+		);
+		Ok(Some(clamped))
+	}
+
+	/// Runs the next validation step past the checkpoint: one of the MMRs or
+	/// the roots, sizes and kernel sums in the Sums phase, the next batch of
+	/// rangeproofs or kernel signatures otherwise. The chain locks are only
+	/// held for the duration of a step.
+	fn full_validation_step(
+		&self,
+		checkpoint: &ValidationCheckpoint,
+	) -> Result<ValidationCheckpoint, Error> {
+		let mut header_pmmr = self.header_pmmr.write();
+		let mut txhashset = self.txhashset.write();
+		let header = self.store.head_header()?;
+		let phase = checkpoint.phase;
+
+		let (last_pos, count, total) =
+			txhashset::extending_readonly(&mut header_pmmr, &mut txhashset, |ext, batch| {
+				pipe::rewind_and_apply_fork(&header, ext, batch)?;
+				let extension = &ext.extension;
+				match phase {
+					ValidationPhase::Sums => {
+						match checkpoint.pos {
+							0 => extension.validate_output_mmr()?,
+							1 => extension.validate_rproof_mmr()?,
+							2 => extension.validate_kernel_mmr()?,
+							_ => {
+								extension.validate_roots(&header)?;
+								extension.validate_sizes(&header)?;
+								extension.validate_kernel_sums(&self.genesis, &header)?;
+								return Ok((None, 1, 4));
+							}
+						}
+						Ok((Some(checkpoint.pos + 1), 1, 4))
+					}
+					ValidationPhase::Rangeproofs => {
+						let (last_pos, count) = extension
+							.verify_rangeproofs_from(checkpoint.pos, FULL_VALIDATION_STEP_SIZE)?;
+						Ok((last_pos, count, extension.n_unspent_outputs()))
+					}
+					ValidationPhase::Kernels => {
+						let (last_pos, count) = extension.verify_kernel_signatures_from(
+							checkpoint.pos,
+							FULL_VALIDATION_STEP_SIZE,
+						)?;
+						Ok((last_pos, count, extension.n_kernels()))
+					}
+					_ => Ok((None, 0, 0)),
+				}
+			})?;
+
+		let done = checkpoint.done + count;
+		self.set_full_validation_progress(phase, header.height, done, total);
+		debug!(
+			"full chain validation: {:?} {}/{} at {}",
+			phase, done, total, header.height
+		);
+
+		Ok(match last_pos {
+			Some(pos) => ValidationCheckpoint {
+				header: header.hash(),
+				phase,
+				pos,
+				done,
+			},
+			None => ValidationCheckpoint {
+				header: header.hash(),
+				phase: match phase {
+					ValidationPhase::Sums => ValidationPhase::Rangeproofs,
+					ValidationPhase::Rangeproofs => ValidationPhase::Kernels,
+					_ => ValidationPhase::Done,
+				},
+				pos: 0,
+				done: 0,
+			},
+		})
+	}
+
+	fn set_full_validation_progress(
+		&self,
+		phase: ValidationPhase,
+		height: u64,
+		done: u64,
+		total: u64,
+	) {
+		let mut status = self.validation_status.write();
+		status.phase = phase;
+		status.height = height;
+		status.done = done;
+		status.total = total;
+	}
+
+	/// Check the consistency of the db against the MMR data files: the header
+	/// MMR against the stored headers, the output_pos index against the output
+	/// MMR, the leaf sets against the prune lists, the block sums against the
+	/// kernels and the sanity of head and tail. In repair mode the derived
+	/// indices (output_pos, block sums and bitmap accumulator) are rebuilt in
+	/// place. Returns the inconsistencies found (before any repair).
+	pub fn check_db(&self, repair: bool) -> Result<Vec<DbIssue>, Error> {
+		let mut header_pmmr = self.header_pmmr.write();
+		let mut txhashset = self.txhashset.write();
+		let mut issues = vec![];
+
+		// Head and tail sanity.
+		let head = self.store.head()?;
+		let head_header = match self.store.get_block_header(&head.last_block_h) {
+			Ok(header) => header,
+			Err(_) => {
+				issues.push(DbIssue::fatal(
+					"head",
+					format!("header of the head {} not found", head.last_block_h),
+				));
+				return Ok(issues);
+			}
+		};
+		if !self.store.block_exists(&head.last_block_h)? {
+			issues.push(DbIssue::fatal(
+				"head",
+				format!("block of the head {} not found", head.last_block_h),
+			));
+		}
+		let header_head = self.read_header_head(&header_pmmr)?;
+		if head.total_difficulty > header_head.total_difficulty {
+			issues.push(DbIssue::fatal(
+				"head",
+				format!(
+					"head at {} has more work than the header head at {}",
+					head.height, header_head.height
+				),
+			));
+		}
+		let tail_height = match self.store.tail() {
+			Ok(tail) => {
+				if tail.height > head.height {
+					issues.push(DbIssue::fatal(
+						"tail",
+						format!(
+							"tail at {} is above the head at {}",
+							tail.height, head.height
+						),
+					));
+				}
+				if self.store.get_block_header(&tail.last_block_h).is_err() {
+					issues.push(DbIssue::fatal(
+						"tail",
+						format!("header of the tail {} not found", tail.last_block_h),
+					));
+				}
+				tail.height
+			}
+			Err(_) => 0,
+		};
+
+		// Header MMR against the stored headers.
+		let mut prev_hash = None;
+		for height in 0..=header_head.height {
+			let hash = match header_pmmr.get_header_hash_by_height(height) {
+				Ok(hash) => hash,
+				Err(_) => {
+					issues.push(DbIssue::fatal(
+						"header_mmr",
+						format!("no header hash at {} in the header MMR", height),
+					));
+					prev_hash = None;
+					continue;
+				}
+			};
+			match self.store.get_block_header(&hash) {
+				Ok(header) => {
+					if header.height != height {
+						issues.push(DbIssue::fatal(
+							"header_mmr",
+							format!("header {} at {} has height {}", hash, height, header.height),
+						));
+					}
+					if prev_hash.map_or(false, |h| h != header.prev_hash) {
+						issues.push(DbIssue::fatal(
+							"header_mmr",
+							format!(
+								"header {} at {} does not follow the previous one",
+								hash, height
+							),
+						));
+					}
+				}
+				Err(_) => issues.push(DbIssue::fatal(
+					"header_mmr",
+					format!("header {} at {} not found in the db", hash, height),
+				)),
+			}
+			prev_hash = Some(hash);
+		}
+
+		// Full kernel sums and roots of the txhashset against the head.
+		let (roots_res, sums_res) =
+			txhashset::extending_readonly(&mut header_pmmr, &mut txhashset, |ext, _| {
+				let roots_res = ext
+					.extension
+					.validate_roots(&head_header)
+					.and_then(|_| ext.extension.validate_sizes(&head_header));
+				let sums_res = if head.height > 0 {
+					Some(
+						ext.extension
+							.validate_kernel_sums(&self.genesis, &head_header),
+					)
+				} else {
+					None
+				};
+				Ok((roots_res, sums_res))
+			})?;
+		if let Err(e) = roots_res {
+			issues.push(DbIssue::fatal(
+				"head",
+				format!("txhashset does not match the head, {}", e),
+			));
+		}
+		let mut head_sums = None;
+		match sums_res {
+			Some(Ok((utxo_sum, kernel_sum))) => {
+				let is_match = self
+					.store
+					.get_block_sums(&head.last_block_h)
+					.map_or(false, |s| {
+						s.utxo_sum == utxo_sum && s.kernel_sum == kernel_sum
+					});
+				if !is_match {
+					issues.push(DbIssue::repairable(
+						"block_sums",
+						format!(
+							"block sums of the head {} do not match the kernels",
+							head.height
+						),
+					));
+					head_sums = Some(BlockSums {
+						utxo_sum,
+						kernel_sum,
+					});
+				}
+			}
+			Some(Err(e)) => issues.push(DbIssue::fatal(
+				"block_sums",
+				format!("kernel sums do not match the head, {}", e),
+			)),
+			None => {}
+		}
+
+		// Block sums (and blocks) of the body chain down to the tail.
+		let mut missing_sums = vec![];
+		let mut header = head_header.clone();
+		while header.height > tail_height {
+			if self.store.get_block_sums(&header.hash()).is_err() {
+				missing_sums.push(header.clone());
+			}
+			if !self.store.block_exists(&header.hash())? {
+				issues.push(DbIssue::fatal(
+					"blocks",
+					format!("block {} at {} not found", header.hash(), header.height),
+				));
+			}
+			header = self.store.get_previous_header(&header)?;
+		}
+		if !missing_sums.is_empty() {
+			issues.push(DbIssue::repairable(
+				"block_sums",
+				format!("{} blocks without block sums", missing_sums.len()),
+			));
+		}
+
+		// Output positions against the output MMR.
+		let batch = self.store.batch()?;
+		let (stale_count, missing_count) = txhashset.check_output_pos_index(&batch)?;
+		if stale_count > 0 || missing_count > 0 {
+			issues.push(DbIssue::repairable(
+				"output_pos",
+				format!(
+					"{} stale entries, {} unspent outputs without entry",
+					stale_count, missing_count
+				),
+			));
+		}
+
+		// Leaf sets against the prune lists.
+		for (mmr, pos) in txhashset.check_leaf_sets() {
+			issues.push(DbIssue::fatal(
+				"leaf_set",
+				format!("{} leaf at {} is in the leaf set but pruned", mmr, pos),
+			));
+		}
+
+		if !txhashset.check_bitmap_accumulator()? {
+			issues.push(DbIssue::repairable(
+				"bitmap_accumulator",
+				"bitmap accumulator does not match the output MMR".to_owned(),
+			));
+		}
+
+		for issue in &issues {
+			warn!("check_db: {}: {}", issue.check, issue.description);
+		}
+
+		if repair {
+			txhashset.init_output_pos_index(&header_pmmr, &batch)?;
+
+			// Lowest first, the block sums build on the previous ones.
+			for header in missing_sums.iter().rev() {
+				let sums = batch
+					.get_block(&header.hash())
+					.map_err(Error::from)
+					.and_then(|b| pipe::verify_block_sums(&b, &batch));
+				match sums {
+					Ok(sums) => batch.save_block_sums(&header.hash(), &sums)?,
+					Err(e) => warn!(
+						"check_db: cannot rebuild block sums at {}, {}",
+						header.height, e
+					),
+				}
+			}
+			if let Some(sums) = head_sums {
+				batch.save_block_sums(&head.last_block_h, &sums)?;
+			}
+			batch.commit()?;
+
+			txhashset.init_bitmap_accumulator()?;
+			info!("check_db: rebuilt output_pos, block sums and bitmap accumulator");
+		}
+
+		Ok(issues)
+	}
+
 	/// Sets the txhashset roots on a brand new block by applying the block on
 	/// the current txhashset state.
 	pub fn set_txhashset_roots(&self, b: &mut Block) -> Result<(), Error> {
@@ -971,6 +2070,17 @@ impl Chain {
 			return Err(ErrorKind::InvalidTxHashSet("not needed".to_owned()).into());
 		}
 
+		self.write_txhashset(h, txhashset_data, status)
+	}
+
+	// Validates and writes the txhashset, whether it was needed or explicitly
+	// imported. Returns true if the header is unknown (a bannable reason).
+	fn write_txhashset(
+		&self,
+		h: Hash,
+		txhashset_data: File,
+		status: &dyn TxHashsetWriteStatus,
+	) -> Result<bool, Error> {
 		let header = match self.get_block_header(&h) {
 			Ok(header) => header,
 			Err(e) => {
@@ -1044,6 +2154,20 @@ impl Chain {
 		// Rebuild our output_pos index in the db based on fresh UTXO set.
 		txhashset.init_output_pos_index(&header_pmmr, &batch)?;
 
+		// The kernel MMR has been replaced so the kernel_pos index must be fully rebuilt.
+		if batch.is_kernel_pos_index_built()? {
+			batch.delete_kernel_pos_index_built()?;
+		}
+		if self.kernel_index {
+			txhashset.init_kernel_pos_index(&header_pmmr, &batch)?;
+		}
+
+		// Likewise the NRD kernel index must be rebuilt from the new kernel MMR.
+		if batch.is_nrd_kernel_index_built()? {
+			batch.delete_nrd_kernel_index_built()?;
+		}
+		txhashset.init_nrd_kernel_index(&header_pmmr, &batch)?;
+
 		// Commit all the changes to the db.
 		batch.commit()?;
 
@@ -1066,6 +2190,8 @@ impl Chain {
 				self.store.clone(),
 				Some(&header),
 			)?;
+			txhashset.set_kernel_pos_index(self.kernel_index);
+			txhashset.set_output_history_index(self.output_history_index);
 
 			// Replace the chain txhashset with the newly built one.
 			*txhashset_ref = txhashset;
@@ -1078,6 +2204,228 @@ impl Chain {
 		Ok(false)
 	}
 
+	/// Export a snapshot of the chain state at the provided block to a file:
+	/// the txhashset archive (as provided to peers for state sync) along with
+	/// the header chain up to that block.
+	pub fn snapshot_export(&self, h: Hash, out: &Path) -> Result<(), Error> {
+		let header = self.get_block_header(&h)?;
+		self.is_on_current_chain(&header)?;
+		if header.height > self.head()?.height {
+			return Err(ErrorKind::Other(format!("block {} is above the chain head", h)).into());
+		}
+
+		let snapshot_dir = self.get_tmp_dir().join(format!("snapshot_{}", h));
+		if snapshot_dir.exists() {
+			fs::remove_dir_all(&snapshot_dir)?;
+		}
+		fs::create_dir_all(&snapshot_dir)?;
+
+		let (_, _, mut txhashset_data) = self.txhashset_read(h)?;
+		let mut txhashset_file = File::create(snapshot_dir.join(SNAPSHOT_TXHASHSET_FILE))?;
+		io::copy(&mut txhashset_data, &mut txhashset_file)?;
+
+		// Headers from height 1, the genesis is known by every node.
+		{
+			let mut headers_file =
+				BufWriter::new(File::create(snapshot_dir.join(SNAPSHOT_HEADERS_FILE))?);
+			ser::serialize_default(&mut headers_file, &header.height).map_err(ErrorKind::SerErr)?;
+			for height in 1..=header.height {
+				let header = self.get_header_by_height(height)?;
+				ser::serialize_default(&mut headers_file, &header).map_err(ErrorKind::SerErr)?;
+			}
+			headers_file.flush()?;
+		}
+
+		let files = vec![
+			PathBuf::from(SNAPSHOT_TXHASHSET_FILE),
+			PathBuf::from(SNAPSHOT_HEADERS_FILE),
+		];
+		zip::create_zip(&File::create(out)?, &snapshot_dir, files)?;
+		fs::remove_dir_all(&snapshot_dir)?;
+
+		info!(
+			"snapshot_export: exported snapshot at {} ({}) to {:?}",
+			header.height, h, out
+		);
+		Ok(())
+	}
+
+	/// Bootstrap a new chain from a snapshot file written by `snapshot_export`.
+	/// The headers are processed as during header sync, then the txhashset is
+	/// fully validated (kernel sums, rangeproofs and kernel signatures) as
+	/// during state sync. Returns the header of the snapshot.
+	pub fn snapshot_import(
+		&self,
+		snapshot: &Path,
+		status: &dyn TxHashsetWriteStatus,
+	) -> Result<BlockHeader, Error> {
+		if self.head()?.height > 0 || self.header_head()?.height > 0 {
+			return Err(ErrorKind::Other(
+				"a snapshot can only be imported into an empty chain".to_owned(),
+			)
+			.into());
+		}
+
+		let snapshot_dir = self.get_tmp_dir().join("snapshot_import");
+		if snapshot_dir.exists() {
+			fs::remove_dir_all(&snapshot_dir)?;
+		}
+		fs::create_dir_all(&snapshot_dir)?;
+		let files = vec![
+			PathBuf::from(SNAPSHOT_TXHASHSET_FILE),
+			PathBuf::from(SNAPSHOT_HEADERS_FILE),
+		];
+		zip::extract_files(File::open(snapshot)?, &snapshot_dir, files)?;
+
+		let mut headers_file =
+			BufReader::new(File::open(snapshot_dir.join(SNAPSHOT_HEADERS_FILE))?);
+		let count: u64 = ser::deserialize_default(&mut headers_file).map_err(ErrorKind::SerErr)?;
+		let mut last = None;
+		let mut remaining = count;
+		while remaining > 0 {
+			let batch_size = remaining.min(SNAPSHOT_HEADERS_BATCH as u64);
+			let mut headers = Vec::with_capacity(batch_size as usize);
+			for _ in 0..batch_size {
+				let header: BlockHeader =
+					ser::deserialize_default(&mut headers_file).map_err(ErrorKind::SerErr)?;
+				headers.push(header);
+			}
+			self.sync_block_headers(&headers, Options::NONE, vec![])?;
+			last = headers.pop();
+			remaining -= batch_size;
+			debug!(
+				"snapshot_import: processed {} of {} headers",
+				count - remaining,
+				count
+			);
+		}
+		let header = last.ok_or_else(|| ErrorKind::Other("snapshot without headers".to_owned()))?;
+
+		// Not a state sync, the snapshot is imported even if the chain is below
+		// the horizon and wouldn't need a txhashset.
+		let txhashset_data = File::open(snapshot_dir.join(SNAPSHOT_TXHASHSET_FILE))?;
+		status.on_setup();
+		if self.write_txhashset(header.hash(), txhashset_data, status)? {
+			return Err(ErrorKind::InvalidTxHashSet(format!(
+				"snapshot header {} not found",
+				header.hash()
+			))
+			.into());
+		}
+		fs::remove_dir_all(&snapshot_dir)?;
+
+		info!(
+			"snapshot_import: imported snapshot at {} ({})",
+			header.height,
+			header.hash()
+		);
+		Ok(header)
+	}
+
+	/// Stream all the blocks of the chain, from genesis to the head, to a
+	/// bootstrap file. The blocks are serialized with the provided protocol
+	/// version, each one prefixed with its length. The progress callback is
+	/// called with the number of blocks written and the total.
+	/// Returns the number of blocks written.
+	pub fn export_blocks(
+		&self,
+		sink: &mut dyn Write,
+		version: ProtocolVersion,
+		progress: &mut dyn FnMut(u64, u64),
+	) -> Result<u64, Error> {
+		let head = self.head()?;
+		let genesis = self.get_header_by_height(0)?;
+		ser::serialize_default(sink, &version).map_err(ErrorKind::SerErr)?;
+		ser::serialize_default(sink, &genesis.hash()).map_err(ErrorKind::SerErr)?;
+		ser::serialize_default(sink, &head.height).map_err(ErrorKind::SerErr)?;
+
+		for height in 1..=head.height {
+			let hash = self.get_header_hash_by_height(height)?;
+			let block = self.store.get_block(&hash).map_err(|e| {
+				ErrorKind::StoreErr(
+					e,
+					format!("block at {} not found, not an archive node?", height),
+				)
+			})?;
+			let data = ser::ser_vec(&block, version).map_err(ErrorKind::SerErr)?;
+			ser::serialize_default(sink, &(data.len() as u64)).map_err(ErrorKind::SerErr)?;
+			sink.write_all(&data)?;
+			progress(height, head.height);
+		}
+		sink.flush()?;
+
+		info!(
+			"export_blocks: exported {} blocks up to {}",
+			head.height, head.last_block_h
+		);
+		Ok(head.height)
+	}
+
+	/// Replay the blocks of a bootstrap file written by `export_blocks` through
+	/// the usual block processing. Blocks already in the chain are skipped,
+	/// so an interrupted import can be resumed. The proof of work isn't
+	/// verified for the blocks up to `skip_pow_height` matching our header
+	/// chain, their headers were already validated with their proof of work.
+	/// Returns the number of blocks processed.
+	pub fn import_blocks(
+		&self,
+		source: &mut dyn Read,
+		skip_pow_height: Option<u64>,
+		progress: &mut dyn FnMut(u64, u64),
+	) -> Result<u64, Error> {
+		let version: ProtocolVersion =
+			ser::deserialize_default(source).map_err(ErrorKind::SerErr)?;
+		let genesis: Hash = ser::deserialize_default(source).map_err(ErrorKind::SerErr)?;
+		if genesis != self.genesis.hash() {
+			return Err(ErrorKind::Other(format!(
+				"blocks of another chain, genesis {} instead of {}",
+				genesis,
+				self.genesis.hash()
+			))
+			.into());
+		}
+		let count: u64 = ser::deserialize_default(source).map_err(ErrorKind::SerErr)?;
+
+		let mut processed = 0;
+		for n in 1..=count {
+			let len: u64 = ser::deserialize_default(source).map_err(ErrorKind::SerErr)?;
+			if len > global::max_block_size() {
+				return Err(ErrorKind::Other(format!(
+					"block {} of {} bytes, more than the max block size",
+					n, len
+				))
+				.into());
+			}
+			let mut data = vec![0; len as usize];
+			source.read_exact(&mut data)?;
+			let block: Block =
+				ser::deserialize(&mut &data[..], version).map_err(ErrorKind::SerErr)?;
+
+			let mut opts = Options::SYNC;
+			if skip_pow_height.map_or(false, |h| block.header.height <= h)
+				&& self.is_on_current_chain(&block.header).is_ok()
+			{
+				opts |= Options::SKIP_POW;
+			}
+			match self.process_block(block, opts, vec![]) {
+				Ok(_) => processed += 1,
+				Err(e) => match e.kind() {
+					ErrorKind::Unfit(_) => {}
+					_ => return Err(e),
+				},
+			}
+			progress(n, count);
+		}
+
+		info!(
+			"import_blocks: processed {} of {} blocks, head at {}",
+			processed,
+			count,
+			self.head()?.height
+		);
+		Ok(processed)
+	}
+
 	/// Cleanup old blocks from the db.
 	/// Determine the cutoff height from the horizon and the current block height.
 	/// *Only* runs if we are not in archive mode.
@@ -1170,6 +2518,9 @@ impl Chain {
 			let horizon_header = batch.get_block_header(&horizon_hash)?;
 
 			txhashset.compact(&horizon_header, &batch)?;
+
+			let pruned = batch.prune_chain_tips(horizon_height)?;
+			debug!("compact: pruned {} chain tips below the horizon", pruned);
 		}
 
 		// If we are not in archival mode remove historical blocks from the db.
@@ -1371,13 +2722,122 @@ impl Chain {
 		Ok(self.get_block_header(&hash)?)
 	}
 
+	/// Gets the creation and spend history of all outputs with the given commitment,
+	/// from the (optional) output history index.
+	pub fn get_output_history(&self, commit: &Commitment) -> Result<Vec<OutputHistory>, Error> {
+		if !self.output_history_index {
+			return Err(ErrorKind::IndexNotEnabled("output history".to_owned()).into());
+		}
+		match self.store.get_output_history(commit) {
+			Ok(history) => Ok(history),
+			Err(NotFoundErr(_)) => Ok(vec![]),
+			Err(e) => Err(ErrorKind::StoreErr(e, "chain get output history".to_owned()).into()),
+		}
+	}
+
+	/// Gets the MMR pos and block height of the given output commitments if they were
+	/// unspent as of the block at the given height on the current chain.
+	/// Uses the output history index if enabled, otherwise rewinds a readonly extension
+	/// to the requested height using the spent index of each rewound block.
+	/// This requires full blocks back to the requested height, archive nodes
+	/// can rewind to any height while pruned nodes are limited to
+	/// MAX_UNSPENT_AT_HEIGHT_REWIND blocks below the head.
+	pub fn get_unspent_at_height(
+		&self,
+		commits: &[Commitment],
+		height: u64,
+	) -> Result<Vec<Option<CommitPos>>, Error> {
+		let head = self.head()?;
+		if height > head.height {
+			return Err(ErrorKind::Other(format!(
+				"height {} is above the chain head {}",
+				height, head.height
+			))
+			.into());
+		}
+
+		if self.output_history_index {
+			let mut res = vec![];
+			for commit in commits {
+				let history = self.get_output_history(commit)?;
+				let unspent = history
+					.iter()
+					.rev()
+					.find(|x| x.height <= height && x.spent_height.map_or(true, |h| h > height))
+					.map(|x| CommitPos {
+						pos: x.pos,
+						height: x.height,
+					});
+				res.push(unspent);
+			}
+			return Ok(res);
+		}
+
+		let tail_height = self.tail().map(|x| x.height).unwrap_or(0);
+		if height < tail_height {
+			return Err(ErrorKind::BelowHorizon(height, tail_height).into());
+		}
+		if !self.archive_mode && head.height - height > MAX_UNSPENT_AT_HEIGHT_REWIND {
+			return Err(ErrorKind::RewindTooDeep(MAX_UNSPENT_AT_HEIGHT_REWIND).into());
+		}
+
+		let header = self.get_header_by_height(height)?;
+		let mut header_pmmr = self.header_pmmr.write();
+		let mut txhashset = self.txhashset.write();
+		txhashset::extending_readonly(&mut header_pmmr, &mut txhashset, |ext, batch| {
+			pipe::rewind_and_apply_fork(&header, ext, batch)?;
+
+			let ref mut extension = ext.extension;
+			let ref mut header_extension = ext.header_extension;
+			let utxo = extension.utxo_view(header_extension);
+
+			// The output_pos index has been rewound along with the extension.
+			let mut res = vec![];
+			for commit in commits {
+				let unspent = match batch.get_output_pos_height(commit) {
+					Ok((pos, out_height)) => match utxo.get_unspent_output_at(pos) {
+						Ok(out) if out.commitment() == *commit => Some(CommitPos {
+							pos,
+							height: out_height,
+						}),
+						_ => None,
+					},
+					Err(NotFoundErr(_)) => None,
+					Err(e) => {
+						return Err(ErrorKind::StoreErr(e, "rewound output pos".to_owned()).into())
+					}
+				};
+				res.push(unspent);
+			}
+			Ok(res)
+		})
+	}
+
 	/// Gets the kernel with a given excess and the block height it is included in.
+	/// Uses the kernel_pos index if enabled, falling back to a scan of the kernel MMR.
 	pub fn get_kernel_height(
 		&self,
 		excess: &Commitment,
 		min_height: Option<u64>,
 		max_height: Option<u64>,
 	) -> Result<Option<(TxKernel, u64, u64)>, Error> {
+		if self.kernel_index {
+			let kernel_pos = self.txhashset.read().find_kernel_pos(excess)?;
+			match kernel_pos {
+				Some((kernel, pos)) => {
+					if min_height.map_or(false, |h| pos.height < h) {
+						return Ok(None);
+					}
+					if max_height.map_or(true, |h| pos.height <= h) {
+						return Ok(Some((kernel, pos.height, pos.pos)));
+					}
+					// The index only tracks the most recent kernel for a given excess.
+					// Scan the kernel MMR for an earlier duplicate below max_height.
+				}
+				None => return Ok(None),
+			}
+		}
+
 		let min_index = match min_height {
 			Some(h) => Some(self.get_header_by_height(h - 1)?.kernel_mmr_size + 1),
 			None => None,
@@ -149,6 +149,30 @@ pub enum ErrorKind {
 	/// Error during chain sync
 	#[fail(display = "Sync error")]
 	SyncError(String),
+	/// Optional index required by the request is not enabled on this node
+	#[fail(display = "Index not enabled: {}", _0)]
+	IndexNotEnabled(String),
+	/// Requested height is below the horizon, full blocks have been pruned
+	#[fail(
+		display = "Height {} is below the horizon, earliest full block is at {}",
+		_0, _1
+	)]
+	BelowHorizon(u64, u64),
+	/// Switching to the fork would rewind the chain further than allowed
+	#[fail(display = "Fork is deeper than the maximum reorg depth of {}", _0)]
+	ReorgTooDeep(u64),
+	/// Readonly rewind deeper than allowed without the output history index
+	#[fail(
+		display = "Rewind is deeper than the maximum of {} blocks, the output history index is required",
+		_0
+	)]
+	RewindTooDeep(u64),
+	/// Full chain validation already running
+	#[fail(display = "Full chain validation already running")]
+	ValidationRunning,
+	/// NRD kernel seen again within its relative lock height
+	#[fail(display = "NRD kernel relative height")]
+	NRDRelativeHeight,
 }
 
 impl Display for Error {
@@ -193,6 +217,11 @@ impl Error {
 			| ErrorKind::SerErr(_)
 			| ErrorKind::TxHashSetErr(_)
 			| ErrorKind::GenesisBlockRequired
+			| ErrorKind::IndexNotEnabled(_)
+			| ErrorKind::BelowHorizon(_, _)
+			| ErrorKind::ReorgTooDeep(_)
+			| ErrorKind::RewindTooDeep(_)
+			| ErrorKind::ValidationRunning
 			| ErrorKind::Other(_) => false,
 			_ => true,
 		}
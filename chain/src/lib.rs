@@ -46,5 +46,7 @@ pub use crate::chain::{Chain, MAX_ORPHAN_SIZE};
 pub use crate::error::{Error, ErrorKind};
 pub use crate::store::ChainStore;
 pub use crate::types::{
-	BlockStatus, ChainAdapter, Options, SyncState, SyncStatus, Tip, TxHashsetWriteStatus,
+	BlockStatus, ChainAdapter, ChainOptions, ChainTip, ChainTipStatus, DbIssue, Options,
+	OutputHistory, RefusedReorg, SyncState, SyncStatus, Tip, TxHashsetWriteStatus, ValidationPhase,
+	ValidationStatus,
 };
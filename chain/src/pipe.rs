@@ -45,8 +45,14 @@ pub struct BlockContext<'a> {
 	/// The verifier cache (caching verifier for rangeproofs and kernel signatures)
 	pub verifier_cache: Arc<RwLock<dyn VerifierCache>>,
 
-	/// Invalid header list
+	/// Invalid header list, from the config and explicitly invalidated blocks
 	pub invalid_block_hashes: Vec<Hash>,
+
+	/// Maximum number of blocks a fork may rewind from our head, if any
+	pub max_reorg_depth: Option<u64>,
+
+	/// Blocks explicitly accepted regardless of the maximum reorg depth
+	pub accepted_reorgs: Vec<Hash>,
 }
 
 // Check if we already know about this block for various reasons
@@ -57,16 +63,129 @@ fn check_known(header: &BlockHeader, ctx: &mut BlockContext<'_>) -> Result<(), E
 	Ok(())
 }
 
-// Validate only the proof of work in a block header.
-// Used to cheaply validate pow before checking if orphan or continuing block validation.
-fn validate_pow_only(header: &BlockHeader, ctx: &mut BlockContext<'_>) -> Result<(), Error> {
-	for hash in &ctx.invalid_block_hashes {
-		if hash == &header.hash() {
-			error!("Invalid header found: {}. Rejecting it!", hash);
-			return Err(ErrorKind::InvalidHash.into());
+// Reject a header if it is invalid or descends from an invalid block.
+fn check_not_invalid(header: &BlockHeader, ctx: &mut BlockContext<'_>) -> Result<(), Error> {
+	if let Some(hash) = invalid_ancestor(
+		header,
+		&ctx.invalid_block_hashes,
+		ctx.header_pmmr,
+		&ctx.batch,
+	) {
+		error!(
+			"Invalid header found: {} at {} (invalid block {}). Rejecting it!",
+			header.hash(),
+			header.height,
+			hash
+		);
+		return Err(ErrorKind::InvalidHash.into());
+	}
+	Ok(())
+}
+
+/// Find the invalid block this header is or descends from, if any. Walks back
+/// the ancestors of the header until reaching the provided header chain, which
+/// never includes an invalid block (it is rewound when a block is invalidated).
+pub fn invalid_ancestor(
+	header: &BlockHeader,
+	invalid_block_hashes: &[Hash],
+	header_pmmr: &txhashset::PMMRHandle<BlockHeader>,
+	batch: &store::Batch<'_>,
+) -> Option<Hash> {
+	if invalid_block_hashes.is_empty() {
+		return None;
+	}
+	let mut current = header.clone();
+	loop {
+		let hash = current.hash();
+		if invalid_block_hashes.contains(&hash) {
+			return Some(hash);
+		}
+		if invalid_block_hashes.contains(&current.prev_hash) {
+			return Some(current.prev_hash);
+		}
+		if current.height == 0 {
+			return None;
+		}
+		// We do not know the parent, the header will be rejected as an orphan.
+		current = match batch.get_previous_header(&current) {
+			Ok(prev) => prev,
+			Err(_) => return None,
+		};
+		if header_pmmr.get_header_hash_by_height(current.height).ok() == Some(current.hash()) {
+			return None;
 		}
 	}
+}
 
+// Refuse a header that would switch to a fork rewinding the head further than
+// the maximum reorg depth, unless it is (or descends from) an accepted block.
+fn check_reorg_depth(
+	header: &BlockHeader,
+	head: &Tip,
+	ctx: &BlockContext<'_>,
+) -> Result<(), Error> {
+	let max_depth = match ctx.max_reorg_depth {
+		Some(max_depth) => max_depth,
+		None => return Ok(()),
+	};
+	if header.prev_hash == head.last_block_h || !has_more_work(header, head) {
+		return Ok(());
+	}
+
+	// Walk back both chains to the fork point, giving up once deeper than allowed.
+	let mut fork = header.clone();
+	let mut ours = ctx.batch.get_block_header(&head.last_block_h)?;
+	loop {
+		if fork.height > ours.height {
+			fork = ctx.batch.get_previous_header(&fork)?;
+		} else if fork.hash() == ours.hash() {
+			return Ok(());
+		} else if head.height - ours.height >= max_depth {
+			break;
+		} else {
+			if fork.height == ours.height {
+				fork = ctx.batch.get_previous_header(&fork)?;
+			}
+			ours = ctx.batch.get_previous_header(&ours)?;
+		}
+	}
+
+	// Keep walking the fork down to the accepted blocks, if any.
+	if let Some(min_height) = ctx
+		.accepted_reorgs
+		.iter()
+		.filter_map(|h| ctx.batch.get_block_header(h).ok())
+		.map(|h| h.height)
+		.min()
+	{
+		let mut current = header.clone();
+		loop {
+			if ctx.accepted_reorgs.contains(&current.hash()) {
+				return Ok(());
+			}
+			if current.height <= min_height {
+				break;
+			}
+			current = ctx.batch.get_previous_header(&current)?;
+		}
+	}
+	if ctx.accepted_reorgs.contains(&header.hash()) {
+		return Ok(());
+	}
+
+	warn!(
+		"Refusing fork at {} {}, deeper than the maximum reorg depth of {} from {}",
+		header.hash(),
+		header.height,
+		max_depth,
+		head.height
+	);
+	Err(ErrorKind::ReorgTooDeep(max_depth).into())
+}
+
+// Validate only the proof of work in a block header.
+// Used to cheaply validate pow before checking if orphan or continuing block validation.
+fn validate_pow_only(header: &BlockHeader, ctx: &mut BlockContext<'_>) -> Result<(), Error> {
 	if ctx.opts.contains(Options::SKIP_POW) {
 		// Some of our tests require this check to be skipped (we should revisit this).
 		return Ok(());
@@ -100,6 +219,9 @@ pub fn process_block(b: &Block, ctx: &mut BlockContext<'_>) -> Result<Option<Tip
 	// Check if we have already processed this block previously.
 	check_known(&b.header, ctx)?;
 
+	// Reject invalid blocks (and their descendants) before anything else.
+	check_not_invalid(&b.header, ctx)?;
+
 	// Quick pow validation. No point proceeding if this is invalid.
 	// We want to do this before we add the block to the orphan pool so we
 	// want to do this now and not later during header validation.
@@ -126,6 +248,9 @@ pub fn process_block(b: &Block, ctx: &mut BlockContext<'_>) -> Result<Option<Tip
 		}
 	}
 
+	// Refuse to switch to a fork forking off too far back from our head.
+	check_reorg_depth(&b.header, &head, ctx)?;
+
 	// Process the header for the block.
 	// Note: We still want to process the full block if we have seen this header before
 	// as we may have processed it "header first" and not yet processed the full block.
@@ -228,6 +353,9 @@ pub fn sync_block_headers(
 		add_block_header(header, &ctx.batch)?;
 	}
 
+	// Refuse to switch the sync chain to a fork forking off too far back.
+	check_reorg_depth(last_header, &sync_head, ctx)?;
+
 	// Now apply this entire chunk of headers to the sync MMR (ctx is sync MMR specific).
 	txhashset::header_extending(&mut ctx.header_pmmr, &mut ctx.batch, |ext, batch| {
 		rewind_and_apply_header_fork(&last_header, ext, batch)?;
@@ -266,6 +394,13 @@ pub fn process_block_header(header: &BlockHeader, ctx: &mut BlockContext<'_>) ->
 		}
 	}
 
+	// Check pow and difficulty first, only a valid header may be refused
+	// (and reported) as a reorg too deep.
+	validate_header(header, ctx)?;
+
+	// Refuse to switch the header chain to a fork forking off too far back.
+	check_reorg_depth(header, &header_head, ctx)?;
+
 	txhashset::header_extending(&mut ctx.header_pmmr, &mut ctx.batch, |ext, batch| {
 		rewind_and_apply_header_fork(&prev_header, ext, batch)?;
 		ext.validate_root(header)?;
@@ -276,7 +411,6 @@ pub fn process_block_header(header: &BlockHeader, ctx: &mut BlockContext<'_>) ->
 		Ok(())
 	})?;
 
-	validate_header(header, ctx)?;
 	add_block_header(header, &ctx.batch)?;
 
 	Ok(())
@@ -299,6 +433,11 @@ fn check_known_store(header: &BlockHeader, ctx: &mut BlockContext<'_>) -> Result
 	match ctx.batch.block_exists(&header.hash()) {
 		Ok(true) => {
 			let head = ctx.batch.head()?;
+			// A known block with more work than our head was left off-chain
+			// (by a chain rewind), process it again to switch back to it.
+			if has_more_work(header, &head) {
+				return Ok(());
+			}
 			if header.height < head.height.saturating_sub(50) {
 				// TODO - we flag this as an "abusive peer" but only in the case
 				// where we have the full block in our store.
@@ -338,6 +477,9 @@ fn validate_header(header: &BlockHeader, ctx: &mut BlockContext<'_>) -> Result<(
 	// First I/O cost, delayed as late as possible.
 	let prev = prev_header_store(header, &mut ctx.batch)?;
 
+	// Reject headers on an invalidated chain.
+	check_not_invalid(header, ctx)?;
+
 	// This header height must increase the height from the previous header by exactly 1.
 	if header.height != prev.height + 1 {
 		return Err(ErrorKind::InvalidBlockHeight.into());
@@ -425,7 +567,7 @@ fn verify_coinbase_maturity(
 
 /// Verify kernel sums across the full utxo and kernel sets based on block_sums
 /// of previous block accounting for the inputs|outputs|kernels of the new block.
-fn verify_block_sums(b: &Block, batch: &store::Batch<'_>) -> Result<BlockSums, Error> {
+pub(crate) fn verify_block_sums(b: &Block, batch: &store::Batch<'_>) -> Result<BlockSums, Error> {
 	// Retrieve the block_sums for the previous block.
 	let block_sums = batch.get_block_sums(&b.header.prev_hash)?;
 
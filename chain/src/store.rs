@@ -19,11 +19,12 @@ use crate::core::core::hash::{Hash, Hashed};
 use crate::core::core::{Block, BlockHeader, BlockSums};
 use crate::core::pow::Difficulty;
 use crate::core::ser::ProtocolVersion;
-use crate::types::{CommitPos, Tip};
+use crate::types::{CommitPos, OutputHistory, Tip, ValidationCheckpoint};
 use crate::util::secp::pedersen::Commitment;
 use croaring::Bitmap;
 use mimble_store as store;
 use mimble_store::{option_to_not_found, to_key, Error, SerIterator};
+use std::collections::HashSet;
 use std::convert::TryInto;
 use std::sync::Arc;
 
@@ -37,6 +38,16 @@ const OUTPUT_POS_PREFIX: u8 = b'p';
 const BLOCK_INPUT_BITMAP_PREFIX: u8 = b'B';
 const BLOCK_SUMS_PREFIX: u8 = b'M';
 const BLOCK_SPENT_PREFIX: u8 = b'S';
+const KERNEL_POS_PREFIX: u8 = b'K';
+const KERNEL_POS_INDEX_PREFIX: u8 = b'k';
+const OUTPUT_HISTORY_PREFIX: u8 = b'O';
+const OUTPUT_HISTORY_INDEX_PREFIX: u8 = b'o';
+const INVALID_BLOCK_PREFIX: u8 = b'V';
+const ACCEPTED_REORG_PREFIX: u8 = b'R';
+const CHAIN_TIP_PREFIX: u8 = b'C';
+const VALIDATION_CHECKPOINT_PREFIX: u8 = b'v';
+const NRD_KERNEL_PREFIX: u8 = b'N';
+const NRD_KERNEL_INDEX_PREFIX: u8 = b'n';
 
 /// All chain-related database operations
 pub struct ChainStore {
@@ -63,6 +74,11 @@ impl ChainStore {
 }
 
 impl ChainStore {
+	/// LMDB map size and used space of the chain db, in bytes.
+	pub fn map_usage(&self) -> Result<(usize, usize), Error> {
+		self.db.map_usage()
+	}
+
 	/// The current chain head.
 	pub fn head(&self) -> Result<Tip, Error> {
 		option_to_not_found(self.db.get_ser(&[HEAD_PREFIX]), || "HEAD".to_owned())
@@ -127,6 +143,63 @@ impl ChainStore {
 		)
 	}
 
+	/// Get kernel MMR pos and block height of the most recent kernel with the
+	/// given kernel excess.
+	/// Only available if the (optional) kernel_pos index is maintained.
+	pub fn get_kernel_pos_height(&self, excess: &Commitment) -> Result<CommitPos, Error> {
+		let history: Option<Vec<CommitPos>> = self
+			.db
+			.get_ser(&to_key(KERNEL_POS_PREFIX, &mut excess.as_ref().to_vec()))?;
+		option_to_not_found(Ok(history.and_then(|mut h| h.pop())), || {
+			format!("Kernel position for: {:?}", excess)
+		})
+	}
+
+	/// Has the kernel_pos index been fully built (and maintained since)?
+	pub fn is_kernel_pos_index_built(&self) -> Result<bool, Error> {
+		self.db.exists(&[KERNEL_POS_INDEX_PREFIX])
+	}
+
+	/// Get the history (creation and spend) of all outputs with the given commitment.
+	/// Only available if the (optional) output history index is maintained.
+	pub fn get_output_history(&self, commit: &Commitment) -> Result<Vec<OutputHistory>, Error> {
+		option_to_not_found(
+			self.db.get_ser(&to_key(
+				OUTPUT_HISTORY_PREFIX,
+				&mut commit.as_ref().to_vec(),
+			)),
+			|| format!("Output history for: {:?}", commit),
+		)
+	}
+
+	/// Has the output history index been fully built (and maintained since)?
+	pub fn is_output_history_index_built(&self) -> Result<bool, Error> {
+		self.db.exists(&[OUTPUT_HISTORY_INDEX_PREFIX])
+	}
+
+	/// Hashes of the blocks explicitly marked as invalid.
+	pub fn invalid_blocks(&self) -> Result<Vec<Hash>, Error> {
+		let key = to_key(INVALID_BLOCK_PREFIX, &mut "".to_string().into_bytes());
+		Ok(self.db.iter::<Hash>(&key)?.map(|(_, h)| h).collect())
+	}
+
+	/// Hashes of the forks accepted regardless of the maximum reorg depth.
+	pub fn accepted_reorgs(&self) -> Result<Vec<Hash>, Error> {
+		let key = to_key(ACCEPTED_REORG_PREFIX, &mut "".to_string().into_bytes());
+		Ok(self.db.iter::<Hash>(&key)?.map(|(_, h)| h).collect())
+	}
+
+	/// All known chain tips, the headers without any known child.
+	pub fn chain_tips(&self) -> Result<Vec<Tip>, Error> {
+		let key = to_key(CHAIN_TIP_PREFIX, &mut "".to_string().into_bytes());
+		Ok(self.db.iter::<Tip>(&key)?.map(|(_, t)| t).collect())
+	}
+
+	/// Checkpoint of an interrupted full chain validation, if any.
+	pub fn get_validation_checkpoint(&self) -> Result<Option<ValidationCheckpoint>, Error> {
+		self.db.get_ser(&[VALIDATION_CHECKPOINT_PREFIX])
+	}
+
 	/// Builds a new batch to be used with this store.
 	pub fn batch(&self) -> Result<Batch<'_>, Error> {
 		Ok(Batch {
@@ -231,14 +304,67 @@ impl<'a> Batch<'a> {
 	/// Save block header to db.
 	pub fn save_block_header(&self, header: &BlockHeader) -> Result<(), Error> {
 		let hash = header.hash();
+		let key = to_key(BLOCK_HEADER_PREFIX, &mut hash.to_vec());
+		let is_new = self.db.get_ser::<BlockHeader>(&key)?.is_none();
 
 		// Store the header itself indexed by hash.
-		self.db
-			.put_ser(&to_key(BLOCK_HEADER_PREFIX, &mut hash.to_vec())[..], header)?;
+		self.db.put_ser(&key[..], header)?;
+
+		// A new header is a chain tip, and its parent no longer is.
+		if is_new {
+			self.save_chain_tip(header)?;
+			let prev_key = to_key(CHAIN_TIP_PREFIX, &mut header.prev_hash.to_vec());
+			if self.db.get_ser::<Tip>(&prev_key)?.is_some() {
+				self.db.delete(&prev_key)?;
+			}
+		}
 
 		Ok(())
 	}
 
+	fn save_chain_tip(&self, header: &BlockHeader) -> Result<(), Error> {
+		self.db.put_ser(
+			&to_key(CHAIN_TIP_PREFIX, &mut header.hash().to_vec())[..],
+			&Tip::from_header(header),
+		)
+	}
+
+	/// Rebuild the chain tips from all the headers we know about. Only needed
+	/// for databases created before the tips were tracked.
+	pub fn rebuild_chain_tips(&self) -> Result<usize, Error> {
+		let key = to_key(BLOCK_HEADER_PREFIX, &mut "".to_string().into_bytes());
+		let parents: HashSet<Hash> = self
+			.db
+			.iter::<BlockHeader>(&key)?
+			.map(|(_, h)| h.prev_hash)
+			.collect();
+		let mut count = 0;
+		for (_, header) in self.db.iter::<BlockHeader>(&key)? {
+			if !parents.contains(&header.hash()) {
+				self.save_chain_tip(&header)?;
+				count += 1;
+			}
+		}
+		Ok(count)
+	}
+
+	/// Remove the chain tips below the given height, forks that old can't be
+	/// reorged to anymore.
+	pub fn prune_chain_tips(&self, height: u64) -> Result<usize, Error> {
+		let key = to_key(CHAIN_TIP_PREFIX, &mut "".to_string().into_bytes());
+		let old_tips: Vec<Hash> = self
+			.db
+			.iter::<Tip>(&key)?
+			.filter(|(_, t)| t.height < height)
+			.map(|(_, t)| t.last_block_h)
+			.collect();
+		for hash in &old_tips {
+			self.db
+				.delete(&to_key(CHAIN_TIP_PREFIX, &mut hash.to_vec()))?;
+		}
+		Ok(old_tips.len())
+	}
+
 	/// Save output_pos and block height to index.
 	pub fn save_output_pos_height(
 		&self,
@@ -286,6 +412,184 @@ impl<'a> Batch<'a> {
 		)
 	}
 
+	/// Save kernel MMR pos and block height to the kernel_pos index.
+	/// Earlier kernels with the same excess are kept so they can be restored
+	/// when the block of the more recent one is rewound.
+	pub fn save_kernel_pos_height(
+		&self,
+		excess: &Commitment,
+		pos: u64,
+		height: u64,
+	) -> Result<(), Error> {
+		let mut history = self.get_kernel_pos_history(excess)?;
+		history.push(CommitPos { pos, height });
+		self.db.put_ser(
+			&to_key(KERNEL_POS_PREFIX, &mut excess.as_ref().to_vec())[..],
+			&history,
+		)
+	}
+
+	/// Rewind the kernel_pos index entries for the given excess to the provided
+	/// kernel MMR size, restoring the previous kernel with this excess (if any).
+	pub fn rewind_kernel_pos_height(
+		&self,
+		excess: &Commitment,
+		kernel_mmr_size: u64,
+	) -> Result<(), Error> {
+		let mut history = self.get_kernel_pos_history(excess)?;
+		if history.is_empty() {
+			return Ok(());
+		}
+		history.retain(|x| x.pos <= kernel_mmr_size);
+		let key = to_key(KERNEL_POS_PREFIX, &mut excess.as_ref().to_vec());
+		if history.is_empty() {
+			self.db.delete(&key)
+		} else {
+			self.db.put_ser(&key[..], &history)
+		}
+	}
+
+	/// Get kernel MMR pos and block height of the most recent kernel with the
+	/// given excess from the kernel_pos index.
+	pub fn get_kernel_pos_height(&self, excess: &Commitment) -> Result<CommitPos, Error> {
+		let mut history = self.get_kernel_pos_history(excess)?;
+		option_to_not_found(Ok(history.pop()), || {
+			format!("Kernel position for excess: {:?}", excess)
+		})
+	}
+
+	/// Get the pos and height of all kernels with the given excess, in the
+	/// order they were applied to the kernel MMR.
+	fn get_kernel_pos_history(&self, excess: &Commitment) -> Result<Vec<CommitPos>, Error> {
+		let history = self
+			.db
+			.get_ser(&to_key(KERNEL_POS_PREFIX, &mut excess.as_ref().to_vec()))?;
+		Ok(history.unwrap_or_default())
+	}
+
+	/// Iterator over the kernel_pos index.
+	pub fn kernel_pos_iter(&self) -> Result<SerIterator<Vec<CommitPos>>, Error> {
+		let key = to_key(KERNEL_POS_PREFIX, &mut "".to_string().into_bytes());
+		self.db.iter(&key)
+	}
+
+	/// Has the kernel_pos index been fully built (and maintained since)?
+	pub fn is_kernel_pos_index_built(&self) -> Result<bool, Error> {
+		self.db.exists(&[KERNEL_POS_INDEX_PREFIX])
+	}
+
+	/// Flag the kernel_pos index as fully built.
+	pub fn save_kernel_pos_index_built(&self) -> Result<(), Error> {
+		self.db.put_ser(&[KERNEL_POS_INDEX_PREFIX], &1u8)
+	}
+
+	/// Flag the kernel_pos index as stale, forcing a full rebuild next time
+	/// it is enabled.
+	pub fn delete_kernel_pos_index_built(&self) -> Result<(), Error> {
+		self.db.delete(&[KERNEL_POS_INDEX_PREFIX])
+	}
+
+	/// Save the history of all outputs with the given commitment.
+	pub fn save_output_history(
+		&self,
+		commit: &Commitment,
+		history: &Vec<OutputHistory>,
+	) -> Result<(), Error> {
+		self.db.put_ser(
+			&to_key(OUTPUT_HISTORY_PREFIX, &mut commit.as_ref().to_vec())[..],
+			history,
+		)
+	}
+
+	/// Delete the output history for the given commitment.
+	pub fn delete_output_history(&self, commit: &Commitment) -> Result<(), Error> {
+		self.db.delete(&to_key(
+			OUTPUT_HISTORY_PREFIX,
+			&mut commit.as_ref().to_vec(),
+		))
+	}
+
+	/// Get the history of all outputs with the given commitment.
+	/// Returns an empty vec if the commitment has never been seen.
+	pub fn get_output_history(&self, commit: &Commitment) -> Result<Vec<OutputHistory>, Error> {
+		let history = self.db.get_ser(&to_key(
+			OUTPUT_HISTORY_PREFIX,
+			&mut commit.as_ref().to_vec(),
+		))?;
+		Ok(history.unwrap_or_default())
+	}
+
+	/// Iterator over the output history index.
+	pub fn output_history_iter(&self) -> Result<SerIterator<Vec<OutputHistory>>, Error> {
+		let key = to_key(OUTPUT_HISTORY_PREFIX, &mut "".to_string().into_bytes());
+		self.db.iter(&key)
+	}
+
+	/// Has the output history index been fully built (and maintained since)?
+	pub fn is_output_history_index_built(&self) -> Result<bool, Error> {
+		self.db.exists(&[OUTPUT_HISTORY_INDEX_PREFIX])
+	}
+
+	/// Flag the output history index as fully built.
+	pub fn save_output_history_index_built(&self) -> Result<(), Error> {
+		self.db.put_ser(&[OUTPUT_HISTORY_INDEX_PREFIX], &1u8)
+	}
+
+	/// Flag the output history index as stale, forcing a full rebuild next time
+	/// it is enabled.
+	pub fn delete_output_history_index_built(&self) -> Result<(), Error> {
+		self.db.delete(&[OUTPUT_HISTORY_INDEX_PREFIX])
+	}
+
+	/// Save the pos and height of all NRD kernels with the given excess commitment.
+	pub fn save_nrd_kernel_history(
+		&self,
+		excess: &Commitment,
+		history: &Vec<CommitPos>,
+	) -> Result<(), Error> {
+		self.db.put_ser(
+			&to_key(NRD_KERNEL_PREFIX, &mut excess.as_ref().to_vec())[..],
+			history,
+		)
+	}
+
+	/// Delete the NRD kernel history for the given excess commitment.
+	pub fn delete_nrd_kernel_history(&self, excess: &Commitment) -> Result<(), Error> {
+		self.db
+			.delete(&to_key(NRD_KERNEL_PREFIX, &mut excess.as_ref().to_vec()))
+	}
+
+	/// Get the pos and height of all NRD kernels with the given excess commitment,
+	/// in the order they were applied to the kernel MMR.
+	/// Returns an empty vec if no NRD kernel with this excess has been seen.
+	pub fn get_nrd_kernel_history(&self, excess: &Commitment) -> Result<Vec<CommitPos>, Error> {
+		let history = self
+			.db
+			.get_ser(&to_key(NRD_KERNEL_PREFIX, &mut excess.as_ref().to_vec()))?;
+		Ok(history.unwrap_or_default())
+	}
+
+	/// Iterator over the NRD kernel index.
+	pub fn nrd_kernel_iter(&self) -> Result<SerIterator<Vec<CommitPos>>, Error> {
+		let key = to_key(NRD_KERNEL_PREFIX, &mut "".to_string().into_bytes());
+		self.db.iter(&key)
+	}
+
+	/// Has the NRD kernel index been fully built (and maintained since)?
+	pub fn is_nrd_kernel_index_built(&self) -> Result<bool, Error> {
+		self.db.exists(&[NRD_KERNEL_INDEX_PREFIX])
+	}
+
+	/// Flag the NRD kernel index as fully built.
+	pub fn save_nrd_kernel_index_built(&self) -> Result<(), Error> {
+		self.db.put_ser(&[NRD_KERNEL_INDEX_PREFIX], &1u8)
+	}
+
+	/// Flag the NRD kernel index as stale, forcing a full rebuild.
+	pub fn delete_nrd_kernel_index_built(&self) -> Result<(), Error> {
+		self.db.delete(&[NRD_KERNEL_INDEX_PREFIX])
+	}
+
 	/// Get the previous header.
 	pub fn get_previous_header(&self, header: &BlockHeader) -> Result<BlockHeader, Error> {
 		self.get_block_header(&header.prev_hash)
@@ -384,6 +688,54 @@ impl<'a> Batch<'a> {
 		let key = to_key(BLOCK_PREFIX, &mut "".to_string().into_bytes());
 		self.db.iter(&key)
 	}
+
+	/// All known chain tips, the headers without any known child.
+	pub fn chain_tips(&self) -> Result<Vec<Tip>, Error> {
+		let key = to_key(CHAIN_TIP_PREFIX, &mut "".to_string().into_bytes());
+		Ok(self.db.iter::<Tip>(&key)?.map(|(_, t)| t).collect())
+	}
+
+	/// Mark the block as invalid, it will be rejected along with its descendants.
+	pub fn save_invalid_block(&self, h: &Hash) -> Result<(), Error> {
+		self.db
+			.put_ser(&to_key(INVALID_BLOCK_PREFIX, &mut h.to_vec())[..], h)
+	}
+
+	/// Accept the fork at this block regardless of the maximum reorg depth.
+	pub fn save_accepted_reorg(&self, h: &Hash) -> Result<(), Error> {
+		self.db
+			.put_ser(&to_key(ACCEPTED_REORG_PREFIX, &mut h.to_vec())[..], h)
+	}
+
+	/// Remove the invalid mark of the block.
+	pub fn delete_invalid_block(&self, h: &Hash) -> Result<(), Error> {
+		self.db
+			.delete(&to_key(INVALID_BLOCK_PREFIX, &mut h.to_vec()))
+	}
+
+	/// Whether the block has been marked as invalid.
+	pub fn is_invalid_block(&self, h: &Hash) -> Result<bool, Error> {
+		let res: Option<Hash> = self
+			.db
+			.get_ser(&to_key(INVALID_BLOCK_PREFIX, &mut h.to_vec()))?;
+		Ok(res.is_some())
+	}
+
+	/// Save the checkpoint of the running full chain validation.
+	pub fn save_validation_checkpoint(
+		&self,
+		checkpoint: &ValidationCheckpoint,
+	) -> Result<(), Error> {
+		self.db.put_ser(&[VALIDATION_CHECKPOINT_PREFIX], checkpoint)
+	}
+
+	/// Delete the full chain validation checkpoint, once the validation is over.
+	pub fn delete_validation_checkpoint(&self) -> Result<(), Error> {
+		if self.db.exists(&[VALIDATION_CHECKPOINT_PREFIX])? {
+			self.db.delete(&[VALIDATION_CHECKPOINT_PREFIX])?;
+		}
+		Ok(())
+	}
 }
 
 /// An iterator on blocks, from latest to earliest, specialized to return
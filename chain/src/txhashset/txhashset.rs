@@ -19,18 +19,23 @@ use crate::core::core::committed::Committed;
 use crate::core::core::hash::{Hash, Hashed};
 use crate::core::core::merkle_proof::MerkleProof;
 use crate::core::core::pmmr::{self, Backend, ReadonlyPMMR, RewindablePMMR, PMMR};
-use crate::core::core::{Block, BlockHeader, Input, Output, OutputIdentifier, TxKernel};
+use crate::core::core::{
+	Block, BlockHeader, Input, KernelFeatures, Output, OutputIdentifier, TxKernel,
+};
 use crate::core::ser::{PMMRIndexHashable, PMMRable, ProtocolVersion};
 use crate::error::{Error, ErrorKind};
 use crate::store::{Batch, ChainStore};
 use crate::txhashset::bitmap_accumulator::BitmapAccumulator;
 use crate::txhashset::{RewindableKernelView, UTXOView};
-use crate::types::{CommitPos, OutputRoots, Tip, TxHashSetRoots, TxHashsetWriteStatus};
+use crate::types::{
+	CommitPos, OutputHistory, OutputRoots, Tip, TxHashSetRoots, TxHashsetWriteStatus,
+};
 use crate::util::secp::pedersen::{Commitment, RangeProof};
 use crate::util::{file, secp_static, zip};
 use croaring::Bitmap;
 use mimble_store;
 use mimble_store::pmmr::{clean_files_by_prefix, PMMRBackend};
+use rayon::prelude::*;
 use std::fs::{self, File};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -44,6 +49,12 @@ const KERNEL_SUBDIR: &str = "kernel";
 
 const TXHASHSET_ZIP: &str = "txhashset_snapshot";
 
+/// Number of kernel signatures verified per (parallel) batch.
+const VALIDATION_KERNEL_BATCH_SIZE: usize = 5_000;
+
+/// Number of rangeproofs verified per (parallel) batch.
+const VALIDATION_RPROOF_BATCH_SIZE: usize = 1_000;
+
 /// Convenience wrapper around a single prunable MMR backend.
 pub struct PMMRHandle<T: PMMRable> {
 	/// The backend storage for the MMR.
@@ -120,6 +131,12 @@ pub struct TxHashSet {
 
 	// chain store used as index of commitments to MMR positions
 	commit_index: Arc<ChainStore>,
+
+	// maintain the (optional) kernel_pos index of kernel excess to MMR positions
+	kernel_pos_index: bool,
+
+	// maintain the (optional) output history index of output creation and spend
+	output_history_index: bool,
 }
 
 impl TxHashSet {
@@ -198,6 +215,8 @@ impl TxHashSet {
 				kernel_pmmr_h,
 				bitmap_accumulator,
 				commit_index,
+				kernel_pos_index: false,
+				output_history_index: false,
 			})
 		} else {
 			Err(ErrorKind::TxHashSetErr("failed to open kernel PMMR".to_string()).into())
@@ -213,6 +232,28 @@ impl TxHashSet {
 		Ok(bitmap_accumulator)
 	}
 
+	/// Enable (or disable) maintenance of the kernel_pos index when applying
+	/// and rewinding blocks.
+	pub fn set_kernel_pos_index(&mut self, enabled: bool) {
+		self.kernel_pos_index = enabled;
+	}
+
+	/// Is the kernel_pos index being maintained?
+	pub fn kernel_pos_index(&self) -> bool {
+		self.kernel_pos_index
+	}
+
+	/// Enable (or disable) maintenance of the output history index when applying
+	/// and rewinding blocks.
+	pub fn set_output_history_index(&mut self, enabled: bool) {
+		self.output_history_index = enabled;
+	}
+
+	/// Is the output history index being maintained?
+	pub fn output_history_index(&self) -> bool {
+		self.output_history_index
+	}
+
 	/// Close all backend file handles
 	pub fn release_backend_files(&mut self) {
 		self.output_pmmr_h.backend.release_files();
@@ -328,6 +369,28 @@ impl TxHashSet {
 		None
 	}
 
+	/// Find a kernel with a given excess via the kernel_pos index.
+	/// Returns the most recent kernel with this excess along with its MMR pos and block height.
+	/// Only valid if the kernel_pos index is being maintained.
+	pub fn find_kernel_pos(
+		&self,
+		excess: &Commitment,
+	) -> Result<Option<(TxKernel, CommitPos)>, Error> {
+		let kernel_pos = match self.commit_index.get_kernel_pos_height(excess) {
+			Ok(kernel_pos) => kernel_pos,
+			Err(mimble_store::Error::NotFoundErr(_)) => return Ok(None),
+			Err(e) => return Err(e.into()),
+		};
+		if kernel_pos.pos > self.kernel_pmmr_h.last_pos {
+			return Ok(None);
+		}
+		let pmmr = ReadonlyPMMR::at(&self.kernel_pmmr_h.backend, self.kernel_pmmr_h.last_pos);
+		match pmmr.get_data(kernel_pos.pos) {
+			Some(kernel) if &kernel.excess == excess => Ok(Some((kernel, kernel_pos))),
+			_ => Ok(None),
+		}
+	}
+
 	/// Get MMR roots.
 	pub fn roots(&self) -> TxHashSetRoots {
 		let output_pmmr =
@@ -468,6 +531,258 @@ impl TxHashSet {
 		);
 		Ok(())
 	}
+
+	/// Check the output_pos index against the current UTXO set without changing
+	/// it. Returns the number of stale entries (not pointing to the expected
+	/// output) and the number of unspent outputs without an entry.
+	pub fn check_output_pos_index(&self, batch: &Batch<'_>) -> Result<(u64, u64), Error> {
+		let output_pmmr =
+			ReadonlyPMMR::at(&self.output_pmmr_h.backend, self.output_pmmr_h.last_pos);
+
+		let mut stale_count = 0;
+		for (key, (pos, _)) in batch.output_pos_iter()? {
+			let is_valid = output_pmmr.get_data(pos).map_or(false, |out| {
+				batch.get_output_pos(&out.commitment()).ok() == Some(pos)
+					&& batch.is_match_output_pos_key(&key, &out.commitment())
+			});
+			if !is_valid {
+				stale_count += 1;
+			}
+		}
+
+		let mut missing_count = 0;
+		for pos in output_pmmr.leaf_pos_iter() {
+			if let Some(out) = output_pmmr.get_data(pos) {
+				if batch.get_output_pos_height(&out.commit).is_err() {
+					missing_count += 1;
+				}
+			}
+		}
+		Ok((stale_count, missing_count))
+	}
+
+	/// Leaf positions of the output and rangeproof MMRs still in their leaf set
+	/// but whose data was pruned or compacted, by MMR name.
+	pub fn check_leaf_sets(&self) -> Vec<(&'static str, u64)> {
+		let mut res: Vec<_> = self
+			.output_pmmr_h
+			.backend
+			.inconsistent_leaves()
+			.into_iter()
+			.map(|pos| ("output", pos))
+			.collect();
+		res.extend(
+			self.rproof_pmmr_h
+				.backend
+				.inconsistent_leaves()
+				.into_iter()
+				.map(|pos| ("rangeproof", pos)),
+		);
+		res
+	}
+
+	/// Whether the bitmap accumulator matches the current output MMR.
+	pub fn check_bitmap_accumulator(&self) -> Result<bool, Error> {
+		let rebuilt = TxHashSet::bitmap_accumulator(&self.output_pmmr_h)?;
+		Ok(rebuilt.root() == self.bitmap_accumulator.root())
+	}
+
+	/// Rebuild the bitmap accumulator from the current output MMR.
+	pub fn init_bitmap_accumulator(&mut self) -> Result<(), Error> {
+		self.bitmap_accumulator = TxHashSet::bitmap_accumulator(&self.output_pmmr_h)?;
+		Ok(())
+	}
+
+	/// Build the kernel_pos index from the full kernel MMR if it has not been built yet.
+	/// Once built the index is maintained as blocks are applied and rewound, so this
+	/// is a no-op on subsequent calls unless the index was flagged as stale.
+	pub fn init_kernel_pos_index(
+		&self,
+		header_pmmr: &PMMRHandle<BlockHeader>,
+		batch: &Batch<'_>,
+	) -> Result<(), Error> {
+		if batch.is_kernel_pos_index_built()? {
+			return Ok(());
+		}
+
+		let now = Instant::now();
+
+		// The index is not trusted, clear out all existing entries.
+		let mut removed_count = 0;
+		for (key, _) in batch.kernel_pos_iter()? {
+			batch.delete(&key)?;
+			removed_count += 1;
+		}
+		debug!(
+			"init_kernel_pos_index: removed {} stale index entries",
+			removed_count
+		);
+
+		let kernel_pmmr =
+			ReadonlyPMMR::at(&self.kernel_pmmr_h.backend, self.kernel_pmmr_h.last_pos);
+		let max_height = batch.head()?.height;
+
+		// Kernels are indexed in MMR order so the most recent kernel wins
+		// in the (unlikely) case of duplicate excess commitments.
+		let mut pos = 1;
+		let mut kernel_count = 0;
+		for height in 0..=max_height {
+			let hash = header_pmmr.get_header_hash_by_height(height)?;
+			let h = batch.get_block_header(&hash)?;
+			while pos <= h.kernel_mmr_size {
+				if pmmr::is_leaf(pos) {
+					if let Some(kernel) = kernel_pmmr.get_data(pos) {
+						batch.save_kernel_pos_height(&kernel.excess, pos, h.height)?;
+						kernel_count += 1;
+					}
+				}
+				pos += 1;
+			}
+		}
+
+		batch.save_kernel_pos_index_built()?;
+
+		debug!(
+			"init_kernel_pos_index: added entries for {} kernels, took {}s",
+			kernel_count,
+			now.elapsed().as_secs(),
+		);
+		Ok(())
+	}
+
+	/// Build the NRD kernel index from the full kernel MMR if it has not been built yet.
+	/// NRD kernels cannot be validated without this index so, unlike the optional
+	/// indices, it is always maintained as blocks are applied and rewound.
+	pub fn init_nrd_kernel_index(
+		&self,
+		header_pmmr: &PMMRHandle<BlockHeader>,
+		batch: &Batch<'_>,
+	) -> Result<(), Error> {
+		if batch.is_nrd_kernel_index_built()? {
+			return Ok(());
+		}
+
+		let now = Instant::now();
+
+		// The index is not trusted, clear out all existing entries.
+		let mut removed_count = 0;
+		for (key, _) in batch.nrd_kernel_iter()? {
+			batch.delete(&key)?;
+			removed_count += 1;
+		}
+		debug!(
+			"init_nrd_kernel_index: removed {} stale index entries",
+			removed_count
+		);
+
+		let kernel_pmmr =
+			ReadonlyPMMR::at(&self.kernel_pmmr_h.backend, self.kernel_pmmr_h.last_pos);
+		let max_height = batch.head()?.height;
+
+		let mut pos = 1;
+		let mut kernel_count = 0;
+		for height in 0..=max_height {
+			let hash = header_pmmr.get_header_hash_by_height(height)?;
+			let h = batch.get_block_header(&hash)?;
+			while pos <= h.kernel_mmr_size {
+				if pmmr::is_leaf(pos) {
+					if let Some(kernel) = kernel_pmmr.get_data(pos) {
+						if kernel.is_nrd() {
+							let mut history = batch.get_nrd_kernel_history(&kernel.excess)?;
+							history.push(CommitPos {
+								pos,
+								height: h.height,
+							});
+							batch.save_nrd_kernel_history(&kernel.excess, &history)?;
+							kernel_count += 1;
+						}
+					}
+				}
+				pos += 1;
+			}
+		}
+
+		batch.save_nrd_kernel_index_built()?;
+
+		debug!(
+			"init_nrd_kernel_index: added entries for {} kernels, took {}s",
+			kernel_count,
+			now.elapsed().as_secs(),
+		);
+		Ok(())
+	}
+
+	/// Build the output history index by replaying all full blocks in the db.
+	/// This requires the full block history and is only supported in archive mode.
+	/// Once built the index is maintained as blocks are applied and rewound, so this
+	/// is a no-op on subsequent calls unless the index was flagged as stale.
+	pub fn init_output_history_index(
+		&self,
+		header_pmmr: &PMMRHandle<BlockHeader>,
+		batch: &Batch<'_>,
+	) -> Result<(), Error> {
+		if batch.is_output_history_index_built()? {
+			return Ok(());
+		}
+
+		let now = Instant::now();
+
+		// The index is not trusted, clear out all existing entries.
+		let mut removed_count = 0;
+		for (key, _) in batch.output_history_iter()? {
+			batch.delete(&key)?;
+			removed_count += 1;
+		}
+		debug!(
+			"init_output_history_index: removed {} stale index entries",
+			removed_count
+		);
+
+		let max_height = batch.head()?.height;
+
+		let mut output_count = 0;
+		let mut prev_output_mmr_size = 0;
+		for height in 0..=max_height {
+			let hash = header_pmmr.get_header_hash_by_height(height)?;
+			let block = batch.get_block(&hash)?;
+
+			// Outputs are appended to the output MMR in block order.
+			let prev_leaves = pmmr::n_leaves(prev_output_mmr_size);
+			for (i, out) in block.outputs().iter().enumerate() {
+				let pos = pmmr::insertion_to_pmmr_index(prev_leaves + i as u64 + 1);
+				let mut history = batch.get_output_history(&out.commitment())?;
+				history.push(OutputHistory {
+					pos,
+					height,
+					spent_height: None,
+					spent_block: None,
+				});
+				batch.save_output_history(&out.commitment(), &history)?;
+				output_count += 1;
+			}
+
+			// An input always spends the most recent unspent output with that commitment.
+			for input in block.inputs() {
+				let mut history = batch.get_output_history(&input.commitment())?;
+				if let Some(entry) = history.iter_mut().rev().find(|x| !x.is_spent()) {
+					entry.spent_height = Some(height);
+					entry.spent_block = Some(hash);
+				}
+				batch.save_output_history(&input.commitment(), &history)?;
+			}
+
+			prev_output_mmr_size = block.header.output_mmr_size;
+		}
+
+		batch.save_output_history_index_built()?;
+
+		debug!(
+			"init_output_history_index: added entries for {} outputs, took {}s",
+			output_count,
+			now.elapsed().as_secs(),
+		);
+		Ok(())
+	}
 }
 
 /// Starts a new unit of work to extend (or rewind) the chain with additional
@@ -879,6 +1194,12 @@ pub struct Extension<'a> {
 
 	bitmap_accumulator: BitmapAccumulator,
 
+	/// Maintain the kernel_pos index?
+	kernel_pos_index: bool,
+
+	/// Maintain the output history index?
+	output_history_index: bool,
+
 	/// Rollback flag.
 	rollback: bool,
 }
@@ -928,6 +1249,8 @@ impl<'a> Extension<'a> {
 				trees.kernel_pmmr_h.last_pos,
 			),
 			bitmap_accumulator: trees.bitmap_accumulator.clone(),
+			kernel_pos_index: trees.kernel_pos_index,
+			output_history_index: trees.output_history_index,
 			rollback: false,
 		}
 	}
@@ -961,6 +1284,16 @@ impl<'a> Extension<'a> {
 			let pos = self.apply_output(out, batch)?;
 			affected_pos.push(pos);
 			batch.save_output_pos_height(&out.commitment(), pos, b.header.height)?;
+			if self.output_history_index {
+				let mut history = batch.get_output_history(&out.commitment())?;
+				history.push(OutputHistory {
+					pos,
+					height: b.header.height,
+					spent_height: None,
+					spent_block: None,
+				});
+				batch.save_output_history(&out.commitment(), &history)?;
+			}
 		}
 
 		// Remove the output from the output and rangeproof MMRs.
@@ -970,12 +1303,18 @@ impl<'a> Extension<'a> {
 			let spent_pos = self.apply_input(input, batch)?;
 			affected_pos.push(spent_pos.pos);
 			batch.delete_output_pos_height(&input.commitment())?;
+			if self.output_history_index {
+				let mut history = batch.get_output_history(&input.commitment())?;
+				if let Some(entry) = history.iter_mut().find(|x| x.pos == spent_pos.pos) {
+					entry.spent_height = Some(b.header.height);
+					entry.spent_block = Some(b.hash());
+				}
+				batch.save_output_history(&input.commitment(), &history)?;
+			}
 			spent.push(spent_pos);
 		}
 
-		for kernel in b.kernels() {
-			self.apply_kernel(kernel)?;
-		}
+		self.apply_kernels(b.kernels(), b.header.height, batch)?;
 
 		// Update our BitmapAccumulator based on affected outputs (both spent and created).
 		self.apply_to_bitmap_accumulator(&affected_pos)?;
@@ -1072,12 +1411,41 @@ impl<'a> Extension<'a> {
 		Ok(output_pos)
 	}
 
+	/// Apply the kernels in order at the given height.
+	/// Each kernel is added to the kernel_pos index (if enabled).
+	/// NRD kernels are verified against (and then added to) the NRD kernel index,
+	/// so an NRD kernel is also checked against any earlier kernel in the list.
+	pub fn apply_kernels(
+		&mut self,
+		kernels: &[TxKernel],
+		height: u64,
+		batch: &Batch<'_>,
+	) -> Result<(), Error> {
+		for kernel in kernels {
+			if kernel.is_nrd() {
+				verify_nrd_kernel(kernel, height, batch)?;
+			}
+			let pos = self.apply_kernel(kernel)?;
+			if self.kernel_pos_index {
+				batch.save_kernel_pos_height(&kernel.excess, pos, height)?;
+			}
+			if kernel.is_nrd() {
+				let mut history = batch.get_nrd_kernel_history(&kernel.excess)?;
+				history.push(CommitPos { pos, height });
+				batch.save_nrd_kernel_history(&kernel.excess, &history)?;
+			}
+		}
+		Ok(())
+	}
+
 	/// Push kernel onto MMR (hash and data files).
-	fn apply_kernel(&mut self, kernel: &TxKernel) -> Result<(), Error> {
-		self.kernel_pmmr
+	/// Returns the MMR pos of the kernel.
+	fn apply_kernel(&mut self, kernel: &TxKernel) -> Result<u64, Error> {
+		let pos = self
+			.kernel_pmmr
 			.push(kernel)
 			.map_err(|e| ErrorKind::TxHashSetErr(format!("pmmr push kernel error, {}", e)))?;
-		Ok(())
+		Ok(pos)
 	}
 
 	/// Build a Merkle proof for the given output and the block
@@ -1180,12 +1548,14 @@ impl<'a> Extension<'a> {
 			bitmap.iter().map(|x| x.into()).collect()
 		};
 
-		if header.height == 0 {
+		let (prev_output_mmr_size, prev_kernel_mmr_size) = if header.height == 0 {
 			self.rewind_mmrs_to_pos(0, 0, &spent_pos)?;
+			(0, 0)
 		} else {
 			let prev = batch.get_previous_header(&header)?;
 			self.rewind_mmrs_to_pos(prev.output_mmr_size, prev.kernel_mmr_size, &spent_pos)?;
-		}
+			(prev.output_mmr_size, prev.kernel_mmr_size)
+		};
 
 		// Update our BitmapAccumulator based on affected outputs.
 		// We want to "unspend" every rewound spent output.
@@ -1210,6 +1580,54 @@ impl<'a> Extension<'a> {
 			);
 		}
 
+		// Remove outputs created by the block being rewound from the output history index
+		// and "unspend" any outputs spent by this block.
+		if self.output_history_index {
+			for out in block.outputs() {
+				let mut history = batch.get_output_history(&out.commitment())?;
+				history.retain(|x| x.pos <= prev_output_mmr_size);
+				if history.is_empty() {
+					batch.delete_output_history(&out.commitment())?;
+				} else {
+					batch.save_output_history(&out.commitment(), &history)?;
+				}
+			}
+			let hash = header.hash();
+			for input in block.inputs() {
+				let mut history = batch.get_output_history(&input.commitment())?;
+				for entry in history.iter_mut() {
+					if entry.spent_block == Some(hash) {
+						entry.spent_height = None;
+						entry.spent_block = None;
+					}
+				}
+				batch.save_output_history(&input.commitment(), &history)?;
+			}
+		}
+
+		// Remove any entries from the kernel_pos index created by the block being rewound.
+		// Only remove entries pointing into the rewound part of the kernel MMR, an earlier
+		// kernel with the same excess becomes the indexed one again.
+		if self.kernel_pos_index {
+			for kernel in block.kernels() {
+				batch.rewind_kernel_pos_height(&kernel.excess, prev_kernel_mmr_size)?;
+			}
+		}
+
+		// Remove any entries from the NRD kernel index created by the block being rewound.
+		for kernel in block.kernels().iter().filter(|x| x.is_nrd()) {
+			let mut history = batch.get_nrd_kernel_history(&kernel.excess)?;
+			if history.is_empty() {
+				continue;
+			}
+			history.retain(|x| x.pos <= prev_kernel_mmr_size);
+			if history.is_empty() {
+				batch.delete_nrd_kernel_history(&kernel.excess)?;
+			} else {
+				batch.save_nrd_kernel_history(&kernel.excess, &history)?;
+			}
+		}
+
 		// Update output_pos based on "unspending" all spent pos from this block.
 		// This is necessary to ensure the output_pos index correclty reflects a
 		// reused output commitment. For example an output at pos 1, spent, reused at pos 2.
@@ -1295,15 +1713,9 @@ impl<'a> Extension<'a> {
 		let now = Instant::now();
 
 		// validate all hashes and sums within the trees
-		if let Err(e) = self.output_pmmr.validate() {
-			return Err(ErrorKind::InvalidTxHashSet(e).into());
-		}
-		if let Err(e) = self.rproof_pmmr.validate() {
-			return Err(ErrorKind::InvalidTxHashSet(e).into());
-		}
-		if let Err(e) = self.kernel_pmmr.validate() {
-			return Err(ErrorKind::InvalidTxHashSet(e).into());
-		}
+		self.validate_output_mmr()?;
+		self.validate_rproof_mmr()?;
+		self.validate_kernel_mmr()?;
 
 		debug!(
 			"txhashset: validated the output {}, rproof {}, kernel {} mmrs, took {}s",
@@ -1316,6 +1728,27 @@ impl<'a> Extension<'a> {
 		Ok(())
 	}
 
+	/// Validate all hashes and sums within the output MMR.
+	pub fn validate_output_mmr(&self) -> Result<(), Error> {
+		self.output_pmmr
+			.validate()
+			.map_err(|e| ErrorKind::InvalidTxHashSet(e).into())
+	}
+
+	/// Validate all hashes and sums within the rangeproof MMR.
+	pub fn validate_rproof_mmr(&self) -> Result<(), Error> {
+		self.rproof_pmmr
+			.validate()
+			.map_err(|e| ErrorKind::InvalidTxHashSet(e).into())
+	}
+
+	/// Validate all hashes and sums within the kernel MMR.
+	pub fn validate_kernel_mmr(&self) -> Result<(), Error> {
+		self.kernel_pmmr
+			.validate()
+			.map_err(|e| ErrorKind::InvalidTxHashSet(e).into())
+	}
+
 	/// Validate full kernel sums against the provided header (for overage and kernel_offset).
 	/// This is an expensive operation as we need to retrieve all the UTXOs and kernels
 	/// from the respective MMRs.
@@ -1449,6 +1882,112 @@ impl<'a> Extension<'a> {
 		Ok(())
 	}
 
+	/// Number of unspent outputs, hence of rangeproofs to verify.
+	pub fn n_unspent_outputs(&self) -> u64 {
+		self.output_pmmr.n_unpruned_leaves()
+	}
+
+	/// Number of kernels, hence of kernel signatures to verify.
+	pub fn n_kernels(&self) -> u64 {
+		pmmr::n_leaves(self.kernel_pmmr.unpruned_size())
+	}
+
+	/// Verify the signatures of (at most) `max` kernels past kernel MMR
+	/// position `from_pos`, the batches being verified in parallel.
+	/// Returns the position of the last kernel verified (none if there were no
+	/// kernels left) and the number of signatures verified.
+	pub fn verify_kernel_signatures_from(
+		&self,
+		from_pos: u64,
+		max: usize,
+	) -> Result<(Option<u64>, u64), Error> {
+		let mut tx_kernels: Vec<TxKernel> = Vec::with_capacity(max);
+		let mut last_pos = None;
+		for n in (from_pos + 1)..=self.kernel_pmmr.unpruned_size() {
+			if tx_kernels.len() >= max {
+				break;
+			}
+			if pmmr::is_leaf(n) {
+				let kernel = self
+					.kernel_pmmr
+					.get_data(n)
+					.ok_or_else(|| ErrorKind::TxKernelNotFound)?;
+				tx_kernels.push(kernel);
+				last_pos = Some(n);
+			}
+		}
+
+		tx_kernels
+			.par_chunks(VALIDATION_KERNEL_BATCH_SIZE)
+			.try_for_each(|kernels| {
+				secp_static::with_thread_secp_instance(|secp| {
+					TxKernel::batch_sig_verify_with(secp, kernels)
+				})
+			})?;
+
+		Ok((last_pos, tx_kernels.len() as u64))
+	}
+
+	/// Verify the rangeproofs of (at most) `max` unspent outputs past output
+	/// MMR position `from_pos`, the batches being verified in parallel.
+	/// Returns the position of the last output verified (none if there were no
+	/// outputs left) and the number of rangeproofs verified.
+	pub fn verify_rangeproofs_from(
+		&self,
+		from_pos: u64,
+		max: usize,
+	) -> Result<(Option<u64>, u64), Error> {
+		let mut commits: Vec<Commitment> = Vec::with_capacity(max);
+		let mut proofs: Vec<RangeProof> = Vec::with_capacity(max);
+		let mut last_pos = None;
+
+		// Walk the positions from from_pos, spent outputs having neither an
+		// output nor a rangeproof.
+		for pos in (from_pos + 1)..=self.output_pmmr.unpruned_size() {
+			if commits.len() >= max {
+				break;
+			}
+			if !pmmr::is_leaf(pos) {
+				continue;
+			}
+			let output = self.output_pmmr.get_data(pos);
+			let proof = self.rproof_pmmr.get_data(pos);
+			match (output, proof) {
+				(None, None) => continue,
+				(None, _) => {
+					return Err(ErrorKind::OutputNotFound(format!(
+						"at verify_rangeproofs_from for pos {}",
+						pos
+					))
+					.into())
+				}
+				(_, None) => {
+					return Err(ErrorKind::RangeproofNotFound(format!(
+						"at verify_rangeproofs_from for pos {}",
+						pos
+					))
+					.into())
+				}
+				(Some(output), Some(proof)) => {
+					commits.push(output.commit);
+					proofs.push(proof);
+				}
+			}
+			last_pos = Some(pos);
+		}
+
+		commits
+			.par_chunks(VALIDATION_RPROOF_BATCH_SIZE)
+			.zip(proofs.par_chunks(VALIDATION_RPROOF_BATCH_SIZE))
+			.try_for_each(|(commits, proofs)| {
+				secp_static::with_thread_secp_instance(|secp| {
+					Output::batch_verify_proofs_with(secp, commits, proofs)
+				})
+			})?;
+
+		Ok((last_pos, commits.len() as u64))
+	}
+
 	fn verify_rangeproofs(&self, status: &dyn TxHashsetWriteStatus) -> Result<(), Error> {
 		let now = Instant::now();
 
@@ -1695,3 +2234,21 @@ fn input_pos_to_rewind(
 	}
 	Ok(bitmap)
 }
+
+/// Verify an NRD kernel can be included in a block at the given height.
+/// A previous NRD kernel with the same excess must be at least relative_height
+/// blocks prior, based on the NRD kernel index.
+pub fn verify_nrd_kernel(kernel: &TxKernel, height: u64, batch: &Batch<'_>) -> Result<(), Error> {
+	if let KernelFeatures::NoRecentDuplicate {
+		relative_height, ..
+	} = kernel.features
+	{
+		let history = batch.get_nrd_kernel_history(&kernel.excess)?;
+		if let Some(prev) = history.last() {
+			if height.saturating_sub(prev.height) < u64::from(relative_height) {
+				return Err(ErrorKind::NRDRelativeHeight.into());
+			}
+		}
+	}
+	Ok(())
+}
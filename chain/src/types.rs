@@ -38,6 +38,19 @@ bitflags! {
 	}
 }
 
+/// Configuration of the chain, given to `Chain::init`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChainOptions {
+	/// Keep the full block history instead of pruning below the horizon
+	pub archive_mode: bool,
+	/// Maintain the kernel excess to MMR position index
+	pub kernel_index: bool,
+	/// Maintain the output history index, requires archive mode
+	pub output_history_index: bool,
+	/// Maximum number of blocks a reorg may rewind, unlimited if none
+	pub max_reorg_depth: Option<u64>,
+}
+
 /// Various status sync can be in, whether it's fast sync or archival.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
 #[allow(missing_docs)]
@@ -284,6 +297,66 @@ impl Writeable for CommitPos {
 	}
 }
 
+/// Lifecycle of a single output as tracked by the (optional) output history index.
+/// Each output created on chain is tracked via its MMR position, a commitment
+/// may appear multiple times if reused after being spent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputHistory {
+	/// MMR position of the output
+	pub pos: u64,
+	/// Height of the block that created the output
+	pub height: u64,
+	/// Height of the block that spent the output (if spent)
+	pub spent_height: Option<u64>,
+	/// Hash of the block that spent the output (if spent)
+	pub spent_block: Option<Hash>,
+}
+
+impl OutputHistory {
+	/// Is this output spent?
+	pub fn is_spent(&self) -> bool {
+		self.spent_height.is_some()
+	}
+}
+
+impl Readable for OutputHistory {
+	fn read(reader: &mut dyn Reader) -> Result<OutputHistory, ser::Error> {
+		let pos = reader.read_u64()?;
+		let height = reader.read_u64()?;
+		let (spent_height, spent_block) = match reader.read_u8()? {
+			0 => (None, None),
+			1 => (Some(reader.read_u64()?), Some(Hash::read(reader)?)),
+			_ => {
+				return Err(ser::Error::CorruptedData(
+					"invalid output history spent flag".to_string(),
+				))
+			}
+		};
+		Ok(OutputHistory {
+			pos,
+			height,
+			spent_height,
+			spent_block,
+		})
+	}
+}
+
+impl Writeable for OutputHistory {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_u64(self.pos)?;
+		writer.write_u64(self.height)?;
+		match (self.spent_height, self.spent_block) {
+			(Some(spent_height), Some(spent_block)) => {
+				writer.write_u8(1)?;
+				writer.write_u64(spent_height)?;
+				spent_block.write(writer)?;
+			}
+			_ => writer.write_u8(0)?,
+		}
+		Ok(())
+	}
+}
+
 /// The tip of a fork. A handle to the fork ancestry from its leaf in the
 /// blockchain tree. References the max height and the latest and previous
 /// blocks
@@ -355,6 +428,195 @@ impl ser::Readable for Tip {
 	}
 }
 
+/// Status of a chain tip.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ChainTipStatus {
+	/// Head of our block chain.
+	Active,
+	/// Fully validated fork.
+	ValidFork,
+	/// Fork we only have (some of) the headers of.
+	HeadersOnly,
+	/// Fork including an invalidated block.
+	Invalid,
+}
+
+/// A chain tip, a header without any known child.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChainTip {
+	/// The tip itself
+	pub tip: Tip,
+	/// Number of blocks since the fork point with the header chain
+	pub branch_length: u64,
+	/// Status of the tip
+	pub status: ChainTipStatus,
+}
+
+/// An inconsistency between the chain db and the MMR data files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DbIssue {
+	/// Name of the check that found it (e.g. `header_mmr`, `output_pos`)
+	pub check: &'static str,
+	/// Description of the inconsistency
+	pub description: String,
+	/// Whether it is fixed by rebuilding a derived index in repair mode
+	pub repairable: bool,
+}
+
+impl DbIssue {
+	fn new(check: &'static str, description: String, repairable: bool) -> DbIssue {
+		DbIssue {
+			check,
+			description,
+			repairable,
+		}
+	}
+
+	/// Inconsistency fixed by rebuilding a derived index.
+	pub fn repairable(check: &'static str, description: String) -> DbIssue {
+		DbIssue::new(check, description, true)
+	}
+
+	/// Inconsistency of the primary data, requiring a resync.
+	pub fn fatal(check: &'static str, description: String) -> DbIssue {
+		DbIssue::new(check, description, false)
+	}
+}
+
+/// Phase of a full chain validation job.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ValidationPhase {
+	/// No validation run since the node started.
+	Idle,
+	/// Validating the MMRs, their roots and sizes and the kernel sums.
+	Sums,
+	/// Verifying the rangeproofs of the unspent outputs.
+	Rangeproofs,
+	/// Verifying the kernel signatures.
+	Kernels,
+	/// Validation completed successfully.
+	Done,
+	/// Validation cancelled, resumed from its checkpoint on next run.
+	Cancelled,
+	/// Validation failed.
+	Failed,
+}
+
+impl ValidationPhase {
+	fn to_u8(self) -> u8 {
+		match self {
+			ValidationPhase::Idle => 0,
+			ValidationPhase::Sums => 1,
+			ValidationPhase::Rangeproofs => 2,
+			ValidationPhase::Kernels => 3,
+			ValidationPhase::Done => 4,
+			ValidationPhase::Cancelled => 5,
+			ValidationPhase::Failed => 6,
+		}
+	}
+
+	fn from_u8(n: u8) -> Result<ValidationPhase, ser::Error> {
+		match n {
+			0 => Ok(ValidationPhase::Idle),
+			1 => Ok(ValidationPhase::Sums),
+			2 => Ok(ValidationPhase::Rangeproofs),
+			3 => Ok(ValidationPhase::Kernels),
+			4 => Ok(ValidationPhase::Done),
+			5 => Ok(ValidationPhase::Cancelled),
+			6 => Ok(ValidationPhase::Failed),
+			_ => Err(ser::Error::CorruptedData(format!(
+				"invalid validation phase {}",
+				n
+			))),
+		}
+	}
+}
+
+/// Progress of the full chain validation job.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ValidationStatus {
+	/// Current phase
+	pub phase: ValidationPhase,
+	/// Height of the chain head validated against
+	pub height: u64,
+	/// Number of rangeproofs or kernel signatures verified in the current phase
+	pub done: u64,
+	/// Number of rangeproofs or kernel signatures to verify in the current phase
+	pub total: u64,
+	/// Whether the validation resumed from a checkpoint
+	pub resumed: bool,
+	/// Error the validation failed with
+	pub error: Option<String>,
+}
+
+impl Default for ValidationStatus {
+	fn default() -> ValidationStatus {
+		ValidationStatus {
+			phase: ValidationPhase::Idle,
+			height: 0,
+			done: 0,
+			total: 0,
+			resumed: false,
+			error: None,
+		}
+	}
+}
+
+/// Checkpoint of the full chain validation job, saved after each step so an
+/// interrupted validation resumes where it left off.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationCheckpoint {
+	/// Header the last step was validated against
+	pub header: Hash,
+	/// Phase to resume
+	pub phase: ValidationPhase,
+	/// Last MMR position verified in this phase
+	pub pos: u64,
+	/// Number of items verified in this phase
+	pub done: u64,
+}
+
+impl Readable for ValidationCheckpoint {
+	fn read(reader: &mut dyn Reader) -> Result<ValidationCheckpoint, ser::Error> {
+		let header = Hash::read(reader)?;
+		let phase = ValidationPhase::from_u8(reader.read_u8()?)?;
+		let pos = reader.read_u64()?;
+		let done = reader.read_u64()?;
+		Ok(ValidationCheckpoint {
+			header,
+			phase,
+			pos,
+			done,
+		})
+	}
+}
+
+impl Writeable for ValidationCheckpoint {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		self.header.write(writer)?;
+		writer.write_u8(self.phase.to_u8())?;
+		writer.write_u64(self.pos)?;
+		writer.write_u64(self.done)?;
+		Ok(())
+	}
+}
+
+/// A fork refused because switching to it would rewind the chain further
+/// than the configured maximum reorg depth.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RefusedReorg {
+	/// Block (or header) that would have made the fork our chain
+	pub hash: Hash,
+	/// Height of that block
+	pub height: u64,
+	/// Total difficulty of the fork
+	pub total_difficulty: Difficulty,
+	/// Maximum reorg depth at the time
+	pub max_depth: u64,
+	/// When the fork was first refused (unix timestamp)
+	pub refused_at: i64,
+}
+
 /// Bridge between the chain pipeline and the rest of the system. Handles
 /// downstream processing of valid blocks by the rest of the system, most
 /// importantly the broadcasting of blocks to our peers.
@@ -362,6 +624,15 @@ pub trait ChainAdapter {
 	/// The blockchain pipeline has accepted this block as valid and added
 	/// it to our chain.
 	fn block_accepted(&self, block: &Block, status: BlockStatus, opts: Options);
+
+	/// The chain head switched to another fork, rewinding `depth` blocks of
+	/// the previous chain. Also fired when the head is moved by
+	/// `invalidate_block`, `reconsider_block` or `rewind_chain`.
+	fn reorg(&self, _depth: u64, _old_tip: &Tip, _new_tip: &Tip) {}
+
+	/// The blockchain pipeline has refused to switch to a fork deeper than
+	/// the maximum reorg depth.
+	fn reorg_refused(&self, _reorg: &RefusedReorg) {}
 }
 
 /// Inform the caller of the current status of a txhashset write operation,
@@ -14,7 +14,7 @@
 
 use self::chain::types::NoopAdapter;
 use self::chain::types::Options;
-use self::chain::Chain;
+use self::chain::{Chain, ChainOptions};
 use self::core::core::hash::Hashed;
 use self::core::core::verifier_cache::LruVerifierCache;
 use self::core::core::Block;
@@ -44,7 +44,7 @@ pub fn init_chain(dir_name: &str, genesis: Block) -> Chain {
 		genesis,
 		pow::verify_size,
 		verifier_cache,
-		false,
+		ChainOptions::default(),
 	)
 	.unwrap()
 }
@@ -12,7 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use self::chain::types::{ValidationCheckpoint, ValidationPhase};
+use self::core::core::Committed;
 use self::core::genesis;
+use mimble_chain as chain;
 use mimble_core as core;
 use mimble_util as util;
 
@@ -44,3 +47,105 @@ fn data_files() {
 	// Cleanup chain directory
 	clean_output_dir(chain_dir);
 }
+
+#[test]
+fn check_and_repair_db() {
+	util::init_test_logger();
+
+	let chain_dir = ".mwc_check_db";
+	clean_output_dir(chain_dir);
+	{
+		let chain = mine_chain(chain_dir, 4);
+		assert!(chain.check_db(false).unwrap().is_empty());
+
+		// Drop an output_pos entry and overwrite the head block sums.
+		let head = chain.head().unwrap();
+		let commit = chain
+			.get_block(&head.last_block_h)
+			.unwrap()
+			.outputs_committed()[0];
+		{
+			let batch = chain.store().batch().unwrap();
+			batch.delete_output_pos_height(&commit).unwrap();
+			let prev_sums = batch.get_block_sums(&head.prev_block_h).unwrap();
+			batch
+				.save_block_sums(&head.last_block_h, &prev_sums)
+				.unwrap();
+			batch.commit().unwrap();
+		}
+
+		let issues: Vec<_> = chain
+			.check_db(false)
+			.unwrap()
+			.iter()
+			.map(|i| (i.check, i.repairable))
+			.collect();
+		assert_eq!(issues, vec![("block_sums", true), ("output_pos", true)]);
+
+		// Repair rebuilds the derived indices in place.
+		assert_eq!(chain.check_db(true).unwrap().len(), 2);
+		assert!(chain.check_db(false).unwrap().is_empty());
+		chain.validate(false).unwrap();
+	}
+	clean_output_dir(chain_dir);
+}
+
+#[test]
+fn full_validation_checkpoint() {
+	util::init_test_logger();
+
+	let chain_dir = ".mwc_full_validation";
+	clean_output_dir(chain_dir);
+	{
+		let chain = mine_chain(chain_dir, 4);
+		chain.run_full_validation().unwrap();
+		let status = chain.full_validation_status();
+		assert_eq!(status.phase, ValidationPhase::Done);
+		assert_eq!(status.height, 3);
+		assert!(!status.resumed);
+		assert!(!chain.has_full_validation_checkpoint().unwrap());
+
+		// Interrupted while verifying the kernels, past the first one.
+		let checkpoint = ValidationCheckpoint {
+			header: chain.head().unwrap().last_block_h,
+			phase: ValidationPhase::Kernels,
+			pos: 1,
+			done: 1,
+		};
+		let batch = chain.store().batch().unwrap();
+		batch.save_validation_checkpoint(&checkpoint).unwrap();
+		batch.commit().unwrap();
+	}
+
+	// Resumes from the checkpoint after a restart.
+	{
+		let chain = init_chain(chain_dir, genesis::genesis_dev());
+		assert!(chain.has_full_validation_checkpoint().unwrap());
+		chain.run_full_validation().unwrap();
+		let status = chain.full_validation_status();
+		assert_eq!(status.phase, ValidationPhase::Done);
+		assert!(status.resumed);
+		assert!(!chain.has_full_validation_checkpoint().unwrap());
+
+		// A reorg below the checkpoint resumes from the fork point rather
+		// than starting over.
+		let head = chain.head_header().unwrap();
+		let checkpoint = ValidationCheckpoint {
+			header: head.hash(),
+			phase: ValidationPhase::Kernels,
+			pos: head.kernel_mmr_size,
+			done: 3,
+		};
+		let batch = chain.store().batch().unwrap();
+		batch.save_validation_checkpoint(&checkpoint).unwrap();
+		batch.commit().unwrap();
+		chain.rewind_chain(1).unwrap();
+
+		chain.run_full_validation().unwrap();
+		let status = chain.full_validation_status();
+		assert_eq!(status.phase, ValidationPhase::Done);
+		assert_eq!(status.height, 1);
+		assert!(status.resumed);
+	}
+	clean_output_dir(chain_dir);
+}
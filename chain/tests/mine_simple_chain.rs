@@ -13,13 +13,14 @@
 // limitations under the License.
 
 use self::chain::types::{NoopAdapter, Tip};
-use self::chain::Chain;
+use self::chain::{Chain, ChainOptions};
 use self::core::core::hash::Hashed;
 use self::core::core::verifier_cache::LruVerifierCache;
 use self::core::core::{Block, BlockHeader, KernelFeatures, OutputIdentifier, Transaction};
 use self::core::global::ChainTypes;
 use self::core::libtx::{self, build, ProofBuilder};
 use self::core::pow::Difficulty;
+use self::core::ser::ProtocolVersion;
 use self::core::{consensus, global, pow};
 use self::keychain::{ExtKeychain, ExtKeychainPath, Keychain};
 use self::util::RwLock;
@@ -35,14 +36,18 @@ mod chain_test_helper;
 
 use self::chain_test_helper::{clean_output_dir, init_chain, mine_chain};
 
-/// Adapter to retrieve last status
+/// Adapter to retrieve last status and last reorg (depth and new tip)
 pub struct StatusAdapter {
 	pub last_status: RwLock<Option<BlockStatus>>,
+	pub last_reorg: RwLock<Option<(u64, Tip)>>,
 }
 
 impl StatusAdapter {
 	pub fn new(last_status: RwLock<Option<BlockStatus>>) -> Self {
-		StatusAdapter { last_status }
+		StatusAdapter {
+			last_status,
+			last_reorg: RwLock::new(None),
+		}
 	}
 }
 
@@ -50,6 +55,10 @@ impl ChainAdapter for StatusAdapter {
 	fn block_accepted(&self, _b: &Block, status: BlockStatus, _opts: Options) {
 		*self.last_status.write() = Some(status);
 	}
+
+	fn reorg(&self, depth: u64, _old_tip: &Tip, new_tip: &Tip) {
+		*self.last_reorg.write() = Some((depth, new_tip.clone()));
+	}
 }
 
 /// Creates a `Chain` instance with `StatusAdapter` attached to it.
@@ -63,7 +72,7 @@ fn setup_with_status_adapter(dir_name: &str, genesis: Block, adapter: Arc<Status
 		genesis,
 		pow::verify_size,
 		verifier_cache,
-		false,
+		ChainOptions::default(),
 	)
 	.unwrap();
 
@@ -88,6 +97,53 @@ fn mine_short_chain() {
 	clean_output_dir(chain_dir);
 }
 
+#[test]
+fn export_import_blocks() {
+	let chain_dir = ".mwc.export_blocks";
+	let import_dir = ".mwc.import_blocks";
+	clean_output_dir(chain_dir);
+	clean_output_dir(import_dir);
+	{
+		let chain = mine_chain(chain_dir, 10);
+		let head = chain.head().unwrap();
+		let genesis = chain
+			.get_block(&chain.get_header_by_height(0).unwrap().hash())
+			.unwrap();
+
+		let mut data = vec![];
+		let mut progress = vec![];
+		let exported = chain
+			.export_blocks(&mut data, ProtocolVersion::local(), &mut |done, total| {
+				progress.push((done, total))
+			})
+			.unwrap();
+		assert_eq!(exported, 9);
+		assert_eq!(progress.last(), Some(&(9, 9)));
+
+		let imported = init_chain(import_dir, genesis);
+		let processed = imported
+			.import_blocks(&mut &data[..], Some(4), &mut |_, _| {})
+			.unwrap();
+		assert_eq!(processed, 9);
+		assert_eq!(imported.head().unwrap(), head);
+
+		// Known blocks are skipped when importing again
+		let processed = imported
+			.import_blocks(&mut &data[..], None, &mut |_, _| {})
+			.unwrap();
+		assert_eq!(processed, 0);
+
+		// A block length over the max block size is refused before reading it
+		let mut corrupted = data.clone();
+		corrupted[44..52].copy_from_slice(&u64::max_value().to_be_bytes());
+		assert!(imported
+			.import_blocks(&mut &corrupted[..], None, &mut |_, _| {})
+			.is_err());
+	}
+	clean_output_dir(chain_dir);
+	clean_output_dir(import_dir);
+}
+
 // Convenience wrapper for processing a full block on the test chain.
 fn process_header(chain: &Chain, header: &BlockHeader) {
 	chain
@@ -546,6 +602,225 @@ fn longer_fork() {
 	clean_output_dir(".mwc4");
 }
 
+#[test]
+fn invalidate_and_reconsider_block() {
+	let chain_dir = ".mwc_invalidate";
+	clean_output_dir(chain_dir);
+	global::set_mining_mode(ChainTypes::AutomatedTesting);
+	let kc = ExtKeychain::from_random_seed(false).unwrap();
+	{
+		let adapter = Arc::new(StatusAdapter::new(RwLock::new(None)));
+		let chain = setup_with_status_adapter(
+			chain_dir,
+			pow::mine_genesis_block().unwrap(),
+			adapter.clone(),
+		);
+
+		let mut prev = chain.head_header().unwrap();
+		for n in 0..5 {
+			let b = prepare_block(&kc, &prev, &chain, 2 * n + 2);
+			prev = b.header.clone();
+			process_block(&chain, &b);
+		}
+		let tip = prev;
+		let invalid = chain.get_header_by_height(3).unwrap();
+		let child = prepare_block(&kc, &tip, &chain, 20);
+
+		// Head rewinds to the parent of the invalidated block
+		chain.invalidate_block(invalid.hash()).unwrap();
+		assert_eq!(chain.invalid_blocks(), vec![invalid.hash()]);
+		assert_eq!(chain.head().unwrap().height, 2);
+		assert_eq!(chain.header_head().unwrap().height, 2);
+		assert_eq!(*adapter.last_reorg.read(), Some((3, chain.head().unwrap())));
+
+		// Descendants of the invalidated block are rejected
+		assert!(chain
+			.process_block(child, chain::Options::SKIP_POW, vec![])
+			.is_err());
+
+		// Reconsidering it switches back to the most worked chain
+		chain.reconsider_block(invalid.hash()).unwrap();
+		assert!(chain.invalid_blocks().is_empty());
+		assert_eq!(chain.head().unwrap().hash(), tip.hash());
+		assert_eq!(chain.header_head().unwrap().hash(), tip.hash());
+		assert_eq!(*adapter.last_reorg.read(), Some((0, chain.head().unwrap())));
+	}
+	clean_output_dir(chain_dir);
+}
+
+#[test]
+fn rewind_chain() {
+	let chain_dir = ".mwc_rewind_chain";
+	clean_output_dir(chain_dir);
+	global::set_mining_mode(ChainTypes::AutomatedTesting);
+	let kc = ExtKeychain::from_random_seed(false).unwrap();
+	{
+		let adapter = Arc::new(StatusAdapter::new(RwLock::new(None)));
+		let chain = setup_with_status_adapter(
+			chain_dir,
+			pow::mine_genesis_block().unwrap(),
+			adapter.clone(),
+		);
+
+		let mut blocks = vec![];
+		let mut prev = chain.head_header().unwrap();
+		for n in 0..5 {
+			let b = prepare_block(&kc, &prev, &chain, 2 * n + 2);
+			prev = b.header.clone();
+			process_block(&chain, &b);
+			blocks.push(b);
+		}
+
+		// Can't rewind past the head
+		assert!(chain.rewind_chain(6).is_err());
+
+		// Removed blocks are kept in the db as fork blocks
+		let tip = chain.rewind_chain(2).unwrap();
+		assert_eq!(tip.last_block_h, blocks[1].hash());
+		assert_eq!(chain.head().unwrap().height, 2);
+		assert_eq!(chain.header_head().unwrap().height, 2);
+		assert_eq!(*adapter.last_reorg.read(), Some((3, tip)));
+		assert_eq!(chain.orphans_len(), 0);
+		assert!(chain.block_exists(blocks[2].hash()).unwrap());
+		assert!(chain.validate(false).is_ok());
+
+		// Receiving the last removed block again switches back to them
+		process_block(&chain, &blocks[4]);
+		assert_eq!(chain.head().unwrap().hash(), prev.hash());
+		assert_eq!(chain.header_head().unwrap().hash(), prev.hash());
+		assert!(chain.validate(false).is_ok());
+	}
+	clean_output_dir(chain_dir);
+}
+
+#[test]
+fn chain_tips() {
+	let chain_dir = ".mwc_chain_tips";
+	clean_output_dir(chain_dir);
+	global::set_mining_mode(ChainTypes::AutomatedTesting);
+	let kc = ExtKeychain::from_random_seed(false).unwrap();
+	{
+		let chain = init_chain(chain_dir, pow::mine_genesis_block().unwrap());
+
+		let mut prev = chain.head_header().unwrap();
+		for n in 0..3 {
+			let b = prepare_block(&kc, &prev, &chain, 2 * n + 2);
+			prev = b.header.clone();
+			process_block(&chain, &b);
+		}
+
+		// A full block fork off height 1 and a header only fork off height 2.
+		let fork = prepare_block(&kc, &chain.get_header_by_height(1).unwrap(), &chain, 3);
+		process_block(&chain, &fork);
+		let header_fork = prepare_block(&kc, &chain.get_header_by_height(2).unwrap(), &chain, 5);
+		process_header(&chain, &header_fork.header);
+
+		let tips = chain.chain_tips().unwrap();
+		let summary: Vec<_> = tips
+			.iter()
+			.map(|t| (t.tip.last_block_h, t.branch_length, t.status))
+			.collect();
+		assert_eq!(
+			summary,
+			vec![
+				(prev.hash(), 0, chain::ChainTipStatus::Active),
+				(header_fork.hash(), 1, chain::ChainTipStatus::HeadersOnly),
+				(fork.hash(), 1, chain::ChainTipStatus::ValidFork),
+			]
+		);
+
+		// A header ahead of the body head, still the active tip.
+		let next = prepare_block(&kc, &prev, &chain, 8);
+		process_header(&chain, &next.header);
+		let tips = chain.chain_tips().unwrap();
+		assert_eq!(tips.len(), 4);
+		assert_eq!(
+			(tips[0].tip.last_block_h, tips[0].status),
+			(next.hash(), chain::ChainTipStatus::HeadersOnly)
+		);
+		assert_eq!(
+			(tips[1].tip.last_block_h, tips[1].status),
+			(prev.hash(), chain::ChainTipStatus::Active)
+		);
+	}
+	clean_output_dir(chain_dir);
+}
+
+#[test]
+fn accept_reorg_deeper_than_max_depth() {
+	let chain_dir = ".mwc_max_reorg_depth";
+	clean_output_dir(chain_dir);
+	global::set_mining_mode(ChainTypes::AutomatedTesting);
+	let kc = ExtKeychain::from_random_seed(false).unwrap();
+	let genesis = pow::mine_genesis_block().unwrap();
+	let init = || {
+		let verifier_cache = Arc::new(RwLock::new(LruVerifierCache::new()));
+		chain::Chain::init(
+			chain_dir.to_string(),
+			Arc::new(NoopAdapter {}),
+			genesis.clone(),
+			pow::verify_size,
+			verifier_cache,
+			ChainOptions {
+				max_reorg_depth: Some(2),
+				..ChainOptions::default()
+			},
+		)
+		.unwrap()
+	};
+
+	let (head, fork_2) = {
+		let chain = init();
+		let mut prev = chain.head_header().unwrap();
+		for n in 0..5 {
+			let b = prepare_block(&kc, &prev, &chain, 2 * n + 2);
+			prev = b.header.clone();
+			process_block(&chain, &b);
+		}
+		let head = chain.head().unwrap();
+		assert_eq!(head.height, 5);
+
+		// Fork off at height 1, the second fork block has more work than our
+		// chain but would rewind 4 blocks.
+		let fork_point = chain.get_header_by_height(1).unwrap();
+		let fork_1 = prepare_block(&kc, &fork_point, &chain, 3);
+		process_block(&chain, &fork_1);
+		let fork_2 = prepare_block(&kc, &fork_1.header, &chain, 30);
+		let res = chain.process_block(fork_2.clone(), chain::Options::SKIP_POW, vec![]);
+		assert_eq!(res.unwrap_err().kind(), chain::ErrorKind::ReorgTooDeep(2));
+		assert_eq!(chain.head().unwrap(), head);
+		assert_eq!(chain.header_head().unwrap(), head);
+
+		let refused = chain.refused_reorgs();
+		assert_eq!(refused.len(), 1);
+		assert_eq!(refused[0].hash, fork_2.hash());
+		assert_eq!(refused[0].max_depth, 2);
+
+		chain.accept_reorg(fork_2.hash()).unwrap();
+		assert!(chain.refused_reorgs().is_empty());
+		(head, fork_2)
+	};
+
+	// The acceptance survives a restart, the fork is adopted once received
+	// again and followed from there on.
+	{
+		let chain = init();
+		process_block(&chain, &fork_2);
+		assert_eq!(chain.head().unwrap().hash(), fork_2.hash());
+		assert_eq!(chain.header_head().unwrap().hash(), fork_2.hash());
+
+		let fork_3 = prepare_block(&kc, &fork_2.header, &chain, 32);
+		process_block(&chain, &fork_3);
+		assert_eq!(chain.head().unwrap().hash(), fork_3.hash());
+		assert_eq!(chain.header_head().unwrap().hash(), fork_3.hash());
+
+		let old_head = chain.get_block_header(&head.last_block_h).unwrap();
+		assert!(chain.is_on_current_chain(&old_head).is_err());
+		assert_eq!(chain.get_header_by_height(2).unwrap().hash(), fork_2.header.prev_hash);
+	}
+	clean_output_dir(chain_dir);
+}
+
 #[test]
 fn spend_rewind_spend() {
 	global::set_mining_mode(ChainTypes::AutomatedTesting);
@@ -952,7 +1227,7 @@ fn actual_diff_iter_output() {
 		genesis_block,
 		pow::verify_size,
 		verifier_cache,
-		false,
+		ChainOptions::default(),
 	)
 	.unwrap();
 	let iter = chain.difficulty_iter().unwrap();
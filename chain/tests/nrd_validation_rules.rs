@@ -0,0 +1,242 @@
+// Copyright 2020 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use self::chain::{Chain, ErrorKind, Options};
+use self::core::core::{
+	transaction, Block, BlockHeader, KernelFeatures, NRDRelativeHeight, Transaction, TxKernel,
+};
+use self::core::global::{ChainTypes, ConsensusRule};
+use self::core::libtx::{self, aggsig, build, ProofBuilder};
+use self::core::pow::{self, Difficulty};
+use self::core::{consensus, global};
+use self::keychain::{
+	BlindingFactor, ExtKeychain, ExtKeychainPath, Keychain, SwitchCommitmentType,
+};
+use chrono::Duration;
+use mimble_chain as chain;
+use mimble_core as core;
+use mimble_keychain as keychain;
+use mimble_util as util;
+
+mod chain_test_helper;
+
+use self::chain_test_helper::{clean_output_dir, init_chain};
+
+fn build_block<K>(
+	kc: &K,
+	prev: &BlockHeader,
+	chain: &Chain,
+	txs: Vec<&Transaction>,
+) -> Result<Block, chain::Error>
+where
+	K: Keychain,
+{
+	let height = prev.height + 1;
+	let key_id = ExtKeychainPath::new(1, height as u32, 0, 0, 0).to_identifier();
+	let fees = txs.iter().map(|tx| tx.fee()).sum();
+	let reward =
+		libtx::reward::output(kc, &ProofBuilder::new(kc), &key_id, fees, false, height).unwrap();
+	let mut b = Block::new(
+		prev,
+		txs.into_iter().cloned().collect(),
+		Difficulty::from_num(2),
+		reward,
+	)
+	.unwrap();
+	b.header.timestamp = prev.timestamp + Duration::seconds(60);
+	b.header.pow.total_difficulty = prev.total_difficulty() + Difficulty::from_num(2);
+	b.header.pow.proof = pow::Proof::random(global::proofsize());
+	chain.set_txhashset_roots(&mut b)?;
+	Ok(b)
+}
+
+// Build a signed NRD kernel, returning the kernel and its excess so the
+// same kernel can be reused across multiple transactions.
+fn build_nrd_kernel<K>(kc: &K, relative_height: u64) -> (TxKernel, BlindingFactor)
+where
+	K: Keychain,
+{
+	let mut kernel = TxKernel::with_features(KernelFeatures::NoRecentDuplicate {
+		fee: 20000,
+		relative_height: NRDRelativeHeight::new(relative_height).unwrap(),
+	});
+	let msg = kernel.msg_to_sign().unwrap();
+	let key_id = ExtKeychainPath::new(1, 1000, 0, 0, 0).to_identifier();
+	let skey = kc
+		.derive_key(0, &key_id, SwitchCommitmentType::None)
+		.unwrap();
+	let excess = BlindingFactor::from_secret_key(skey.clone());
+	kernel.excess = kc.secp().commit(0, skey).unwrap();
+	let pubkey = kernel.excess.to_pubkey(kc.secp()).unwrap();
+	kernel.excess_sig =
+		aggsig::sign_with_blinding(kc.secp(), &msg, &excess, Some(&pubkey)).unwrap();
+	kernel.verify().unwrap();
+	(kernel, excess)
+}
+
+// Spend the coinbase output from the block at the given height using the NRD kernel.
+fn build_nrd_tx<K>(
+	kc: &K,
+	kernel: &TxKernel,
+	excess: &BlindingFactor,
+	coinbase_height: u32,
+) -> Transaction
+where
+	K: Keychain,
+{
+	let key_id_coinbase = ExtKeychainPath::new(1, coinbase_height, 0, 0, 0).to_identifier();
+	let key_id_out = ExtKeychainPath::new(1, 100 + coinbase_height, 0, 0, 0).to_identifier();
+	build::transaction_with_kernel(
+		vec![
+			build::coinbase_input(consensus::MIMBLE_FIRST_GROUP_REWARD, key_id_coinbase),
+			build::output(consensus::MIMBLE_FIRST_GROUP_REWARD - 20000, key_id_out),
+		],
+		kernel.clone(),
+		excess.clone(),
+		kc,
+		&ProofBuilder::new(kc),
+	)
+	.unwrap()
+}
+
+fn process_block(chain: &Chain, b: &Block) -> Result<(), chain::Error> {
+	chain.process_block(b.clone(), Options::SKIP_POW, vec![])?;
+	Ok(())
+}
+
+// Mine empty blocks up to (and including) the given height.
+fn mine_empty_blocks<K>(kc: &K, chain: &Chain, height: u64) -> BlockHeader
+where
+	K: Keychain,
+{
+	let mut head = chain.head_header().unwrap();
+	while head.height < height {
+		let b = build_block(kc, &head, chain, vec![]).unwrap();
+		process_block(chain, &b).unwrap();
+		head = b.header;
+	}
+	head
+}
+
+#[test]
+fn process_block_nrd_validation() {
+	global::set_mining_mode(ChainTypes::AutomatedTesting);
+	util::init_test_logger();
+	let chain_dir = ".mwc.nrd_kernel";
+	clean_output_dir(chain_dir);
+
+	{
+		let chain = init_chain(chain_dir, pow::mine_genesis_block().unwrap());
+		let kc = ExtKeychain::from_random_seed(false).unwrap();
+
+		let (kernel, excess) = build_nrd_kernel(&kc, 2);
+		let tx1 = build_nrd_tx(&kc, &kernel, &excess, 1);
+		let tx2 = build_nrd_tx(&kc, &kernel, &excess, 2);
+		let tx3 = build_nrd_tx(&kc, &kernel, &excess, 3);
+
+		// A different NRD kernel sharing the same excess.
+		let (kernel_2, _) = build_nrd_kernel(&kc, 1);
+		assert_ne!(kernel, kernel_2);
+		let tx4 = build_nrd_tx(&kc, &kernel_2, &excess, 3);
+
+		let activation_height = global::activation_height(ConsensusRule::NRDKernels).unwrap();
+
+		// NRD kernels are not valid prior to the activation height.
+		let head = mine_empty_blocks(&kc, &chain, activation_height - 2);
+		let b = build_block(&kc, &head, &chain, vec![&tx1]).unwrap();
+		assert_eq!(b.header.height, activation_height - 1);
+		assert!(process_block(&chain, &b).is_err());
+
+		// NRD kernel is valid at the activation height.
+		let head = mine_empty_blocks(&kc, &chain, activation_height - 1);
+		let b = build_block(&kc, &head, &chain, vec![&tx1]).unwrap();
+		process_block(&chain, &b).unwrap();
+		let head = b.header;
+
+		// Reusing the kernel in the next block is within the relative height.
+		let res = build_block(&kc, &head, &chain, vec![&tx2]);
+		assert_eq!(res.unwrap_err().kind(), ErrorKind::NRDRelativeHeight);
+		assert_eq!(
+			chain.validate_tx(&tx2).unwrap_err().kind(),
+			ErrorKind::NRDRelativeHeight
+		);
+
+		// Reusing the kernel at the relative height is valid,
+		// but not twice within the same block.
+		let head = mine_empty_blocks(&kc, &chain, head.height + 1);
+		chain.validate_tx(&tx2).unwrap();
+		let res = build_block(&kc, &head, &chain, vec![&tx2, &tx3]);
+		assert_eq!(res.unwrap_err().kind(), ErrorKind::NRDRelativeHeight);
+
+		// Different NRD kernels sharing an excess are each valid on their own,
+		// but not together in a tx (checked in order against the NRD kernel index).
+		chain.validate_tx(&tx4).unwrap();
+		let agg_tx = transaction::aggregate(vec![tx2.clone(), tx4.clone()]).unwrap();
+		assert_eq!(
+			chain.validate_tx(&agg_tx).unwrap_err().kind(),
+			ErrorKind::NRDRelativeHeight
+		);
+		chain.validate_tx(&tx2).unwrap();
+
+		let b = build_block(&kc, &head, &chain, vec![&tx2]).unwrap();
+		process_block(&chain, &b).unwrap();
+		assert_eq!(chain.head().unwrap().height, activation_height + 2);
+	}
+
+	clean_output_dir(chain_dir);
+}
+
+#[test]
+fn process_block_nrd_validation_fork() {
+	global::set_mining_mode(ChainTypes::AutomatedTesting);
+	util::init_test_logger();
+	let chain_dir = ".mwc.nrd_kernel_fork";
+	clean_output_dir(chain_dir);
+
+	{
+		let chain = init_chain(chain_dir, pow::mine_genesis_block().unwrap());
+		let kc = ExtKeychain::from_random_seed(false).unwrap();
+
+		let (kernel, excess) = build_nrd_kernel(&kc, 2);
+		let tx1 = build_nrd_tx(&kc, &kernel, &excess, 1);
+		let tx2 = build_nrd_tx(&kc, &kernel, &excess, 2);
+
+		let activation_height = global::activation_height(ConsensusRule::NRDKernels).unwrap();
+		let fork_head = mine_empty_blocks(&kc, &chain, activation_height);
+
+		// NRD kernel included in the block after the fork point.
+		let b = build_block(&kc, &fork_head, &chain, vec![&tx1]).unwrap();
+		process_block(&chain, &b).unwrap();
+		let b = build_block(&kc, &b.header, &chain, vec![]).unwrap();
+		process_block(&chain, &b).unwrap();
+
+		// Competing fork without tx1, reusing the kernel in the second fork block.
+		// This is only valid if the NRD kernel index is rewound correctly.
+		let b = build_block(&kc, &fork_head, &chain, vec![]).unwrap();
+		process_block(&chain, &b).unwrap();
+		let b = build_block(&kc, &b.header, &chain, vec![&tx2]).unwrap();
+		process_block(&chain, &b).unwrap();
+		let b = build_block(&kc, &b.header, &chain, vec![]).unwrap();
+		process_block(&chain, &b).unwrap();
+
+		let head = chain.head().unwrap();
+		assert_eq!(head.height, activation_height + 3);
+		assert_eq!(head.last_block_h, b.hash());
+
+		// The kernel from tx2 is now at the relative height on the fork.
+		chain.validate_tx(&tx1).unwrap();
+	}
+
+	clean_output_dir(chain_dir);
+}
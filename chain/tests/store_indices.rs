@@ -12,9 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use self::chain::types::NoopAdapter;
+use self::chain::{Chain, ChainOptions};
 use self::core::core::hash::Hashed;
+use self::core::core::verifier_cache::LruVerifierCache;
+use self::core::pow;
+use self::util::RwLock;
+use mimble_chain as chain;
 use mimble_core as core;
 use mimble_util as util;
+use std::sync::Arc;
 
 mod chain_test_helper;
 
@@ -64,3 +71,228 @@ fn test_store_indices() {
 	// Cleanup chain directory
 	clean_output_dir(chain_dir);
 }
+
+#[test]
+fn test_kernel_pos_index() {
+	util::init_test_logger();
+
+	let chain_dir = ".mwc_idx_2";
+	clean_output_dir(chain_dir);
+
+	// Mine a chain without the kernel_pos index.
+	let genesis = {
+		let chain = mine_chain(chain_dir, 4);
+		assert!(!chain.store().is_kernel_pos_index_built().unwrap());
+		let genesis_hash = chain.get_header_by_height(0).unwrap().hash();
+		chain.get_block(&genesis_hash).unwrap()
+	};
+
+	// Reopen the chain with the kernel_pos index enabled.
+	// The index is built from the existing kernel MMR.
+	let chain = Chain::init(
+		chain_dir.to_string(),
+		Arc::new(NoopAdapter {}),
+		genesis,
+		pow::verify_size,
+		Arc::new(RwLock::new(LruVerifierCache::new())),
+		ChainOptions {
+			kernel_index: true,
+			..ChainOptions::default()
+		},
+	)
+	.unwrap();
+	assert!(chain.store().is_kernel_pos_index_built().unwrap());
+
+	for height in 0..4 {
+		let header = chain.get_header_by_height(height).unwrap();
+		let block = chain.get_block(&header.hash()).unwrap();
+		for kernel in block.kernels() {
+			// Check the kernel_pos index entry.
+			let kernel_pos = chain.store().get_kernel_pos_height(&kernel.excess).unwrap();
+			assert_eq!(kernel_pos.height, height);
+
+			// Check kernel lookup via the index.
+			let (k, h, pos) = chain
+				.get_kernel_height(&kernel.excess, None, None)
+				.unwrap()
+				.unwrap();
+			assert_eq!(k.excess, kernel.excess);
+			assert_eq!(h, height);
+			assert_eq!(pos, kernel_pos.pos);
+
+			// Kernel is not found below the height it was included at.
+			if height > 0 {
+				assert!(chain
+					.get_kernel_height(&kernel.excess, None, Some(height - 1))
+					.unwrap()
+					.is_none());
+			}
+		}
+	}
+
+	// A duplicate excess is indexed on top of the earlier kernel, rewinding
+	// the duplicate restores the earlier kernel entry.
+	{
+		let header = chain.get_header_by_height(1).unwrap();
+		let block = chain.get_block(&header.hash()).unwrap();
+		let excess = block.kernels()[0].excess;
+		let store = chain.store();
+		let batch = store.batch().unwrap();
+		let kernel_pos = batch.get_kernel_pos_height(&excess).unwrap();
+		batch
+			.save_kernel_pos_height(&excess, header.kernel_mmr_size + 10, 5)
+			.unwrap();
+		assert_eq!(batch.get_kernel_pos_height(&excess).unwrap().height, 5);
+
+		batch
+			.rewind_kernel_pos_height(&excess, header.kernel_mmr_size)
+			.unwrap();
+		let restored = batch.get_kernel_pos_height(&excess).unwrap();
+		assert_eq!(restored.pos, kernel_pos.pos);
+		assert_eq!(restored.height, 1);
+
+		batch.rewind_kernel_pos_height(&excess, 0).unwrap();
+		assert!(batch.get_kernel_pos_height(&excess).is_err());
+	}
+
+	// Cleanup chain directory
+	clean_output_dir(chain_dir);
+}
+
+#[test]
+fn test_output_history_index() {
+	util::init_test_logger();
+
+	let chain_dir = ".mwc_idx_3";
+	clean_output_dir(chain_dir);
+
+	// Mine a chain without the output history index.
+	let genesis = {
+		let chain = mine_chain(chain_dir, 4);
+		assert!(!chain.store().is_output_history_index_built().unwrap());
+		let genesis_hash = chain.get_header_by_height(0).unwrap().hash();
+		chain.get_block(&genesis_hash).unwrap()
+	};
+
+	// Reopen the chain in archive mode with the output history index enabled.
+	// The index is built from the full blocks in the db.
+	let chain = Chain::init(
+		chain_dir.to_string(),
+		Arc::new(NoopAdapter {}),
+		genesis,
+		pow::verify_size,
+		Arc::new(RwLock::new(LruVerifierCache::new())),
+		ChainOptions {
+			archive_mode: true,
+			output_history_index: true,
+			..ChainOptions::default()
+		},
+	)
+	.unwrap();
+	assert!(chain.store().is_output_history_index_built().unwrap());
+
+	for height in 0..4 {
+		let header = chain.get_header_by_height(height).unwrap();
+		let block = chain.get_block(&header.hash()).unwrap();
+		for out in block.outputs() {
+			let history = chain.get_output_history(&out.commitment()).unwrap();
+			assert_eq!(history.len(), 1);
+			assert_eq!(history[0].height, height);
+			assert_eq!(
+				history[0].pos,
+				chain.get_output_pos(&out.commitment()).unwrap()
+			);
+			assert!(!history[0].is_spent());
+		}
+	}
+
+	// Outputs are only unspent at or above the height they were created at.
+	let header = chain.get_header_by_height(2).unwrap();
+	let block = chain.get_block(&header.hash()).unwrap();
+	let commits: Vec<_> = block.outputs().iter().map(|x| x.commitment()).collect();
+	let unspent = chain.get_unspent_at_height(&commits, 1).unwrap();
+	assert!(unspent.iter().all(|x| x.is_none()));
+	let unspent = chain.get_unspent_at_height(&commits, 2).unwrap();
+	assert!(unspent
+		.iter()
+		.all(|x| x.as_ref().map(|x| x.height) == Some(2)));
+
+	// Cleanup chain directory
+	clean_output_dir(chain_dir);
+}
+
+#[test]
+fn test_unspent_at_height() {
+	util::init_test_logger();
+
+	let chain_dir = ".mwc_idx_4";
+	clean_output_dir(chain_dir);
+
+	let chain = mine_chain(chain_dir, 4);
+
+	// Check outputs via a readonly rewind to earlier heights.
+	let header = chain.get_header_by_height(2).unwrap();
+	let block = chain.get_block(&header.hash()).unwrap();
+	let commits: Vec<_> = block.outputs().iter().map(|x| x.commitment()).collect();
+	for height in 0..4 {
+		let unspent = chain.get_unspent_at_height(&commits, height).unwrap();
+		assert_eq!(unspent.len(), commits.len());
+		for pos in unspent {
+			if height < 2 {
+				assert!(pos.is_none());
+			} else {
+				let pos = pos.unwrap();
+				assert_eq!(pos.height, 2);
+				assert_eq!(pos.pos, chain.get_output_pos(&commits[0]).unwrap());
+			}
+		}
+	}
+
+	// The chain state is unaffected by the readonly rewind.
+	assert_eq!(chain.head().unwrap().height, 3);
+	assert!(chain.get_unspent_at_height(&commits, 4).is_err());
+
+	// Cleanup chain directory
+	clean_output_dir(chain_dir);
+}
+
+#[test]
+fn test_unspent_at_height_rewind_depth() {
+	util::init_test_logger();
+
+	let chain_dir = ".mwc_idx_5";
+	clean_output_dir(chain_dir);
+
+	// Pruned nodes only rewind a limited number of blocks.
+	let (genesis, commits) = {
+		let chain = mine_chain(chain_dir, 64);
+		let header = chain.get_header_by_height(1).unwrap();
+		let block = chain.get_block(&header.hash()).unwrap();
+		let commits: Vec<_> = block.outputs().iter().map(|x| x.commitment()).collect();
+		assert!(chain.get_unspent_at_height(&commits, 1).is_err());
+		assert!(chain.get_unspent_at_height(&commits, 10).is_ok());
+		let genesis_hash = chain.get_header_by_height(0).unwrap().hash();
+		(chain.get_block(&genesis_hash).unwrap(), commits)
+	};
+
+	// Archive nodes rewind to any height, even without the output history index.
+	let chain = Chain::init(
+		chain_dir.to_string(),
+		Arc::new(NoopAdapter {}),
+		genesis,
+		pow::verify_size,
+		Arc::new(RwLock::new(LruVerifierCache::new())),
+		ChainOptions {
+			archive_mode: true,
+			..ChainOptions::default()
+		},
+	)
+	.unwrap();
+	let unspent = chain.get_unspent_at_height(&commits, 1).unwrap();
+	assert!(unspent
+		.iter()
+		.all(|x| x.as_ref().map(|x| x.height) == Some(1)));
+
+	// Cleanup chain directory
+	clean_output_dir(chain_dir);
+}
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use self::chain::types::NoopAdapter;
-use self::chain::ErrorKind;
+use self::chain::{ChainOptions, ErrorKind};
 use self::core::core::verifier_cache::LruVerifierCache;
 use self::core::core::KernelFeatures;
 use self::core::global::{self, ChainTypes};
@@ -53,7 +53,7 @@ fn test_coinbase_maturity() {
 			genesis_block,
 			pow::verify_size,
 			verifier_cache,
-			false,
+			ChainOptions::default(),
 		)
 		.unwrap();
 
@@ -14,7 +14,12 @@
 
 mod chain_test_helper;
 
-use self::chain_test_helper::{clean_output_dir, mine_chain};
+use self::chain::types::NoStatus;
+use self::chain_test_helper::{clean_output_dir, init_chain, mine_chain};
+use self::core::core::hash::Hashed;
+use mimble_chain as chain;
+use mimble_core as core;
+use std::path::Path;
 
 #[test]
 fn test() {
@@ -25,3 +30,57 @@ fn test() {
 	assert_eq!(10, header.height);
 	clean_output_dir(chain_dir);
 }
+
+#[test]
+fn snapshot_export_import() {
+	let chain_dir = ".txhashset_snapshot_test";
+	let import_dir = ".txhashset_snapshot_import_test";
+	clean_output_dir(chain_dir);
+	clean_output_dir(import_dir);
+	{
+		let chain = mine_chain(chain_dir, 35);
+		let head = chain.head_header().unwrap();
+		let genesis = chain
+			.get_block(&chain.get_header_by_height(0).unwrap().hash())
+			.unwrap();
+		let snapshot = Path::new(chain_dir).join("snapshot.zip");
+		chain.snapshot_export(head.hash(), &snapshot).unwrap();
+
+		let imported = init_chain(import_dir, genesis);
+		let header = imported.snapshot_import(&snapshot, &NoStatus).unwrap();
+		assert_eq!(header.hash(), head.hash());
+		assert_eq!(imported.head().unwrap().last_block_h, head.hash());
+		assert_eq!(imported.header_head().unwrap().last_block_h, head.hash());
+		assert!(imported.validate(true).is_ok());
+
+		// Only an empty chain can be bootstrapped from a snapshot
+		assert!(imported.snapshot_import(&snapshot, &NoStatus).is_err());
+	}
+	clean_output_dir(chain_dir);
+	clean_output_dir(import_dir);
+}
+
+#[test]
+fn snapshot_import_below_horizon() {
+	let chain_dir = ".txhashset_snapshot_short_test";
+	let import_dir = ".txhashset_snapshot_short_import_test";
+	clean_output_dir(chain_dir);
+	clean_output_dir(import_dir);
+	{
+		// Shorter than the horizon, a txhashset wouldn't be needed by sync
+		let chain = mine_chain(chain_dir, 10);
+		let head = chain.head_header().unwrap();
+		let genesis = chain
+			.get_block(&chain.get_header_by_height(0).unwrap().hash())
+			.unwrap();
+		let snapshot = Path::new(chain_dir).join("snapshot.zip");
+		chain.snapshot_export(head.hash(), &snapshot).unwrap();
+
+		let imported = init_chain(import_dir, genesis);
+		let header = imported.snapshot_import(&snapshot, &NoStatus).unwrap();
+		assert_eq!(header.hash(), head.hash());
+		assert_eq!(imported.head().unwrap().last_block_h, head.hash());
+	}
+	clean_output_dir(chain_dir);
+	clean_output_dir(import_dir);
+}
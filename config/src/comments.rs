@@ -71,6 +71,88 @@ fn comments() -> HashMap<String, String> {
 		.to_string(),
 	);
 
+	retval.insert(
+		"api_subscribe_buffer_size".to_string(),
+		"
+#number of events buffered for each client of the foreign API event stream
+#(/v2/foreign/subscribe). Clients that fall further behind are disconnected.
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"api_max_subscribers".to_string(),
+		"
+#maximum number of clients connected to the foreign API event stream at once.
+#New clients above it are rejected until a slot frees up.
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"metrics_http_addr".to_string(),
+		"
+#the address on which to serve Prometheus metrics (/metrics), uncomment to enable
+#metrics_http_addr = \"127.0.0.1:3418\"
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"metrics_secret_path".to_string(),
+		"
+#path of the secret token used for basic auth on the metrics endpoint
+#metrics_secret_path = \".metrics_secret\"
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"api_tokens".to_string(),
+		"
+#scoped API tokens, accepted on the owner and foreign APIs in addition to the
+#API secrets. Clients use basic auth with the token name as user name and the
+#token secret as password. secret_hash is the hex SHA-256 of the secret and
+#allowed lists the permitted methods and v1 routes (\"*\" allows everything).
+#Note: configuring a token turns on authentication of the v1 and v2 Owner APIs
+#even if api_secret_path is not set, only the tokens are then accepted. Tokens
+#are only accepted on the Foreign API if foreign_api_secret_path is set.
+#[[server.api_tokens]]
+#name = \"monitoring\"
+#secret_hash = \"<sha256 of the secret>\"
+#allowed = [\"get_status\", \"get_tip\", \"/v1/status\"]
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"api_rate_limit".to_string(),
+		"
+#per client rate limiting of the foreign API (/v2/foreign), clients are keyed
+#by scoped API token or by IP. Every client has a bucket of capacity tokens,
+#refilled by refill_per_sec tokens every second. Calls cost 1 token unless
+#listed in method_costs, get_unspent_outputs costs 1 more token for every
+#outputs_per_token outputs requested. A call costing more than capacity is
+#accepted with a full bucket, the client then waits for the excess to refill.
+#Uncomment to enable.
+#[server.api_rate_limit]
+#capacity = 100
+#refill_per_sec = 10
+#outputs_per_token = 100
+#method_costs = { push_transaction = 5 }
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"api_max_batch_size".to_string(),
+		"
+#maximum number of calls accepted in a single JSON-RPC batch request
+#on the v2 APIs (owner, foreign and stratum)
+"
+		.to_string(),
+	);
+
 	retval.insert(
 		"db_root".to_string(),
 		"
@@ -89,6 +171,18 @@ fn comments() -> HashMap<String, String> {
 #UserTesting - For regular user testing (cuckoo 16)
 #Floonet - For the long term floonet test network
 #Mainnet - For mainnet
+#Custom - For a user defined network, described by the chain spec file
+#(TOML or JSON) set with chain_spec_file
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"chain_spec_file".to_string(),
+		"
+#the chain spec (TOML or JSON) file of a user defined network, defines
+#the genesis block, consensus parameters, p2p magic bytes, default ports
+#and seeds. Only used with the Custom chain type.
 "
 		.to_string(),
 	);
@@ -112,6 +206,35 @@ fn comments() -> HashMap<String, String> {
 		.to_string(),
 	);
 
+	retval.insert(
+		"kernel_index".to_string(),
+		"
+#maintain an index of kernel excess commitments for fast kernel lookups
+#via the api (built on startup if missing, default is false)
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"output_history_index".to_string(),
+		"
+#maintain an index of output creation and spend heights for the
+#get_output_history api (requires archive_mode, default is false)
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"max_reorg_depth".to_string(),
+		"
+#maximum number of blocks a fork may rewind from our chain head. Deeper forks
+#are refused (the peers sending them are not banned) unless accepted with the
+#accept_reorg owner api. Unlimited if not set.
+#max_reorg_depth = 100
+"
+		.to_string(),
+	);
+
 	retval.insert(
 		"skip_sync_wait".to_string(),
 		"
@@ -197,6 +320,10 @@ fn comments() -> HashMap<String, String> {
 #The url where a POST request will be sent when a new block is received by a peer.
 #block_received_url = \"http://127.0.0.1:8080/block\"
 
+#The url where a POST request will be sent when a reorg deeper than
+#reorg_depth_threshold happens, with the depth and the old and new tips.
+#reorg_url = \"http://127.0.0.1:8080/reorg\"
+
 #The number of worker threads that will be assigned to making the http requests.
 "
 		.to_string(),
@@ -210,6 +337,14 @@ fn comments() -> HashMap<String, String> {
 		.to_string(),
 	);
 
+	retval.insert(
+		"reorg_depth_threshold".to_string(),
+		"
+#Reorgs rewinding more blocks than this are logged and posted to reorg_url.
+"
+		.to_string(),
+	);
+
 	retval.insert(
 		"[server.dandelion_config]".to_string(),
 		"
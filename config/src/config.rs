@@ -22,7 +22,7 @@ use std::fs::{self, File};
 use std::io::prelude::*;
 use std::io::BufReader;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use toml;
 
 use crate::comments::insert_comments;
@@ -112,6 +112,33 @@ fn check_api_secret_files(
 	}
 }
 
+/// Load a chain spec from a TOML file, or a JSON one if the file has a .json
+/// extension.
+pub fn load_chain_spec(file_path: &str) -> Result<global::ChainSpec, ConfigError> {
+	let path = Path::new(file_path);
+	if !path.exists() {
+		return Err(ConfigError::FileNotFoundError(file_path.to_owned()));
+	}
+	let mut contents = String::new();
+	File::open(path)?.read_to_string(&mut contents)?;
+	let spec = if path.extension().map(|x| x == "json").unwrap_or(false) {
+		serde_json::from_str(&contents).map_err(|e| e.to_string())
+	} else {
+		toml::from_str(&contents).map_err(|e| e.to_string())
+	};
+	let spec: global::ChainSpec =
+		spec.map_err(|e| ConfigError::ParseError(file_path.to_owned(), e))?;
+	// Heights (difficulty window, coinbase maturity, etc.) are derived from
+	// the number of blocks in an hour.
+	if spec.block_time_sec == 0 || spec.block_time_sec > 3600 {
+		return Err(ConfigError::ParseError(
+			file_path.to_owned(),
+			"block_time_sec must be between 1 and 3600".to_owned(),
+		));
+	}
+	Ok(spec)
+}
+
 /// Handles setup and detection of paths for node
 pub fn initial_setup_server(chain_type: &global::ChainTypes) -> Result<GlobalConfig, ConfigError> {
 	check_api_secret_files(chain_type, API_SECRET_FILE_NAME)?;
@@ -200,6 +227,21 @@ impl GlobalConfig {
 			global::ChainTypes::AutomatedTesting => {
 				panic!("Can't run automated testing directly");
 			}
+			global::ChainTypes::Custom => {
+				let spec = global::get_chain_spec();
+				defaults.api_http_addr = format!("127.0.0.1:{}", spec.api_port);
+				defaults.p2p_config.port = spec.p2p_port;
+				defaults
+					.stratum_mining_config
+					.as_mut()
+					.unwrap()
+					.stratum_server_addr = Some(format!("127.0.0.1:{}", spec.stratum_port));
+				defaults
+					.stratum_mining_config
+					.as_mut()
+					.unwrap()
+					.wallet_listener_url = format!("http://127.0.0.1:{}", spec.wallet_listener_port);
+			}
 		}
 		defaults_conf
 	}
@@ -338,3 +380,48 @@ fn test_fix_warning_level() {
 	let fixed_config = GlobalConfig::fix_warning_level(config);
 	assert_eq!(fixed_config, "WARN");
 }
+
+#[test]
+fn test_load_chain_spec() {
+	let dir = env::temp_dir().join("mimble_chain_spec_test");
+	fs::create_dir_all(&dir).unwrap();
+	let toml_path = dir.join("spec.toml");
+	let json_path = dir.join("spec.json");
+	fs::write(
+		&toml_path,
+		"name = \"consortium\"\nblock_time_sec = 30\nmagic = [1, 2]\nseeds = [\"seed.example.com\"]\n\n[[consensus_rules]]\nrule = \"nrd_kernels\"\nheight = 100\n",
+	)
+	.unwrap();
+	fs::write(
+		&json_path,
+		"{\"name\": \"consortium\", \"block_time_sec\": 30, \"magic\": [1, 2], \"seeds\": [\"seed.example.com\"], \"consensus_rules\": [{\"rule\": \"nrd_kernels\", \"height\": 100}]}",
+	)
+	.unwrap();
+
+	for path in vec![toml_path, json_path] {
+		let spec = load_chain_spec(path.to_str().unwrap()).unwrap();
+		assert_eq!(spec.name, "consortium");
+		assert_eq!(spec.block_time_sec, 30);
+		assert_eq!(spec.magic, [1, 2]);
+		assert_eq!(spec.seeds, vec!["seed.example.com".to_owned()]);
+		assert_eq!(
+			spec.consensus_rules,
+			vec![global::RuleActivation {
+				rule: global::ConsensusRule::NRDKernels,
+				height: 100,
+			}]
+		);
+		// Everything else defaults to the mainnet values.
+		assert_eq!(
+			spec.max_block_weight,
+			global::ChainSpec::default().max_block_weight
+		);
+	}
+
+	let bad_path = dir.join("bad.toml");
+	fs::write(&bad_path, "block_time_sec = \"soon\"\n").unwrap();
+	assert!(load_chain_spec(bad_path.to_str().unwrap()).is_err());
+	fs::write(&bad_path, "block_time_sec = 0\n").unwrap();
+	assert!(load_chain_spec(bad_path.to_str().unwrap()).is_err());
+	fs::remove_dir_all(&dir).unwrap();
+}
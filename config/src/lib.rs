@@ -32,5 +32,5 @@ mod comments;
 pub mod config;
 pub mod types;
 
-pub use crate::config::initial_setup_server;
+pub use crate::config::{initial_setup_server, load_chain_spec};
 pub use crate::types::{ConfigError, ConfigMembers, GlobalConfig};
@@ -20,7 +20,7 @@
 
 use std::cmp::{max, min};
 
-use crate::global;
+use crate::global::{self, ConsensusRule};
 use crate::core::block::HeaderVersion;
 use crate::pow::Difficulty;
 use crate::core::hash::{Hash, ZERO_HASH};
@@ -54,7 +54,8 @@ pub fn reward(fee: u64, height: u64) -> u64 {
 /// Mimble genesis block reward in nanocoins (0,044100000 coins) to get to exactly 21M max Supply and close the difference from modifications
 pub const GENESIS_BLOCK_REWARD: u64 = 44_100_000;
 
-/// Nominal height for standard time intervals, hour is 60 blocks
+/// Nominal height for standard time intervals at the mainnet block time, hour
+/// is 60 blocks. See global::hour_height (and friends) for the current chain type.
 pub const HOUR_HEIGHT: u64 = 3600 / BLOCK_TIME_SEC;
 /// A day is 1440 blocks
 pub const DAY_HEIGHT: u64 = 24 * HOUR_HEIGHT;
@@ -70,7 +71,7 @@ pub const COINBASE_MATURITY: u64 = DAY_HEIGHT;
 /// function of block height (time). Starts at 90% losing a percent
 /// approximately every week. Represented as an integer between 0 and 100.
 pub fn secondary_pow_ratio(height: u64) -> u64 {
-	90u64.saturating_sub(height / (2 * YEAR_HEIGHT / 90))
+	90u64.saturating_sub(height / (2 * global::year_height() / 90))
 }
 
 /// The AR scale damping factor to use. Dependent on block height
@@ -130,24 +131,26 @@ pub const BLOCK_KERNEL_WEIGHT: usize = 3;
 /// `40_000 / 47 = 851` (txs per block)
 ///
 pub const MAX_BLOCK_WEIGHT: usize = 40_000;
-/// Check whether the block version is valid at a given height, in case of a fork in the future
-pub fn valid_header_version(height: u64, version: HeaderVersion) -> bool {
-
-	version == HeaderVersion(1)
-
 
+/// Check whether the block version is valid at a given height.
+/// Only the header version of the latest active hard-fork is valid.
+pub fn valid_header_version(height: u64, version: HeaderVersion) -> bool {
+	version == header_version(height)
 }
-/// Check whether the block version is valid at a given height, in case there is ever a need for a Fork
+
+/// Header version for a block at the given height, based on the header version
+/// consensus rules active at that height.
 pub fn header_version(height: u64) -> HeaderVersion {
-	//if height < get_c31_hard_fork_block_height() {
+	if global::is_active(ConsensusRule::HeaderVersion2, height) {
+		HeaderVersion(2)
+	} else {
 		HeaderVersion(1)
-	//} else {
-	//	HeaderVersion(2)
-	//}
+	}
 }
 
 
-/// Number of blocks used to calculate difficulty adjustments
+/// Number of blocks used to calculate difficulty adjustments at the mainnet
+/// block time. See global::difficulty_adjust_window for the current chain type.
 pub const DIFFICULTY_ADJUST_WINDOW: u64 = HOUR_HEIGHT;
 
 /// Average time span of the difficulty adjustment window
@@ -269,7 +272,7 @@ pub fn clamp(actual: u64, goal: u64, clamp_factor: u64) -> u64 {
 /// The difficulty calculation is based on both Digishield and GravityWave
 /// family of difficulty computation, coming to something very close to Zcash.
 /// The reference difficulty is an average of the difficulty over a window of
-/// DIFFICULTY_ADJUST_WINDOW blocks. The corresponding timespan is calculated
+/// global::difficulty_adjust_window() blocks. The corresponding timespan is calculated
 /// by using the difference between the median timestamps at the beginning
 /// and the end of the window.
 ///
@@ -282,17 +285,17 @@ where
 	// Create vector of difficulty data running from earliest
 	// to latest, and pad with simulated pre-genesis data to allow earlier
 	// adjustment if there isn't enough window data length will be
-	// DIFFICULTY_ADJUST_WINDOW + 1 (for initial block time bound)
+	// difficulty_adjust_window + 1 (for initial block time bound)
 	let diff_data = global::difficulty_data_to_vector(cursor);
+	let window = global::difficulty_adjust_window();
 
 	// First, get the ratio of secondary PoW vs primary, skipping initial header
 	let sec_pow_scaling = secondary_pow_scaling(height, &diff_data[1..]);
 
 	// Get the timestamp delta across the window
-	let ts_delta: u64 =
-		diff_data[DIFFICULTY_ADJUST_WINDOW as usize].timestamp - diff_data[0].timestamp;
+	let ts_delta: u64 = diff_data[window as usize].timestamp - diff_data[0].timestamp;
 
-	// Get the difficulty sum of the last DIFFICULTY_ADJUST_WINDOW elements
+	// Get the difficulty sum of the last difficulty_adjust_window elements
 	let diff_sum: u64 = diff_data
 		.iter()
 		.skip(1)
@@ -300,13 +303,15 @@ where
 		.sum();
 
 	// adjust time delta toward goal subject to dampening and clamping
+	let block_time_sec = global::block_time_sec();
+	let block_time_window = window * block_time_sec;
 	let adj_ts = clamp(
-		damp(ts_delta, BLOCK_TIME_WINDOW, DIFFICULTY_DAMP_FACTOR),
-		BLOCK_TIME_WINDOW,
+		damp(ts_delta, block_time_window, DIFFICULTY_DAMP_FACTOR),
+		block_time_window,
 		CLAMP_FACTOR,
 	);
 	// minimum difficulty avoids getting stuck due to dampening
-	let difficulty = max(MIN_DIFFICULTY, diff_sum * BLOCK_TIME_SEC / adj_ts);
+	let difficulty = max(MIN_DIFFICULTY, diff_sum * block_time_sec / adj_ts);
 
 	HeaderInfo::from_diff_scaling(Difficulty::from_num(difficulty), sec_pow_scaling)
 }
@@ -318,12 +323,12 @@ pub fn ar_count(_height: u64, diff_data: &[HeaderInfo]) -> u64 {
 
 /// Factor by which the secondary proof of work difficulty will be adjusted
 pub fn secondary_pow_scaling(height: u64, diff_data: &[HeaderInfo]) -> u32 {
-	// Get the scaling factor sum of the last DIFFICULTY_ADJUST_WINDOW elements
+	// Get the scaling factor sum of the last difficulty_adjust_window elements
 	let scale_sum: u64 = diff_data.iter().map(|dd| dd.secondary_scaling as u64).sum();
 
 	// compute ideal 2nd_pow_fraction in pct and across window
 	let target_pct = secondary_pow_ratio(height);
-	let target_count = DIFFICULTY_ADJUST_WINDOW * target_pct;
+	let target_count = global::difficulty_adjust_window() * target_pct;
 
 	// Get the secondary count across the window, adjusting count toward goal
 	// subject to dampening and clamping.
@@ -344,8 +349,9 @@ pub fn secondary_pow_scaling(height: u64, diff_data: &[HeaderInfo]) -> u32 {
 
 // Mimble has block reward schedule similar to bitcoin
 /// Mimble Size of the block group
-const MIMBLE_BLOCKS_PER_GROUP: u64 = 2_100_000; // 4 years
-const MIMBLE_BLOCKS_PER_GROUP_FLOO: u64 = 2_880; // 2 days
+pub const MIMBLE_BLOCKS_PER_GROUP: u64 = 2_100_000; // 4 years
+/// Mimble Size of the block group on floonet
+pub const MIMBLE_BLOCKS_PER_GROUP_FLOO: u64 = 2_880; // 2 days
 /// Mimble Block reward for the first group
 pub const MIMBLE_FIRST_GROUP_REWARD: u64 = 5_238_095_238;
 pub const MIMBLE_SECOND_GROUP_REWARD: u64 = 2_380_952_380;
@@ -366,20 +372,16 @@ pub fn calc_mwc_block_reward(height: u64) -> u64 {
 		// Genesis block
 		return GENESIS_BLOCK_REWARD;
 	}
-	let group_num = if global::is_floonet() {
-		(height-1) / MIMBLE_BLOCKS_PER_GROUP_FLOO
-	} else {
-		(height-1) / MIMBLE_BLOCKS_PER_GROUP
-	};
+	let group_num = (height-1) / global::reward_blocks_per_group();
 	if group_num < 1 {
-		let start_reward = MIMBLE_FIRST_GROUP_REWARD;
+		let start_reward = global::first_group_reward();
 		return start_reward
 		 // First period, increased reward to distribute more coins to first adopters
 	} else if group_num >= MIMBLE_GROUPS_NUM {
 		 0 // far far future, no rewards, sorry
 	} else {
 		//Still in a normal group, calc distribution 
-		let start_reward = MIMBLE_SECOND_GROUP_REWARD * 2;
+		let start_reward = global::second_group_reward() * 2;
 		let group_div = 1 << group_num;
 		println!("{}", group_div);
 		return start_reward / group_div
@@ -388,17 +390,12 @@ pub fn calc_mwc_block_reward(height: u64) -> u64 {
 
 /// Mimble  calculate the total number of rewarded coins in all blocks including this one
 pub fn calc_mwc_block_overage(height: u64, genesis_had_reward: bool) -> u64 {
-	let blocks_per_group = if global::is_floonet() {
-		MIMBLE_BLOCKS_PER_GROUP_FLOO
-	} else {
-		MIMBLE_BLOCKS_PER_GROUP
-	};
+	let blocks_per_group = global::reward_blocks_per_group();
 
 	// including this one happens implicitly.
 	// Because "this block is included", but 0 block (genesis) block is excluded, we will keep height as it is
 	let mut block_count = height;
-	let reward_per_block = MIMBLE_SECOND_GROUP_REWARD;
-	let boostedreward_per_block = MIMBLE_FIRST_GROUP_REWARD;
+	let boostedreward_per_block = global::first_group_reward();
 	let mut overage: u64 = GENESIS_BLOCK_REWARD; // genesis block reward
 
 	for _x in 0..MIMBLE_GROUPS_NUM {
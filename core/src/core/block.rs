@@ -72,6 +72,9 @@ pub enum Error {
 	/// Kernel not valid due to lock_height exceeding block header height
 	#[fail(display = "Block lock_height {} exceeding header height {}", _0, _1)]
 	KernelLockHeight(u64, u64),
+	/// NRD kernel included before NRD kernels activation
+	#[fail(display = "Block NRD kernel not enabled at height {}", _0)]
+	NRDKernelNotEnabled(u64),
 	/// Underlying tx related error
 	#[fail(display = "Block Invalid Transaction, {}", _0)]
 	Transaction(transaction::Error),
@@ -443,7 +446,7 @@ impl Readable for UntrustedBlockHeader {
 	fn read(reader: &mut dyn Reader) -> Result<UntrustedBlockHeader, ser::Error> {
 		let header = read_block_header(reader)?;
 		if header.timestamp
-			> Utc::now() + Duration::seconds(12 * (consensus::BLOCK_TIME_SEC as i64))
+			> global::now() + Duration::seconds(12 * (global::block_time_sec() as i64))
 		{
 			// refuse blocks more than 12 blocks intervals in future (as in bitcoin)
 			// TODO add warning in p2p code if local time is too different from peers
@@ -659,7 +662,7 @@ impl Block {
 		let height = prev.height + 1;		
 		let version = consensus::header_version(height);
 
-		let now = Utc::now().timestamp();
+		let now = global::now().timestamp();
 		let timestamp = DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(now, 0), Utc);
 
 		// Now build the block with all the above information.
@@ -783,6 +786,8 @@ impl Block {
 		prev_kernel_offset: &BlindingFactor,
 		verifier: Arc<RwLock<dyn VerifierCache>>,
 	) -> Result<Commitment, Error> {
+		// Refuse kernel variants not active yet before the (expensive) body validation.
+		self.verify_kernel_features()?;
 		self.body.validate(Weighting::AsBlock, verifier)?;
 
 		self.verify_kernel_lock_heights()?;
@@ -847,6 +852,15 @@ impl Block {
 		}
 		Ok(())
 	}
+
+	// Kernel variants are only valid once their consensus rule is active.
+	// The relative height of NRD kernels is verified against the chain state
+	// by the chain itself.
+	fn verify_kernel_features(&self) -> Result<(), Error> {
+		self.body
+			.verify_kernel_features(self.header.height)
+			.map_err(|_| Error::NRDKernelNotEnabled(self.header.height))
+	}
 }
 
 impl From<UntrustedBlock> for Block {
@@ -17,6 +17,7 @@
 use crate::core::hash::{DefaultHashable, Hashed};
 use crate::core::verifier_cache::VerifierCache;
 use crate::core::{committed, Committed};
+use crate::global::ConsensusRule;
 use crate::libtx::secp_ser;
 use crate::ser::{
 	self, read_multi, PMMRable, ProtocolVersion, Readable, Reader, VerifySortedAndUnique,
@@ -35,6 +36,47 @@ use util::secp::pedersen::{Commitment, RangeProof};
 use util::static_secp_instance;
 use util::RwLock;
 
+/// Relative height of a "no recent duplicate" (NRD) kernel, the minimum number
+/// of blocks between two kernels sharing the same excess.
+/// Limited to a week of blocks (global::week_height), serialized as a u16.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NRDRelativeHeight(u16);
+
+impl DefaultHashable for NRDRelativeHeight {}
+
+impl NRDRelativeHeight {
+	/// Build a relative height, failing if it is not in the 1..=week_height range.
+	pub fn new(height: u64) -> Result<NRDRelativeHeight, Error> {
+		if height == 0 || height > global::week_height() {
+			return Err(Error::InvalidNRDRelativeHeight);
+		}
+		let height: u16 = height
+			.try_into()
+			.map_err(|_| Error::InvalidNRDRelativeHeight)?;
+		Ok(NRDRelativeHeight(height))
+	}
+}
+
+impl From<NRDRelativeHeight> for u64 {
+	fn from(height: NRDRelativeHeight) -> u64 {
+		height.0 as u64
+	}
+}
+
+impl Writeable for NRDRelativeHeight {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_u16(self.0)
+	}
+}
+
+impl Readable for NRDRelativeHeight {
+	fn read(reader: &mut dyn Reader) -> Result<NRDRelativeHeight, ser::Error> {
+		let height = reader.read_u16()?;
+		NRDRelativeHeight::new(height as u64)
+			.map_err(|_| ser::Error::CorruptedData("Invalid NRD relative height".to_string()))
+	}
+}
+
 /// Various tx kernel variants.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum KernelFeatures {
@@ -52,12 +94,21 @@ pub enum KernelFeatures {
 		/// Height locked kernels have lock heights.
 		lock_height: u64,
 	},
+	/// "No recent duplicate" kernel, only valid if no other kernel with the
+	/// same excess was included within the last `relative_height` blocks.
+	NoRecentDuplicate {
+		/// NRD kernels have fees.
+		fee: u64,
+		/// NRD kernels have a relative lock height.
+		relative_height: NRDRelativeHeight,
+	},
 }
 
 impl KernelFeatures {
 	const PLAIN_U8: u8 = 0;
 	const COINBASE_U8: u8 = 1;
 	const HEIGHT_LOCKED_U8: u8 = 2;
+	const NO_RECENT_DUPLICATE_U8: u8 = 3;
 
 	/// Underlying (u8) value representing this kernel variant.
 	/// This is the first byte when we serialize/deserialize the kernel features.
@@ -66,6 +117,7 @@ impl KernelFeatures {
 			KernelFeatures::Plain { .. } => KernelFeatures::PLAIN_U8,
 			KernelFeatures::Coinbase => KernelFeatures::COINBASE_U8,
 			KernelFeatures::HeightLocked { .. } => KernelFeatures::HEIGHT_LOCKED_U8,
+			KernelFeatures::NoRecentDuplicate { .. } => KernelFeatures::NO_RECENT_DUPLICATE_U8,
 		}
 	}
 
@@ -75,18 +127,24 @@ impl KernelFeatures {
 			KernelFeatures::Plain { .. } => String::from("Plain"),
 			KernelFeatures::Coinbase => String::from("Coinbase"),
 			KernelFeatures::HeightLocked { .. } => String::from("HeightLocked"),
+			KernelFeatures::NoRecentDuplicate { .. } => String::from("NoRecentDuplicate"),
 		}
 	}
 
-	/// msg = hash(features)                       for coinbase kernels
-	///       hash(features || fee)                for plain kernels
-	///       hash(features || fee || lock_height) for height locked kernels
+	/// msg = hash(features)                           for coinbase kernels
+	///       hash(features || fee)                    for plain kernels
+	///       hash(features || fee || lock_height)     for height locked kernels
+	///       hash(features || fee || relative_height) for NRD kernels
 	pub fn kernel_sig_msg(&self) -> Result<secp::Message, Error> {
 		let x = self.as_u8();
 		let hash = match self {
 			KernelFeatures::Plain { fee } => (x, fee).hash(),
 			KernelFeatures::Coinbase => (x).hash(),
 			KernelFeatures::HeightLocked { fee, lock_height } => (x, fee, lock_height).hash(),
+			KernelFeatures::NoRecentDuplicate {
+				fee,
+				relative_height,
+			} => (x, fee, relative_height).hash(),
 		};
 
 		let msg = secp::Message::from_slice(&hash.as_bytes())?;
@@ -95,11 +153,16 @@ impl KernelFeatures {
 
 	/// Write tx kernel features out in v1 protocol format.
 	/// Always include the fee and lock_height, writing 0 value if unused.
+	/// The relative height of NRD kernels is written as their lock_height.
 	fn write_v1<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
 		let (fee, lock_height) = match self {
 			KernelFeatures::Plain { fee } => (*fee, 0),
 			KernelFeatures::Coinbase => (0, 0),
 			KernelFeatures::HeightLocked { fee, lock_height } => (*fee, *lock_height),
+			KernelFeatures::NoRecentDuplicate {
+				fee,
+				relative_height,
+			} => (*fee, u64::from(*relative_height)),
 		};
 		writer.write_u8(self.as_u8())?;
 		writer.write_u64(fee)?;
@@ -125,6 +188,14 @@ impl KernelFeatures {
 				writer.write_u64(*fee)?;
 				writer.write_u64(*lock_height)?;
 			}
+			KernelFeatures::NoRecentDuplicate {
+				fee,
+				relative_height,
+			} => {
+				writer.write_u8(self.as_u8())?;
+				writer.write_u64(*fee)?;
+				relative_height.write(writer)?;
+			}
 		}
 		Ok(())
 	}
@@ -160,6 +231,16 @@ impl KernelFeatures {
 				KernelFeatures::Coinbase
 			}
 			KernelFeatures::HEIGHT_LOCKED_U8 => KernelFeatures::HeightLocked { fee, lock_height },
+			KernelFeatures::NO_RECENT_DUPLICATE_U8 => {
+				ser::verify_consensus_rule(ConsensusRule::NRDKernels)?;
+				let relative_height = NRDRelativeHeight::new(lock_height).map_err(|_| {
+					ser::Error::CorruptedData("Invalid NRD relative height".to_string())
+				})?;
+				KernelFeatures::NoRecentDuplicate {
+					fee,
+					relative_height,
+				}
+			}
 			kf => {
 				return Err(ser::Error::CorruptedData(format!(
 					"Invalid kernel feature value {}",
@@ -184,6 +265,15 @@ impl KernelFeatures {
 				let lock_height = reader.read_u64()?;
 				KernelFeatures::HeightLocked { fee, lock_height }
 			}
+			KernelFeatures::NO_RECENT_DUPLICATE_U8 => {
+				ser::verify_consensus_rule(ConsensusRule::NRDKernels)?;
+				let fee = reader.read_u64()?;
+				let relative_height = NRDRelativeHeight::read(reader)?;
+				KernelFeatures::NoRecentDuplicate {
+					fee,
+					relative_height,
+				}
+			}
 			kf => {
 				return Err(ser::Error::CorruptedData(format!(
 					"Invalid kernel feature value {}",
@@ -273,6 +363,12 @@ pub enum Error {
 	/// Signature verification error.
 	#[fail(display = "Tx Invalid signature")]
 	IncorrectSignature,
+	/// NRD kernel relative height out of the 1..=week_height range.
+	#[fail(display = "Tx Invalid NRD kernel relative height")]
+	InvalidNRDRelativeHeight,
+	/// NRD kernels are not active yet at this height.
+	#[fail(display = "Tx NRD kernel not enabled")]
+	NRDKernelNotEnabled,
 	/// Underlying serialization error.
 	#[fail(display = "Tx Serialization error, {}", _0)]
 	Serialization(ser::Error),
@@ -393,6 +489,25 @@ impl KernelFeatures {
 			_ => false,
 		}
 	}
+
+	/// Is this a "no recent duplicate" kernel?
+	pub fn is_nrd(&self) -> bool {
+		match self {
+			KernelFeatures::NoRecentDuplicate { .. } => true,
+			_ => false,
+		}
+	}
+
+	/// Is this kernel variant valid in a block at the given height?
+	/// Variants introduced by a hard-fork are only valid once it is active.
+	pub fn is_active(&self, height: u64) -> bool {
+		match self {
+			KernelFeatures::NoRecentDuplicate { .. } => {
+				global::is_active(ConsensusRule::NRDKernels, height)
+			}
+			_ => true,
+		}
+	}
 }
 
 impl TxKernel {
@@ -411,6 +526,11 @@ impl TxKernel {
 		self.features.is_height_locked()
 	}
 
+	/// Is this a "no recent duplicate" kernel?
+	pub fn is_nrd(&self) -> bool {
+		self.features.is_nrd()
+	}
+
 	/// Return the excess commitment for this tx_kernel.
 	pub fn excess(&self) -> Commitment {
 		self.excess
@@ -449,21 +569,28 @@ impl TxKernel {
 
 	/// Batch signature verification.
 	pub fn batch_sig_verify(tx_kernels: &[TxKernel]) -> Result<(), Error> {
+		let secp = static_secp_instance();
+		let secp = secp.lock();
+		TxKernel::batch_sig_verify_with(&secp, tx_kernels)
+	}
+
+	/// Batch signature verification using the provided secp instance.
+	pub fn batch_sig_verify_with(
+		secp: &secp::Secp256k1,
+		tx_kernels: &[TxKernel],
+	) -> Result<(), Error> {
 		let len = tx_kernels.len();
 		let mut sigs: Vec<secp::Signature> = Vec::with_capacity(len);
 		let mut pubkeys: Vec<secp::key::PublicKey> = Vec::with_capacity(len);
 		let mut msgs: Vec<secp::Message> = Vec::with_capacity(len);
 
-		let secp = static_secp_instance();
-		let secp = secp.lock();
-
 		for tx_kernel in tx_kernels {
 			sigs.push(tx_kernel.excess_sig);
-			pubkeys.push(tx_kernel.excess.to_pubkey(&secp)?);
+			pubkeys.push(tx_kernel.excess.to_pubkey(secp)?);
 			msgs.push(tx_kernel.msg_to_sign()?);
 		}
 
-		if !secp::aggsig::verify_batch(&secp, &sigs, &msgs, &pubkeys) {
+		if !secp::aggsig::verify_batch(secp, &sigs, &msgs, &pubkeys) {
 			return Err(Error::IncorrectSignature);
 		}
 
@@ -704,9 +831,9 @@ impl TransactionBody {
 			.iter()
 			.filter_map(|k| match k.features {
 				KernelFeatures::Coinbase => None,
-				KernelFeatures::Plain { fee } | KernelFeatures::HeightLocked { fee, .. } => {
-					Some(fee)
-				}
+				KernelFeatures::Plain { fee }
+				| KernelFeatures::HeightLocked { fee, .. }
+				| KernelFeatures::NoRecentDuplicate { fee, .. } => Some(fee),
 			})
 			.fold(0, |acc, fee| acc.saturating_add(fee))
 	}
@@ -757,6 +884,14 @@ impl TransactionBody {
 			.unwrap_or(0)
 	}
 
+	/// Verify all kernel variants are valid in a block at the given height.
+	pub fn verify_kernel_features(&self, height: u64) -> Result<(), Error> {
+		if self.kernels.iter().any(|k| !k.features.is_active(height)) {
+			return Err(Error::NRDKernelNotEnabled);
+		}
+		Ok(())
+	}
+
 	/// Verify the body is not too big in terms of number of inputs|outputs|kernels.
 	/// Weight rules vary depending on the "weight type" (block or tx or pool).
 	fn verify_weight(&self, weighting: Weighting) -> Result<(), Error> {
@@ -1518,8 +1653,17 @@ impl Output {
 	/// Batch validates the range proofs using the commitments
 	pub fn batch_verify_proofs(commits: &[Commitment], proofs: &[RangeProof]) -> Result<(), Error> {
 		let secp = static_secp_instance();
-		secp.lock()
-			.verify_bullet_proof_multi(commits.to_vec(), proofs.to_vec(), None)?;
+		let secp = secp.lock();
+		Output::batch_verify_proofs_with(&secp, commits, proofs)
+	}
+
+	/// Batch validates the range proofs using the provided secp instance.
+	pub fn batch_verify_proofs_with(
+		secp: &secp::Secp256k1,
+		commits: &[Commitment],
+		proofs: &[RangeProof],
+	) -> Result<(), Error> {
+		secp.verify_bullet_proof_multi(commits.to_vec(), proofs.to_vec(), None)?;
 		Ok(())
 	}
 }
@@ -1722,6 +1866,9 @@ mod test {
 
 	#[test]
 	fn kernel_features_serialization() {
+		// NRD kernels are only read on a chain type scheduling them.
+		global::set_mining_mode(global::ChainTypes::UserTesting);
+
 		let mut vec = vec![];
 		ser::serialize_default(&mut vec, &(0u8, 10u64, 0u64)).expect("serialized failed");
 		let features: KernelFeatures = ser::deserialize_default(&mut &vec[..]).unwrap();
@@ -1744,12 +1891,34 @@ mod test {
 		);
 
 		let mut vec = vec![];
-		ser::serialize_default(&mut vec, &(3u8, 0u64, 0u64)).expect("serialized failed");
+		ser::serialize_default(&mut vec, &(3u8, 10u64, 100u16)).expect("serialized failed");
+		let features: KernelFeatures = ser::deserialize_default(&mut &vec[..]).unwrap();
+		assert_eq!(
+			features,
+			KernelFeatures::NoRecentDuplicate {
+				fee: 10,
+				relative_height: NRDRelativeHeight::new(100).unwrap(),
+			}
+		);
+
+		// NRD kernels require a non-zero relative height.
+		let mut vec = vec![];
+		ser::serialize_default(&mut vec, &(3u8, 10u64, 0u16)).expect("serialized failed");
+		let res: Result<KernelFeatures, _> = ser::deserialize_default(&mut &vec[..]);
+		assert_eq!(
+			res.err(),
+			Some(ser::Error::CorruptedData(
+				"Invalid NRD relative height".to_string()
+			))
+		);
+
+		let mut vec = vec![];
+		ser::serialize_default(&mut vec, &(4u8, 0u64, 0u64)).expect("serialized failed");
 		let res: Result<KernelFeatures, _> = ser::deserialize_default(&mut &vec[..]);
 		assert_eq!(
 			res.err(),
 			Some(ser::Error::CorruptedData(
-				"Invalid kernel feature value 3".to_string()
+				"Invalid kernel feature value 4".to_string()
 			))
 		);
 	}
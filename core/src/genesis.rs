@@ -21,7 +21,9 @@
 
 use crate::core;
 use crate::core::hash::Hash;
+use crate::global;
 use crate::pow::{Difficulty, Proof, ProofOfWork};
+use crate::ser;
 use chrono::prelude::{TimeZone, Utc};
 use keychain::BlindingFactor;
 use util;
@@ -44,6 +46,24 @@ pub fn genesis_dev() -> core::Block {
 	})
 }
 
+/// Genesis block of a user defined (Custom) network. Deserialized from the
+/// chain spec if it provides one, otherwise a development genesis block at the
+/// chain spec timestamp, which still needs to be mined.
+pub fn genesis_custom() -> Result<core::Block, ser::Error> {
+	let spec = global::get_chain_spec();
+	match spec.genesis {
+		Some(hex) => {
+			let bytes = util::from_hex(&hex).map_err(|e| ser::Error::HexError(e.to_string()))?;
+			ser::deserialize_default(&mut &bytes[..])
+		}
+		None => {
+			let mut gen = genesis_dev();
+			gen.header.timestamp = Utc.timestamp(spec.genesis_timestamp, 0);
+			Ok(gen)
+		}
+	}
+}
+
 /// Floonet genesis block
 pub fn genesis_floo() -> core::Block {
 	let gen = core::Block::with_header(core::BlockHeader {
@@ -17,11 +17,14 @@
 //! should be used sparingly.
 
 use crate::consensus::{
-	graph_weight, HeaderInfo, BASE_EDGE_BITS, BLOCK_TIME_SEC, COINBASE_MATURITY,
-	CUT_THROUGH_HORIZON, DAY_HEIGHT, DEFAULT_MIN_EDGE_BITS, DIFFICULTY_ADJUST_WINDOW,
-	INITIAL_DIFFICULTY, MAX_BLOCK_WEIGHT, PROOFSIZE, SECOND_POW_EDGE_BITS, STATE_SYNC_THRESHOLD,
+	graph_weight, HeaderInfo, BASE_EDGE_BITS, BLOCK_OUTPUT_WEIGHT, BLOCK_TIME_SEC,
+	COINBASE_MATURITY, CUT_THROUGH_HORIZON, DEFAULT_MIN_EDGE_BITS, INITIAL_DIFFICULTY,
+	MAX_BLOCK_WEIGHT, MIMBLE_BLOCKS_PER_GROUP, MIMBLE_BLOCKS_PER_GROUP_FLOO,
+	MIMBLE_FIRST_GROUP_REWARD, MIMBLE_SECOND_GROUP_REWARD, PROOFSIZE, SECOND_POW_EDGE_BITS,
+	STATE_SYNC_THRESHOLD, UNIT_DIFFICULTY,
 };
 use crate::pow::{self, new_cuckarood_ctx, new_cuckatoo_ctx, EdgeType, PoWContext};
+use chrono::prelude::{DateTime, Utc};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use util::RwLock;
@@ -76,6 +79,9 @@ pub const TESTING_INITIAL_DIFFICULTY: u64 = 1;
 /// Testing max_block_weight (artifically low, just enough to support a few txs).
 pub const TESTING_MAX_BLOCK_WEIGHT: usize = 150;
 
+/// Height from which NRD kernels are valid on the testing chains
+pub const TESTING_NRD_ACTIVATION_HEIGHT: u64 = 8;
+
 /// If a peer's last updated difficulty is 2 hours ago and its difficulty's lower than ours,
 /// we're sure this peer is a stuck node, and we will kick out such kind of stuck peers.
 pub const STUCK_PEER_KICK_TIME: i64 = 2 * 3600 * 1000;
@@ -91,7 +97,9 @@ pub const PEER_EXPIRATION_REMOVE_TIME: i64 = PEER_EXPIRATION_DAYS * 24 * 3600;
 /// Will compact the txhashset to remove pruned data.
 /// Will also remove old blocks and associated data from the database.
 /// For a node configured as "archival_mode = true" only the txhashset will be compacted.
-pub const COMPACTION_CHECK: u64 = DAY_HEIGHT;
+pub fn compaction_check() -> u64 {
+	day_height()
+}
 
 /// Number of blocks to reuse a txhashset zip for (automated testing and user testing).
 pub const TESTING_TXHASHSET_ARCHIVE_INTERVAL: u64 = 10;
@@ -111,6 +119,8 @@ pub enum ChainTypes {
 	Floonet,
 	/// Main production network
 	Mainnet,
+	/// User defined network, described by the chain spec (see set_chain_spec)
+	Custom,
 }
 
 impl ChainTypes {
@@ -121,6 +131,7 @@ impl ChainTypes {
 			ChainTypes::UserTesting => "user".to_owned(),
 			ChainTypes::Floonet => "floo".to_owned(),
 			ChainTypes::Mainnet => "main".to_owned(),
+			ChainTypes::Custom => CHAIN_SPEC.read().name.clone(),
 		}
 	}
 }
@@ -131,6 +142,145 @@ impl Default for ChainTypes {
 	}
 }
 
+/// Parameters of a user defined (ChainTypes::Custom) network, usually loaded
+/// from a TOML or JSON chain spec file. Anything not specified defaults to the
+/// mainnet values, heights being scaled to the chain spec block time.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ChainSpec {
+	/// Network name, also used for the node data directory
+	pub name: String,
+	/// Hex of the serialized genesis block. If not set, the node mines the
+	/// genesis block from genesis_timestamp on its first start (which requires
+	/// small edge bits) and saves it to its db root.
+	pub genesis: Option<String>,
+	/// Timestamp (seconds since the unix epoch) of a mined on the fly genesis block
+	pub genesis_timestamp: i64,
+	/// Block interval, in seconds, the difficulty adjustment targets (at most an hour)
+	pub block_time_sec: u64,
+	/// Number of blocks before a coinbase matures and can be spent,
+	/// a day of blocks if not set
+	pub coinbase_maturity: Option<u64>,
+	/// Horizon at which we can cut-through and do full local pruning,
+	/// a week of blocks if not set
+	pub cut_through_horizon: Option<u32>,
+	/// Threshold at which we can request a txhashset (and full blocks from),
+	/// two days of blocks if not set
+	pub state_sync_threshold: Option<u32>,
+	/// Maximum allowed block weight
+	pub max_block_weight: usize,
+	/// The minimum acceptable edge_bits
+	pub min_edge_bits: u8,
+	/// Reference edge_bits for the graph weight
+	pub base_edge_bits: u8,
+	/// The proofsize
+	pub proof_size: usize,
+	/// Initial mining difficulty
+	pub initial_difficulty: u64,
+	/// Initial mining secondary scale
+	pub initial_graph_weight: u32,
+	/// Number of blocks in a reward group, the reward halves on every group
+	/// after the first one
+	pub reward_blocks_per_group: u64,
+	/// Block reward of the first group
+	pub first_group_reward: u64,
+	/// Block reward of the second group
+	pub second_group_reward: u64,
+	/// Consensus rules scheduled on this network, in activation order
+	pub consensus_rules: Vec<RuleActivation>,
+	/// Magic bytes prefixing every p2p message
+	pub magic: [u8; 2],
+	/// Default p2p port
+	pub p2p_port: u16,
+	/// Default api port
+	pub api_port: u16,
+	/// Default stratum server port
+	pub stratum_port: u16,
+	/// Default wallet listener port
+	pub wallet_listener_port: u16,
+	/// DNS seeds, connected to on the p2p port
+	pub seeds: Vec<String>,
+}
+
+impl Default for ChainSpec {
+	fn default() -> ChainSpec {
+		ChainSpec {
+			name: "custom".to_owned(),
+			genesis: None,
+			genesis_timestamp: 0,
+			block_time_sec: BLOCK_TIME_SEC,
+			coinbase_maturity: None,
+			cut_through_horizon: None,
+			state_sync_threshold: None,
+			max_block_weight: MAX_BLOCK_WEIGHT,
+			min_edge_bits: DEFAULT_MIN_EDGE_BITS,
+			base_edge_bits: BASE_EDGE_BITS,
+			proof_size: PROOFSIZE,
+			initial_difficulty: INITIAL_DIFFICULTY,
+			initial_graph_weight: UNIT_DIFFICULTY as u32,
+			reward_blocks_per_group: MIMBLE_BLOCKS_PER_GROUP,
+			first_group_reward: MIMBLE_FIRST_GROUP_REWARD,
+			second_group_reward: MIMBLE_SECOND_GROUP_REWARD,
+			consensus_rules: vec![],
+			magic: [21, 19],
+			p2p_port: 3414,
+			api_port: 3413,
+			stratum_port: 3416,
+			wallet_listener_port: 3415,
+			seeds: vec![],
+		}
+	}
+}
+
+impl ChainSpec {
+	/// Nominal height of an hour of blocks at the block time of this chain spec.
+	pub fn hour_height(&self) -> u64 {
+		3600 / self.block_time_sec
+	}
+}
+
+/// Activation height of a consensus rule in a chain spec.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct RuleActivation {
+	/// The consensus rule
+	pub rule: ConsensusRule,
+	/// Height from which the rule applies
+	pub height: u64,
+}
+
+/// Named consensus rules (hard-forks). Each rule is activated from a chain type
+/// specific height, see the activation tables below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConsensusRule {
+	/// Block header version 2
+	#[serde(rename = "header_version_2")]
+	HeaderVersion2,
+	/// "No recent duplicate" (NRD) relative lock kernels
+	#[serde(rename = "nrd_kernels")]
+	NRDKernels,
+}
+
+impl ConsensusRule {
+	/// Name of the rule as reported by the api.
+	pub fn name(&self) -> &'static str {
+		match *self {
+			ConsensusRule::HeaderVersion2 => "header_version_2",
+			ConsensusRule::NRDKernels => "nrd_kernels",
+		}
+	}
+}
+
+/// Consensus rule activation heights on mainnet, in activation order.
+/// Rules missing from a table are not scheduled on that chain.
+const MAINNET_ACTIVATION_HEIGHTS: &[(ConsensusRule, u64)] = &[];
+
+/// Consensus rule activation heights on floonet, in activation order.
+const FLOONET_ACTIVATION_HEIGHTS: &[(ConsensusRule, u64)] = &[];
+
+/// Consensus rule activation heights on the testing chains, in activation order.
+const TESTING_ACTIVATION_HEIGHTS: &[(ConsensusRule, u64)] =
+	&[(ConsensusRule::NRDKernels, TESTING_NRD_ACTIVATION_HEIGHT)];
+
 /// PoW test mining and verifier context
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum PoWContextTypes {
@@ -147,6 +297,14 @@ lazy_static! {
 	pub static ref CHAIN_TYPE: RwLock<ChainTypes> =
 			RwLock::new(ChainTypes::Mainnet);
 
+	/// Parameters of the Custom chain type
+	pub static ref CHAIN_SPEC: RwLock<ChainSpec> =
+			RwLock::new(ChainSpec::default());
+
+	/// Mock time overriding the system clock, see [`now`](fn.now.html)
+	pub static ref MOCK_TIME: RwLock<Option<DateTime<Utc>>> =
+			RwLock::new(None);
+
 	/// PoW context type to instantiate
 	pub static ref POW_CONTEXT_TYPE: RwLock<PoWContextTypes> =
 			RwLock::new(PoWContextTypes::Cuckoo);
@@ -162,6 +320,37 @@ pub fn set_mining_mode(mode: ChainTypes) {
 	*param_ref = mode;
 }
 
+/// Set the parameters of the Custom chain type
+pub fn set_chain_spec(spec: ChainSpec) {
+	let mut param_ref = CHAIN_SPEC.write();
+	*param_ref = spec;
+}
+
+/// Parameters of the Custom chain type
+pub fn get_chain_spec() -> ChainSpec {
+	CHAIN_SPEC.read().clone()
+}
+
+/// Set the mock time returned by [`now`](fn.now.html) instead of the system
+/// clock, or go back to the system clock with `None`. The mock time stays
+/// still until set again.
+pub fn set_mock_time(time: Option<DateTime<Utc>>) {
+	let mut param_ref = MOCK_TIME.write();
+	*param_ref = time;
+}
+
+/// The mock time, if any
+pub fn get_mock_time() -> Option<DateTime<Utc>> {
+	*MOCK_TIME.read()
+}
+
+/// Current time of the node, the mock time if set or the system clock.
+/// Time dependent consensus and network rules must read the time from here
+/// so tests can move the clock forward.
+pub fn now() -> DateTime<Utc> {
+	get_mock_time().unwrap_or_else(Utc::now)
+}
+
 /// Return either a cuckoo context or a cuckatoo context
 /// Single change point
 /// MWC: We modify this to launch with cuckarood only on both floonet and mainnet
@@ -195,6 +384,7 @@ pub fn min_edge_bits() -> u8 {
 	match *param_ref {
 		ChainTypes::AutomatedTesting => AUTOMATED_TESTING_MIN_EDGE_BITS,
 		ChainTypes::UserTesting => USER_TESTING_MIN_EDGE_BITS,
+		ChainTypes::Custom => CHAIN_SPEC.read().min_edge_bits,
 		_ => DEFAULT_MIN_EDGE_BITS,
 	}
 }
@@ -207,6 +397,7 @@ pub fn base_edge_bits() -> u8 {
 	match *param_ref {
 		ChainTypes::AutomatedTesting => AUTOMATED_TESTING_MIN_EDGE_BITS,
 		ChainTypes::UserTesting => USER_TESTING_MIN_EDGE_BITS,
+		ChainTypes::Custom => CHAIN_SPEC.read().base_edge_bits,
 		_ => BASE_EDGE_BITS,
 	}
 }
@@ -217,6 +408,7 @@ pub fn proofsize() -> usize {
 	match *param_ref {
 		ChainTypes::AutomatedTesting => AUTOMATED_TESTING_PROOF_SIZE,
 		ChainTypes::UserTesting => USER_TESTING_PROOF_SIZE,
+		ChainTypes::Custom => CHAIN_SPEC.read().proof_size,
 		_ => PROOFSIZE,
 	}
 }
@@ -227,6 +419,10 @@ pub fn coinbase_maturity() -> u64 {
 	match *param_ref {
 		ChainTypes::AutomatedTesting => AUTOMATED_TESTING_COINBASE_MATURITY,
 		ChainTypes::UserTesting => USER_TESTING_COINBASE_MATURITY,
+		ChainTypes::Custom => {
+			let spec = CHAIN_SPEC.read();
+			spec.coinbase_maturity.unwrap_or(24 * spec.hour_height())
+		}
 		_ => COINBASE_MATURITY,
 	}
 }
@@ -239,6 +435,7 @@ pub fn initial_block_difficulty() -> u64 {
 		ChainTypes::UserTesting => TESTING_INITIAL_DIFFICULTY,
 		ChainTypes::Floonet => INITIAL_DIFFICULTY,
 		ChainTypes::Mainnet => INITIAL_DIFFICULTY,
+		ChainTypes::Custom => CHAIN_SPEC.read().initial_difficulty,
 	}
 }
 /// Initial mining secondary scale
@@ -249,6 +446,7 @@ pub fn initial_graph_weight() -> u32 {
 		ChainTypes::UserTesting => TESTING_INITIAL_GRAPH_WEIGHT,
 		ChainTypes::Floonet => graph_weight(0, SECOND_POW_EDGE_BITS) as u32,
 		ChainTypes::Mainnet => graph_weight(0, SECOND_POW_EDGE_BITS) as u32,
+		ChainTypes::Custom => CHAIN_SPEC.read().initial_graph_weight,
 	}
 }
 
@@ -260,6 +458,74 @@ pub fn max_block_weight() -> usize {
 		ChainTypes::UserTesting => TESTING_MAX_BLOCK_WEIGHT,
 		ChainTypes::Floonet => MAX_BLOCK_WEIGHT,
 		ChainTypes::Mainnet => MAX_BLOCK_WEIGHT,
+		ChainTypes::Custom => CHAIN_SPEC.read().max_block_weight,
+	}
+}
+
+/// Max theoretical size of a serialized block filled with outputs.
+pub fn max_block_size() -> u64 {
+	(max_block_weight() / BLOCK_OUTPUT_WEIGHT * 708) as u64
+}
+
+/// Block interval, in seconds, the difficulty adjustment targets.
+pub fn block_time_sec() -> u64 {
+	let param_ref = CHAIN_TYPE.read();
+	match *param_ref {
+		ChainTypes::Custom => CHAIN_SPEC.read().block_time_sec,
+		_ => BLOCK_TIME_SEC,
+	}
+}
+
+/// Nominal height of an hour of blocks on the current chain type.
+pub fn hour_height() -> u64 {
+	3600 / block_time_sec()
+}
+
+/// Nominal height of a day of blocks on the current chain type.
+pub fn day_height() -> u64 {
+	24 * hour_height()
+}
+
+/// Nominal height of a week of blocks on the current chain type.
+pub fn week_height() -> u64 {
+	7 * day_height()
+}
+
+/// Nominal height of a year of blocks on the current chain type.
+pub fn year_height() -> u64 {
+	52 * week_height()
+}
+
+/// Number of blocks used to calculate difficulty adjustments.
+pub fn difficulty_adjust_window() -> u64 {
+	hour_height()
+}
+
+/// Number of blocks in a block reward group.
+pub fn reward_blocks_per_group() -> u64 {
+	let param_ref = CHAIN_TYPE.read();
+	match *param_ref {
+		ChainTypes::Floonet => MIMBLE_BLOCKS_PER_GROUP_FLOO,
+		ChainTypes::Custom => CHAIN_SPEC.read().reward_blocks_per_group,
+		_ => MIMBLE_BLOCKS_PER_GROUP,
+	}
+}
+
+/// Block reward of the first reward group.
+pub fn first_group_reward() -> u64 {
+	let param_ref = CHAIN_TYPE.read();
+	match *param_ref {
+		ChainTypes::Custom => CHAIN_SPEC.read().first_group_reward,
+		_ => MIMBLE_FIRST_GROUP_REWARD,
+	}
+}
+
+/// Block reward of the second reward group, halving on every group after it.
+pub fn second_group_reward() -> u64 {
+	let param_ref = CHAIN_TYPE.read();
+	match *param_ref {
+		ChainTypes::Custom => CHAIN_SPEC.read().second_group_reward,
+		_ => MIMBLE_SECOND_GROUP_REWARD,
 	}
 }
 
@@ -269,6 +535,11 @@ pub fn cut_through_horizon() -> u32 {
 	match *param_ref {
 		ChainTypes::AutomatedTesting => AUTOMATED_TESTING_CUT_THROUGH_HORIZON,
 		ChainTypes::UserTesting => USER_TESTING_CUT_THROUGH_HORIZON,
+		ChainTypes::Custom => {
+			let spec = CHAIN_SPEC.read();
+			spec.cut_through_horizon
+				.unwrap_or(7 * 24 * spec.hour_height() as u32)
+		}
 		_ => CUT_THROUGH_HORIZON,
 	}
 }
@@ -279,6 +550,11 @@ pub fn state_sync_threshold() -> u32 {
 	match *param_ref {
 		ChainTypes::AutomatedTesting => TESTING_STATE_SYNC_THRESHOLD,
 		ChainTypes::UserTesting => TESTING_STATE_SYNC_THRESHOLD,
+		ChainTypes::Custom => {
+			let spec = CHAIN_SPEC.read();
+			spec.state_sync_threshold
+				.unwrap_or(2 * 24 * spec.hour_height() as u32)
+		}
 		_ => STATE_SYNC_THRESHOLD,
 	}
 }
@@ -293,6 +569,43 @@ pub fn txhashset_archive_interval() -> u64 {
 	}
 }
 
+/// Consensus rules scheduled on the current chain type with their activation
+/// heights, in activation order.
+pub fn consensus_rules() -> Vec<(ConsensusRule, u64)> {
+	let param_ref = CHAIN_TYPE.read();
+	let rules = match *param_ref {
+		ChainTypes::AutomatedTesting => TESTING_ACTIVATION_HEIGHTS,
+		ChainTypes::UserTesting => TESTING_ACTIVATION_HEIGHTS,
+		ChainTypes::Floonet => FLOONET_ACTIVATION_HEIGHTS,
+		ChainTypes::Mainnet => MAINNET_ACTIVATION_HEIGHTS,
+		ChainTypes::Custom => {
+			return CHAIN_SPEC
+				.read()
+				.consensus_rules
+				.iter()
+				.map(|x| (x.rule, x.height))
+				.collect();
+		}
+	};
+	rules.to_vec()
+}
+
+/// Height from which the consensus rule applies on the current chain type.
+/// None if the rule is not scheduled.
+pub fn activation_height(rule: ConsensusRule) -> Option<u64> {
+	consensus_rules()
+		.into_iter()
+		.find(|(x, _)| *x == rule)
+		.map(|(_, height)| height)
+}
+
+/// Does the consensus rule apply to a block at the given height?
+pub fn is_active(rule: ConsensusRule, height: u64) -> bool {
+	activation_height(rule)
+		.map(|x| height >= x)
+		.unwrap_or(false)
+}
+
 /// Are we in production mode?
 /// Production defined as a live public network, testnet[n] or mainnet.
 pub fn is_production_mode() -> bool {
@@ -329,7 +642,7 @@ where
 	T: IntoIterator<Item = HeaderInfo>,
 {
 	// Convert iterator to vector, so we can append to it if necessary
-	let needed_block_count = DIFFICULTY_ADJUST_WINDOW as usize + 1;
+	let needed_block_count = difficulty_adjust_window() as usize + 1;
 	let mut last_n: Vec<HeaderInfo> = cursor.into_iter().take(needed_block_count).collect();
 
 	// Only needed just after blockchain launch... basically ensures there's
@@ -340,7 +653,7 @@ where
 		let last_ts_delta = if n > 1 {
 			last_n[0].timestamp - last_n[1].timestamp
 		} else {
-			BLOCK_TIME_SEC
+			block_time_sec()
 		};
 		let last_diff = last_n[0].difficulty;
 
@@ -243,6 +243,42 @@ where
 	Ok(tx)
 }
 
+/// Builds a complete transaction around a pre-built (and signed) kernel.
+/// The tx offset is set to the difference between the blinding factors of the
+/// inputs and outputs and the provided kernel excess.
+/// Useful when reusing a kernel excess across multiple transactions (NRD kernels).
+pub fn transaction_with_kernel<K, B>(
+	elems: Vec<Box<Append<K, B>>>,
+	kernel: TxKernel,
+	excess: BlindingFactor,
+	keychain: &K,
+	builder: &B,
+) -> Result<Transaction, Error>
+where
+	K: Keychain,
+	B: ProofBuild,
+{
+	let mut ctx = Context { keychain, builder };
+	let (mut tx, sum) = elems
+		.iter()
+		.fold(Ok((Transaction::empty(), BlindSum::new())), |acc, elem| {
+			elem(&mut ctx, acc)
+		})?;
+	let blind_sum = ctx.keychain.blind_sum(&sum)?;
+
+	// Offset is the sum of all blinding factors minus the kernel excess.
+	tx.offset = ctx.keychain.blind_sum(
+		&BlindSum::new()
+			.add_blinding_factor(blind_sum)
+			.sub_blinding_factor(excess),
+	)?;
+
+	// Set the kernel on the tx.
+	let tx = tx.replace_kernel(kernel);
+
+	Ok(tx)
+}
+
 // Just a simple test, most exhaustive tests in the core.
 #[cfg(test)]
 mod test {
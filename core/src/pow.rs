@@ -77,8 +77,12 @@ pub fn verify_size(bh: &BlockHeader) -> Result<(), Error> {
 
 /// Mines a genesis block using the internal miner
 pub fn mine_genesis_block() -> Result<Block, Error> {
-	let mut gen = genesis::genesis_dev();
+	mine_genesis(genesis::genesis_dev())
+}
 
+/// Mines the provided genesis block using the internal miner. Mining is
+/// deterministic, the same block is always mined from the same input.
+pub fn mine_genesis(mut gen: Block) -> Result<Block, Error> {
 	// total_difficulty on the genesis header *is* the difficulty of that block
 	let genesis_difficulty = gen.header.pow.total_difficulty;
 
@@ -20,7 +20,7 @@
 //! `serialize` or `deserialize` functions on them as appropriate.
 
 use crate::core::hash::{DefaultHashable, Hash, Hashed};
-use crate::global::PROTOCOL_VERSION;
+use crate::global::{self, ConsensusRule, PROTOCOL_VERSION};
 use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
 use keychain::{BlindingFactor, Identifier, IDENTIFIER_SIZE};
 use std::convert::TryInto;
@@ -351,6 +351,19 @@ where
 	fn read(reader: &mut dyn Reader) -> Result<Self, Error>;
 }
 
+/// Data introduced by a consensus rule (a new kernel variant say) can only be
+/// read on a chain type where the rule is scheduled. The activation height
+/// itself is verified as part of block and tx validation.
+pub fn verify_consensus_rule(rule: ConsensusRule) -> Result<(), Error> {
+	if global::activation_height(rule).is_none() {
+		return Err(Error::CorruptedData(format!(
+			"Consensus rule {} not scheduled",
+			rule.name()
+		)));
+	}
+	Ok(())
+}
+
 /// Deserializes a Readable from any std::io::Read implementation.
 pub fn deserialize<T: Readable>(
 	source: &mut dyn Read,
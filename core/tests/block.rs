@@ -22,14 +22,15 @@ use crate::core::core::transaction::{self, Transaction};
 use crate::core::core::verifier_cache::{LruVerifierCache, VerifierCache};
 use crate::core::core::Committed;
 use crate::core::core::{
-	Block, BlockHeader, CompactBlock, HeaderVersion, KernelFeatures, OutputFeatures,
+	Block, BlockHeader, CompactBlock, HeaderVersion, KernelFeatures, NRDRelativeHeight,
+	OutputFeatures,
 };
 use crate::core::libtx::build::{self, input, output};
 use crate::core::libtx::ProofBuilder;
 use crate::core::{global, ser};
 use chrono::Duration;
 use mimble_core as core;
-use mimble_core::global::ChainTypes;
+use mimble_core::global::{ChainTypes, ConsensusRule};
 use keychain::{BlindingFactor, ExtKeychain, Keychain};
 use std::sync::Arc;
 use util::secp;
@@ -594,3 +595,43 @@ fn validate_header_proof() {
 	)
 	.is_err());
 }
+
+#[test]
+fn block_with_nrd_kernel_activation() {
+	global::set_mining_mode(ChainTypes::AutomatedTesting);
+	let keychain = ExtKeychain::from_random_seed(false).unwrap();
+	let builder = ProofBuilder::new(&keychain);
+	let key_id1 = ExtKeychain::derive_key_id(1, 1, 0, 0, 0);
+	let key_id2 = ExtKeychain::derive_key_id(1, 2, 0, 0, 0);
+	let key_id3 = ExtKeychain::derive_key_id(1, 3, 0, 0, 0);
+
+	let tx = build::transaction(
+		KernelFeatures::NoRecentDuplicate {
+			fee: 2,
+			relative_height: NRDRelativeHeight::new(1440).unwrap(),
+		},
+		vec![input(7, key_id1), output(5, key_id2)],
+		&keychain,
+		&builder,
+	)
+	.unwrap();
+
+	let activation_height = global::activation_height(ConsensusRule::NRDKernels).unwrap();
+
+	// Block just before the activation height is invalid.
+	let mut prev = BlockHeader::default();
+	prev.height = activation_height - 2;
+	let b = new_block(vec![&tx], &keychain, &builder, &prev, &key_id3);
+	assert_eq!(b.header.height, activation_height - 1);
+	assert_eq!(
+		b.validate(&BlindingFactor::zero(), verifier_cache()),
+		Err(Error::NRDKernelNotEnabled(activation_height - 1)),
+	);
+
+	// Block at the activation height is valid.
+	prev.height = activation_height - 1;
+	let b = new_block(vec![&tx], &keychain, &builder, &prev, &key_id3);
+	assert_eq!(b.header.height, activation_height);
+	b.validate(&BlindingFactor::zero(), verifier_cache())
+		.unwrap();
+}
@@ -13,11 +13,13 @@
 
 use chrono::Utc;
 use mimble_core::consensus::{
-	next_difficulty, HeaderInfo, AR_SCALE_DAMP_FACTOR, BLOCK_TIME_SEC, DIFFICULTY_ADJUST_WINDOW,
-	MIN_DIFFICULTY,
+	header_version, next_difficulty, valid_header_version, HeaderInfo, AR_SCALE_DAMP_FACTOR,
+	BLOCK_TIME_SEC, DIFFICULTY_ADJUST_WINDOW, MIN_DIFFICULTY, YEAR_HEIGHT,
 };
-use mimble_core::global;
+use mimble_core::core::{HeaderVersion, KernelFeatures, NRDRelativeHeight};
+use mimble_core::global::{self, ConsensusRule};
 use mimble_core::pow::Difficulty;
+use mimble_core::ser::{self, ProtocolVersion};
 
 /// Checks different next_target adjustments and difficulty boundaries
 #[test]
@@ -123,6 +125,65 @@ fn next_target_adjustment() {
 	);
 }
 
+/// Checks consensus rules switch on exactly at their activation height
+#[test]
+fn consensus_rules_activation() {
+	global::set_mining_mode(global::ChainTypes::AutomatedTesting);
+
+	let height = global::activation_height(ConsensusRule::NRDKernels).unwrap();
+	assert_eq!(height, global::TESTING_NRD_ACTIVATION_HEIGHT);
+	assert_eq!(
+		global::consensus_rules(),
+		vec![(ConsensusRule::NRDKernels, height)]
+	);
+
+	let nrd = KernelFeatures::NoRecentDuplicate {
+		fee: 1,
+		relative_height: NRDRelativeHeight::new(10).unwrap(),
+	};
+	let plain = KernelFeatures::Plain { fee: 1 };
+
+	// Just before the activation height.
+	assert!(!global::is_active(ConsensusRule::NRDKernels, height - 1));
+	assert!(!nrd.is_active(height - 1));
+	assert!(plain.is_active(height - 1));
+
+	// At (and after) the activation height.
+	assert!(global::is_active(ConsensusRule::NRDKernels, height));
+	assert!(global::is_active(ConsensusRule::NRDKernels, height + 1));
+	assert!(nrd.is_active(height));
+	assert!(plain.is_active(height));
+
+	// Header version 2 is not scheduled, only version 1 headers are valid.
+	assert_eq!(
+		global::activation_height(ConsensusRule::HeaderVersion2),
+		None
+	);
+	for height in vec![0, height - 1, height, YEAR_HEIGHT] {
+		assert!(!global::is_active(ConsensusRule::HeaderVersion2, height));
+		assert_eq!(header_version(height), HeaderVersion(1));
+		assert!(valid_header_version(height, HeaderVersion(1)));
+		assert!(!valid_header_version(height, HeaderVersion(2)));
+	}
+}
+
+/// Checks NRD kernel features roundtrip once the rule is scheduled on the chain type
+#[test]
+fn nrd_kernel_features_serialization() {
+	global::set_mining_mode(global::ChainTypes::AutomatedTesting);
+
+	let nrd = KernelFeatures::NoRecentDuplicate {
+		fee: 10,
+		relative_height: NRDRelativeHeight::new(100).unwrap(),
+	};
+	for version in vec![ProtocolVersion(1), ProtocolVersion(2)] {
+		let mut vec = vec![];
+		ser::serialize(&mut vec, version, &nrd).expect("serialization failed");
+		let features: KernelFeatures = ser::deserialize(&mut &vec[..], version).unwrap();
+		assert_eq!(features, nrd);
+	}
+}
+
 // Builds an iterator for next difficulty calculation with the provided
 // constant time interval, difficulty and total length.
 fn repeat(interval: u64, diff: HeaderInfo, len: u64, cur_time: Option<u64>) -> Vec<HeaderInfo> {
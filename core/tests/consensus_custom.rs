@@ -0,0 +1,117 @@
+// Copyright 2020 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! core consensus tests for a user defined (custom) chain type
+
+//! Setting global::mining_mode() changes global shared state so automated tests should only use one
+//! mining mode/chain type per test file to avoid non-deterministic behaviour.
+
+use mimble_core as core;
+
+use self::core::consensus::{calc_mwc_block_reward, next_difficulty, HeaderInfo};
+use self::core::core::hash::Hashed;
+use self::core::global::{self, ChainSpec, ChainTypes, ConsensusRule, RuleActivation};
+use self::core::pow::{self, Difficulty};
+use self::core::{genesis, ser};
+
+fn custom_spec() -> ChainSpec {
+	ChainSpec {
+		name: "consortium".to_owned(),
+		genesis_timestamp: 1_600_000_000,
+		block_time_sec: 30,
+		coinbase_maturity: Some(10),
+		max_block_weight: 1_000,
+		min_edge_bits: 10,
+		base_edge_bits: 10,
+		proof_size: 8,
+		initial_difficulty: 1,
+		initial_graph_weight: 1,
+		reward_blocks_per_group: 100,
+		first_group_reward: 1_000,
+		second_group_reward: 400,
+		consensus_rules: vec![RuleActivation {
+			rule: ConsensusRule::NRDKernels,
+			height: 50,
+		}],
+		magic: [1, 2],
+		..ChainSpec::default()
+	}
+}
+
+#[test]
+fn custom_chain_type() {
+	global::set_chain_spec(custom_spec());
+	global::set_mining_mode(ChainTypes::Custom);
+
+	assert_eq!(ChainTypes::Custom.shortname(), "consortium");
+	assert_eq!(global::block_time_sec(), 30);
+	assert_eq!(global::coinbase_maturity(), 10);
+	assert_eq!(global::max_block_weight(), 1_000);
+	assert_eq!(global::min_edge_bits(), 10);
+	assert_eq!(global::proofsize(), 8);
+	assert_eq!(global::initial_block_difficulty(), 1);
+	assert!(!global::is_production_mode());
+
+	// Heights not set by the chain spec are derived from its block time.
+	assert_eq!(global::hour_height(), 120);
+	assert_eq!(global::day_height(), 2_880);
+	assert_eq!(global::week_height(), 20_160);
+	assert_eq!(global::difficulty_adjust_window(), 120);
+	assert_eq!(global::cut_through_horizon(), 20_160);
+	assert_eq!(global::state_sync_threshold(), 5_760);
+
+	// Emission follows the chain spec reward groups.
+	assert_eq!(calc_mwc_block_reward(1), 1_000);
+	assert_eq!(calc_mwc_block_reward(100), 1_000);
+	assert_eq!(calc_mwc_block_reward(101), 400);
+	assert_eq!(calc_mwc_block_reward(201), 200);
+
+	// Consensus rules activate at the chain spec heights.
+	assert_eq!(
+		global::consensus_rules(),
+		vec![(ConsensusRule::NRDKernels, 50)]
+	);
+	assert!(!global::is_active(ConsensusRule::NRDKernels, 49));
+	assert!(global::is_active(ConsensusRule::NRDKernels, 50));
+	assert!(!global::is_active(ConsensusRule::HeaderVersion2, 1_000));
+
+	// Difficulty is stable when blocks come at the chain spec block time.
+	let diff_data: Vec<HeaderInfo> = (0..100)
+		.rev()
+		.map(|i| HeaderInfo::from_ts_diff(i * 30, Difficulty::from_num(1_000)))
+		.collect();
+	assert_eq!(
+		next_difficulty(101, diff_data).difficulty,
+		Difficulty::from_num(1_000)
+	);
+
+	// A genesis block is mined deterministically from the chain spec timestamp.
+	let gen = pow::mine_genesis(genesis::genesis_custom().unwrap()).unwrap();
+	assert_eq!(gen.header.timestamp.timestamp(), 1_600_000_000);
+	let gen2 = pow::mine_genesis(genesis::genesis_custom().unwrap()).unwrap();
+	assert_eq!(gen.hash(), gen2.hash());
+
+	// Or provided serialized by the chain spec.
+	let bytes = ser::ser_vec(&gen, ser::ProtocolVersion::local()).unwrap();
+	global::set_chain_spec(ChainSpec {
+		genesis: Some(util::to_hex(bytes)),
+		..custom_spec()
+	});
+	assert_eq!(genesis::genesis_custom().unwrap().hash(), gen.hash());
+
+	global::set_chain_spec(ChainSpec {
+		genesis: Some("0badbeef".to_owned()),
+		..custom_spec()
+	});
+	assert!(genesis::genesis_custom().is_err());
+}
@@ -623,6 +623,7 @@ fn hard_forks() {
 	{	
 		global::set_mining_mode(global::ChainTypes::Mainnet);
 		assert_eq!(global::is_floonet(), false);
+		assert!(global::consensus_rules().is_empty());
 		assert!(valid_header_version(0, HeaderVersion(1)));
 		assert!(valid_header_version(YEAR_HEIGHT, HeaderVersion(1)));
 		assert!(valid_header_version(YEAR_HEIGHT * 10, HeaderVersion(1)));
@@ -632,6 +633,7 @@ fn hard_forks() {
 	{
 		global::set_mining_mode(global::ChainTypes::Floonet);
 		assert_eq!(global::is_floonet(), true);
+		assert!(global::consensus_rules().is_empty());
 		assert!(valid_header_version(0, HeaderVersion(1)));
 		assert!(valid_header_version(YEAR_HEIGHT, HeaderVersion(1)));
 		assert!(valid_header_version(YEAR_HEIGHT * 10, HeaderVersion(1)));
@@ -16,16 +16,18 @@
 
 pub mod common;
 
+use self::core::consensus;
 use self::core::core::block::BlockHeader;
 use self::core::core::block::Error::KernelLockHeight;
 use self::core::core::hash::{Hashed, ZERO_HASH};
 use self::core::core::verifier_cache::{LruVerifierCache, VerifierCache};
 use self::core::core::{
-	aggregate, deaggregate, KernelFeatures, Output, Transaction, TxKernel, Weighting,
+	aggregate, deaggregate, KernelFeatures, NRDRelativeHeight, Output, Transaction, TxKernel,
+	Weighting,
 };
 use self::core::libtx::build::{self, initial_tx, input, output, with_excess};
 use self::core::libtx::ProofBuilder;
-use self::core::ser;
+use self::core::ser::{self, ProtocolVersion};
 use crate::common::{new_block, tx1i1o, tx1i2o, tx2i1o};
 use mimble_core as core;
 use keychain::{BlindingFactor, ExtKeychain, Keychain};
@@ -139,6 +141,65 @@ fn build_tx_kernel() {
 	assert_eq!(2, tx.fee());
 }
 
+#[test]
+fn build_nrd_tx_kernel() {
+	let keychain = ExtKeychain::from_random_seed(false).unwrap();
+	let builder = ProofBuilder::new(&keychain);
+	let key_id1 = ExtKeychain::derive_key_id(1, 1, 0, 0, 0);
+	let key_id2 = ExtKeychain::derive_key_id(1, 2, 0, 0, 0);
+	let key_id3 = ExtKeychain::derive_key_id(1, 3, 0, 0, 0);
+
+	let features = KernelFeatures::NoRecentDuplicate {
+		fee: 2,
+		relative_height: NRDRelativeHeight::new(100).unwrap(),
+	};
+	let tx = build::transaction(
+		features,
+		vec![input(10, key_id1), output(5, key_id2), output(3, key_id3)],
+		&keychain,
+		&builder,
+	)
+	.unwrap();
+
+	tx.validate(Weighting::AsTransaction, verifier_cache())
+		.unwrap();
+
+	assert_eq!(tx.kernels().len(), 1);
+	let kern = &tx.kernels()[0];
+	kern.verify().unwrap();
+	assert!(kern.is_nrd());
+	assert_eq!(kern.features, features);
+	assert_eq!(2, tx.fee());
+
+	// The relative height is part of the signature message.
+	let mut kern2 = kern.clone();
+	kern2.features = KernelFeatures::NoRecentDuplicate {
+		fee: 2,
+		relative_height: NRDRelativeHeight::new(101).unwrap(),
+	};
+	assert!(kern2.verify().is_err());
+
+	// NRD kernels are not scheduled on mainnet (the chain type of these tests),
+	// the kernel cannot be read in either v1 or v2 serialization.
+	// See consensus_automated for the roundtrip on a chain scheduling them.
+	for version in vec![ProtocolVersion(1), ProtocolVersion(2)] {
+		let mut vec = vec![];
+		ser::serialize(&mut vec, version, kern).expect("serialization failed");
+		let res: Result<TxKernel, _> = ser::deserialize(&mut &vec[..], version);
+		assert_eq!(
+			res.err(),
+			Some(ser::Error::CorruptedData(
+				"Consensus rule nrd_kernels not scheduled".to_string()
+			))
+		);
+	}
+
+	// Relative height must be within 1..=WEEK_HEIGHT.
+	assert!(NRDRelativeHeight::new(0).is_err());
+	assert!(NRDRelativeHeight::new(consensus::WEEK_HEIGHT).is_ok());
+	assert!(NRDRelativeHeight::new(consensus::WEEK_HEIGHT + 1).is_err());
+}
+
 // Combine two transactions into one big transaction (with multiple kernels)
 // and check it still validates.
 #[test]
@@ -0,0 +1,70 @@
+// Copyright 2020 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Mock time tests. The mock time is process wide so it gets its own test file.
+
+mod common;
+use crate::common::new_block;
+use crate::core::core::{BlockHeader, UntrustedBlockHeader};
+use crate::core::global::{self, ChainTypes};
+use crate::core::libtx::ProofBuilder;
+use crate::core::ser;
+use chrono::prelude::{TimeZone, Utc};
+use chrono::Duration;
+use keychain::{ExtKeychain, Keychain};
+use mimble_core as core;
+
+fn is_refused_as_future(header: &BlockHeader) -> bool {
+	let mut vec = Vec::new();
+	ser::serialize_default(&mut vec, header).expect("serialization failed");
+	match ser::deserialize_default::<UntrustedBlockHeader>(&mut &vec[..]) {
+		Err(ser::Error::CorruptedData(msg)) => msg.contains("in future"),
+		_ => false,
+	}
+}
+
+#[test]
+fn mock_time() {
+	global::set_mining_mode(ChainTypes::AutomatedTesting);
+	let mock = Utc.timestamp(1_600_000_000, 0);
+	global::set_mock_time(Some(mock));
+	assert_eq!(global::get_mock_time(), Some(mock));
+	assert_eq!(global::now(), mock);
+
+	// New blocks are timestamped with the mock time.
+	let keychain = ExtKeychain::from_random_seed(false).unwrap();
+	let builder = ProofBuilder::new(&keychain);
+	let key_id = ExtKeychain::derive_key_id(1, 1, 0, 0, 0);
+	let b = new_block(
+		vec![],
+		&keychain,
+		&builder,
+		&BlockHeader::default(),
+		&key_id,
+	);
+	assert_eq!(b.header.timestamp, mock);
+
+	// The future time limit is relative to the mock time.
+	let mut header = b.header.clone();
+	header.timestamp = mock + Duration::days(1);
+	assert!(is_refused_as_future(&header));
+	header.timestamp = mock + Duration::seconds(global::block_time_sec() as i64);
+	assert!(!is_refused_as_future(&header));
+
+	// Back to the system clock.
+	global::set_mock_time(None);
+	assert!(global::now() > mock + Duration::days(1));
+	header.timestamp = mock + Duration::days(1);
+	assert!(!is_refused_as_future(&header));
+}
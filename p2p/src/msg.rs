@@ -17,11 +17,11 @@
 use crate::conn::Tracker;
 use crate::core::core::hash::Hash;
 use crate::core::core::BlockHeader;
+use crate::core::global;
 use crate::core::pow::Difficulty;
 use crate::core::ser::{
 	self, ProtocolVersion, Readable, Reader, StreamingReader, Writeable, Writer,
 };
-use crate::core::{consensus, global};
 use crate::types::{
 	Capabilities, Error, PeerAddr, ReasonForBan, MAX_BLOCK_HEADERS, MAX_LOCATORS, MAX_PEER_ADDRS,
 };
@@ -72,14 +72,9 @@ enum_from_primitive! {
 	}
 }
 
-/// Max theoretical size of a block filled with outputs.
-fn max_block_size() -> u64 {
-	(global::max_block_weight() / consensus::BLOCK_OUTPUT_WEIGHT * 708) as u64
-}
-
 // Max msg size when msg type is unknown.
 fn default_max_msg_size() -> u64 {
-	max_block_size()
+	global::max_block_size()
 }
 
 // Max msg size for each msg type.
@@ -96,11 +91,11 @@ fn max_msg_size(msg_type: Type) -> u64 {
 		Type::Header => 365,
 		Type::Headers => 2 + 365 * MAX_BLOCK_HEADERS as u64,
 		Type::GetBlock => 32,
-		Type::Block => max_block_size(),
+		Type::Block => global::max_block_size(),
 		Type::GetCompactBlock => 32,
-		Type::CompactBlock => max_block_size() / 10,
-		Type::StemTransaction => max_block_size(),
-		Type::Transaction => max_block_size(),
+		Type::CompactBlock => global::max_block_size() / 10,
+		Type::StemTransaction => global::max_block_size(),
+		Type::Transaction => global::max_block_size(),
 		Type::TxHashSetRequest => 40,
 		Type::TxHashSetArchive => 64,
 		Type::BanReason => 64,
@@ -116,6 +111,7 @@ fn magic() -> [u8; 2] {
 	match *global::CHAIN_TYPE.read() {
 		global::ChainTypes::Floonet => FLOONET_MAGIC,
 		global::ChainTypes::Mainnet => MAINNET_MAGIC,
+		global::ChainTypes::Custom => global::CHAIN_SPEC.read().magic,
 		_ => OTHER_MAGIC,
 	}
 }
@@ -81,7 +81,7 @@ impl Peers {
 			flags: State::Healthy,
 			last_banned: 0,
 			ban_reason: ReasonForBan::None,
-			last_connected: Utc::now().timestamp(),
+			last_connected: global::now().timestamp(),
 		};
 		debug!("Saving newly connected peer {}.", peer_data.addr);
 		self.save_peer(&peer_data)?;
@@ -98,9 +98,9 @@ impl Peers {
 			capabilities: Capabilities::UNKNOWN,
 			user_agent: "".to_string(),
 			flags: State::Banned,
-			last_banned: Utc::now().timestamp(),
+			last_banned: global::now().timestamp(),
 			ban_reason,
-			last_connected: Utc::now().timestamp(),
+			last_connected: global::now().timestamp(),
 		};
 		debug!("Banning peer {}, ban_reason={:?}", addr, ban_reason);
 		self.save_peer(&peer_data)
@@ -542,7 +542,7 @@ impl Peers {
 
 	/// Removes those peers that seem to have expired
 	pub fn remove_expired(&self) {
-		let now = Utc::now();
+		let now = global::now();
 
 		// Delete defunct peers from storage
 		let _ = self.store.delete_peers(|peer| {
@@ -776,7 +776,7 @@ impl NetAdapter for Peers {
 				flags: State::Healthy,
 				last_banned: 0,
 				ban_reason: ReasonForBan::None,
-				last_connected: Utc::now().timestamp(),
+				last_connected: global::now().timestamp(),
 			};
 			if let Err(e) = self.save_peer(&peer) {
 				error!("Could not save received peer address: {:?}", e);
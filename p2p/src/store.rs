@@ -14,11 +14,11 @@
 
 //! Storage implementation for peer data.
 
-use chrono::Utc;
 use num::FromPrimitive;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 
+use crate::core::global;
 use crate::core::ser::{self, Readable, Reader, Writeable, Writer};
 use crate::types::{Capabilities, PeerAddr, ReasonForBan};
 use mimble_store::{self, option_to_not_found, to_key, Error};
@@ -91,7 +91,7 @@ impl Readable for PeerData {
 		// this only works because each PeerData is read in its own vector and this
 		// is the last data element
 		let last_connected = match lc {
-			Err(_) => Utc::now().timestamp(),
+			Err(_) => global::now().timestamp(),
 			Ok(lc) => lc,
 		};
 
@@ -195,7 +195,7 @@ impl PeerStore {
 		)?;
 		peer.flags = new_state;
 		if new_state == State::Banned {
-			peer.last_banned = Utc::now().timestamp();
+			peer.last_banned = global::now().timestamp();
 		}
 
 		batch.put_ser(&peer_key(peer_addr)[..], &peer)?;
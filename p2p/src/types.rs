@@ -289,9 +289,14 @@ impl std::fmt::Display for PeerAddr {
 
 impl PeerAddr {
 	/// Convenient way of constructing a new peer_addr from an ip_addr
-	/// defaults to port 3414 on mainnet and 13414 on floonet.
+	/// defaults to port 3414 on mainnet, 13414 on floonet and the chain spec
+	/// p2p port on a custom network.
 	pub fn from_ip(addr: IpAddr) -> PeerAddr {
-		let port = if global::is_floonet() { 13414 } else { 3414 };
+		let port = match global::get_chain_type() {
+			global::ChainTypes::Floonet => 13414,
+			global::ChainTypes::Custom => global::CHAIN_SPEC.read().p2p_port,
+			_ => 3414,
+		};
 		PeerAddr::Ip(SocketAddr::new(addr, port))
 	}
 
@@ -534,9 +539,9 @@ impl PeerLiveInfo {
 		PeerLiveInfo {
 			total_difficulty: difficulty,
 			height: 0,
-			first_seen: Utc::now(),
-			last_seen: Utc::now(),
-			stuck_detector: Utc::now(),
+			first_seen: global::now(),
+			last_seen: global::now(),
+			stuck_detector: global::now(),
 		}
 	}
 }
@@ -575,11 +580,11 @@ impl PeerInfo {
 	pub fn update(&self, height: u64, total_difficulty: Difficulty) {
 		let mut live_info = self.live_info.write();
 		if total_difficulty != live_info.total_difficulty {
-			live_info.stuck_detector = Utc::now();
+			live_info.stuck_detector = global::now();
 		}
 		live_info.height = height;
 		live_info.total_difficulty = total_difficulty;
-		live_info.last_seen = Utc::now()
+		live_info.last_seen = global::now()
 	}
 }
 
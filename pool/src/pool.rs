@@ -168,12 +168,30 @@ impl Pool {
 		entry: PoolEntry,
 		extra_txs: Vec<Transaction>,
 		header: &BlockHeader,
+	) -> Result<(), PoolError> {
+		self.validate_tx(&entry.tx, extra_txs, header)?;
+
+		// If we get here successfully then we can safely add the entry to the pool.
+		self.log_pool_add(&entry, header);
+		self.entries.push(entry);
+
+		Ok(())
+	}
+
+	/// Validate the tx aggregated with all existing txs in the pool (and any
+	/// extra txs provided) against the chain state at the provided header.
+	/// Does not modify the pool.
+	pub fn validate_tx(
+		&self,
+		tx: &Transaction,
+		extra_txs: Vec<Transaction>,
+		header: &BlockHeader,
 	) -> Result<(), PoolError> {
 		// Combine all the txs from the pool with any extra txs provided.
 		let mut txs = self.all_transactions();
 
 		// Quick check to see if we have seen this tx before.
-		if txs.contains(&entry.tx) {
+		if txs.contains(tx) {
 			return Err(PoolError::DuplicateTx);
 		}
 
@@ -181,11 +199,11 @@ impl Pool {
 
 		let agg_tx = if txs.is_empty() {
 			// If we have nothing to aggregate then simply return the tx itself.
-			entry.tx.clone()
+			tx.clone()
 		} else {
 			// Create a single aggregated tx from the existing pool txs and the
-			// new entry
-			txs.push(entry.tx.clone());
+			// new tx
+			txs.push(tx.clone());
 			transaction::aggregate(txs)?
 		};
 
@@ -193,10 +211,22 @@ impl Pool {
 		// Validate against known chain state at the provided header.
 		self.validate_raw_tx(&agg_tx, header, Weighting::NoLimit)?;
 
-		// If we get here successfully then we can safely add the entry to the pool.
-		self.log_pool_add(&entry, header);
-		self.entries.push(entry);
+		Ok(())
+	}
 
+	/// Report the first kernel of the tx whose excess is already in the pool.
+	/// Such a tx fails the aggregated validation anyway, this gives a more
+	/// helpful error to a dry-run validation.
+	pub fn verify_no_duplicate_kernels(&self, tx: &Transaction) -> Result<(), PoolError> {
+		let txs = self.all_transactions();
+		for kernel in tx.kernels() {
+			if txs
+				.iter()
+				.any(|x| x.kernels().iter().any(|k| k.excess == kernel.excess))
+			{
+				return Err(PoolError::DuplicateKernel(kernel.excess));
+			}
+		}
 		Ok(())
 	}
 
@@ -224,6 +254,9 @@ impl Pool {
 		// based on weight verification type.
 		tx.validate(weighting, self.verifier_cache.clone())?;
 
+		// Check NRD kernels sharing an excess are not aggregated together.
+		self.verify_nrd_kernels(tx)?;
+
 		// Validate the tx against current chain state.
 		// Check all inputs are in the current UTXO set.
 		// Check all outputs are unique in current UTXO set.
@@ -233,6 +266,19 @@ impl Pool {
 		Ok(new_sums)
 	}
 
+	/// NRD kernels with the same excess cannot both be included in the next
+	/// block as the relative height is at least 1. The aggregate would fail
+	/// the NRD checks on the chain, report it with a more helpful error here.
+	fn verify_nrd_kernels(&self, tx: &Transaction) -> Result<(), PoolError> {
+		let mut excesses = HashSet::new();
+		for kernel in tx.kernels().iter().filter(|x| x.is_nrd()) {
+			if !excesses.insert(kernel.excess) {
+				return Err(PoolError::NRDKernelRelativeHeight);
+			}
+		}
+		Ok(())
+	}
+
 	pub fn validate_raw_txs(
 		&self,
 		txs: &[Transaction],
@@ -283,11 +329,13 @@ impl Pool {
 		})
 	}
 
+	/// Revalidate every entry against the given header, returning the entries
+	/// that are no longer valid and were dropped from the pool.
 	pub fn reconcile(
 		&mut self,
 		extra_tx: Option<Transaction>,
 		header: &BlockHeader,
-	) -> Result<(), PoolError> {
+	) -> Result<Vec<PoolEntry>, PoolError> {
 		let existing_entries = self.entries.clone();
 		self.entries.clear();
 
@@ -296,11 +344,17 @@ impl Pool {
 			extra_txs.push(extra_tx);
 		}
 
+		let mut removed = vec![];
 		for x in existing_entries {
-			let _ = self.add_to_pool(x, extra_txs.clone(), header);
+			if self
+				.add_to_pool(x.clone(), extra_txs.clone(), header)
+				.is_err()
+			{
+				removed.push(x);
+			}
 		}
 
-		Ok(())
+		Ok(removed)
 	}
 
 	/// Buckets consist of a vec of txs and track the aggregate fee_to_weight.
@@ -425,16 +479,18 @@ impl Pool {
 	}
 
 	/// Quick reconciliation step - we can evict any txs in the pool where
-	/// inputs or kernels intersect with the block.
-	pub fn reconcile_block(&mut self, block: &Block) {
+	/// inputs or kernels intersect with the block. Returns the evicted entries.
+	pub fn reconcile_block(&mut self, block: &Block) -> Vec<PoolEntry> {
 		// Filter txs in the pool based on the latest block.
 		// Reject any txs where we see a matching tx kernel in the block.
 		// Also reject any txs where we see a conflicting tx,
 		// where an input is spent in a different tx.
-		self.entries.retain(|x| {
+		let (kept, removed) = self.entries.drain(..).partition(|x| {
 			!x.tx.kernels().iter().any(|y| block.kernels().contains(y))
 				&& !x.tx.inputs().iter().any(|y| block.inputs().contains(y))
 		});
+		self.entries = kept;
+		removed
 	}
 
 	/// Size of the pool.
@@ -21,13 +21,14 @@ use self::core::core::hash::{Hash, Hashed};
 use self::core::core::id::ShortId;
 use self::core::core::verifier_cache::VerifierCache;
 use self::core::core::{transaction, Block, BlockHeader, Transaction, Weighting};
+use self::core::global;
 use self::util::RwLock;
 use crate::pool::Pool;
 use crate::types::{BlockChain, PoolAdapter, PoolConfig, PoolEntry, PoolError, TxSource};
 use chrono::prelude::*;
 use mimble_core as core;
 use mimble_util as util;
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::sync::Arc;
 
 /// Transaction pool implementation.
@@ -45,6 +46,8 @@ pub struct TransactionPool {
 	pub verifier_cache: Arc<RwLock<dyn VerifierCache>>,
 	/// The pool adapter
 	pub adapter: Arc<dyn PoolAdapter>,
+	/// Txs already reported as evicted while still held in the reorg cache.
+	evicted: HashSet<Hash>,
 }
 
 impl TransactionPool {
@@ -67,6 +70,7 @@ impl TransactionPool {
 			blockchain: chain,
 			verifier_cache,
 			adapter,
+			evicted: HashSet::new(),
 		}
 	}
 
@@ -82,14 +86,38 @@ impl TransactionPool {
 	}
 
 	fn add_to_reorg_cache(&mut self, entry: PoolEntry) {
-		let mut cache = self.reorg_cache.write();
-		cache.push_back(entry);
+		let dropped = {
+			let mut cache = self.reorg_cache.write();
+			cache.push_back(entry);
+
+			// We cache 30 mins of txs but we have a hard limit to avoid catastrophic failure.
+			// For simplicity use the same value as the actual tx pool limit.
+			if cache.len() > self.config.max_pool_size {
+				cache.pop_front()
+			} else {
+				None
+			}
+		};
+		if let Some(entry) = dropped {
+			self.dropped_from_reorg_cache(&entry);
+		}
+	}
+
+	// Deaggregate the tx based on current txpool txs.
+	// Returns None if none of the txpool txs are part of this tx.
+	fn deaggregate_tx(&self, tx: &Transaction) -> Result<Option<Transaction>, PoolError> {
+		if tx.kernels().len() > 1 {
+			let txs = self.txpool.find_matching_transactions(tx.kernels());
+			if !txs.is_empty() {
+				let tx = transaction::deaggregate(tx.clone(), txs)?;
 
-		// We cache 30 mins of txs but we have a hard limit to avoid catastrophic failure.
-		// For simplicity use the same value as the actual tx pool limit.
-		if cache.len() > self.config.max_pool_size {
-			let _ = cache.pop_front();
+				// Validate this deaggregated tx "as tx", subject to regular tx weight limits.
+				tx.validate(Weighting::AsTransaction, self.verifier_cache.clone())?;
+
+				return Ok(Some(tx));
+			}
 		}
+		Ok(None)
 	}
 
 	fn add_to_txpool(
@@ -98,17 +126,9 @@ impl TransactionPool {
 		header: &BlockHeader,
 	) -> Result<(), PoolError> {
 		// First deaggregate the tx based on current txpool txs.
-		if entry.tx.kernels().len() > 1 {
-			let txs = self.txpool.find_matching_transactions(entry.tx.kernels());
-			if !txs.is_empty() {
-				let tx = transaction::deaggregate(entry.tx, txs)?;
-
-				// Validate this deaggregated tx "as tx", subject to regular tx weight limits.
-				tx.validate(Weighting::AsTransaction, self.verifier_cache.clone())?;
-
-				entry.tx = tx;
-				entry.src = TxSource::Deaggregate;
-			}
+		if let Some(tx) = self.deaggregate_tx(&entry.tx)? {
+			entry.tx = tx;
+			entry.src = TxSource::Deaggregate;
 		}
 		self.txpool.add_to_pool(entry.clone(), vec![], header)?;
 
@@ -150,6 +170,9 @@ impl TransactionPool {
 		tx.validate(Weighting::AsTransaction, self.verifier_cache.clone())
 			.map_err(PoolError::InvalidTx)?;
 
+		// Check NRD kernels are valid for inclusion in the next block.
+		self.verify_kernel_variants(&tx, header)?;
+
 		// Check the tx lock_time is valid based on current chain state.
 		self.blockchain.verify_tx_lock_height(&tx)?;
 
@@ -158,7 +181,7 @@ impl TransactionPool {
 
 		let entry = PoolEntry {
 			src,
-			tx_at: Utc::now(),
+			tx_at: global::now(),
 			tx,
 		};
 
@@ -184,6 +207,47 @@ impl TransactionPool {
 		Ok(())
 	}
 
+	/// Run the same checks as add_to_pool (capacity, fees, tx validation,
+	/// lock height, coinbase maturity and validation against the txpool and
+	/// current chain state) without adding the tx to the pool or relaying it.
+	pub fn validate_tx(&self, tx: &Transaction, header: &BlockHeader) -> Result<(), PoolError> {
+		if self.txpool.contains_tx(tx.hash()) {
+			return Err(PoolError::DuplicateTx);
+		}
+
+		// Being over capacity is fine, add_to_pool evicts to make space.
+		match self.is_acceptable(tx, false) {
+			Ok(_) | Err(PoolError::OverCapacity) => {}
+			Err(e) => return Err(e),
+		}
+
+		tx.validate(Weighting::AsTransaction, self.verifier_cache.clone())
+			.map_err(PoolError::InvalidTx)?;
+
+		self.verify_kernel_variants(tx, header)?;
+		self.blockchain.verify_tx_lock_height(tx)?;
+		self.blockchain.verify_coinbase_maturity(tx)?;
+
+		let tx = match self.deaggregate_tx(tx)? {
+			Some(tx) => tx,
+			None => tx.clone(),
+		};
+		self.txpool.verify_no_duplicate_kernels(&tx)?;
+		self.txpool.validate_tx(&tx, vec![], header)
+	}
+
+	// NRD kernels are only valid once the NRD activation height is reached.
+	// Checked against the next block height as this is where the tx would be included.
+	fn verify_kernel_variants(
+		&self,
+		tx: &Transaction,
+		header: &BlockHeader,
+	) -> Result<(), PoolError> {
+		tx.body
+			.verify_kernel_features(header.height + 1)
+			.map_err(|_| PoolError::NRDKernelNotEnabled)
+	}
+
 	// Remove the last transaction from the flattened bucket transactions.
 	// No other tx depends on it, it has low fee_to_weight and is unlikely to participate in any cut-through.
 	pub fn evict_from_txpool(&mut self) {
@@ -199,23 +263,56 @@ impl TransactionPool {
 		if let Some(evictable_transaction) = bucket_transactions.last() {
 			// Remove transaction
 			debug!("evict_from_txpool self.txpool.entries starting len = {},  evicting transaction {:?}", self.txpool.entries.len(), evictable_transaction );
+			let evicted: Vec<_> = self
+				.txpool
+				.entries
+				.iter()
+				.filter(|x| x.tx == *evictable_transaction)
+				.cloned()
+				.collect();
 			self.txpool
 				.entries
 				.retain(|x| x.tx != *evictable_transaction);
+			for entry in &evicted {
+				self.report_evicted(entry);
+			}
 		};
 	}
 
+	// Report the tx as evicted, remembering it so it is not reported again
+	// when it expires from the reorg cache.
+	fn report_evicted(&mut self, entry: &PoolEntry) {
+		self.evicted.insert(entry.tx.hash());
+		self.adapter.tx_evicted(entry);
+	}
+
 	// Old txs will "age out" after 30 mins.
+	// Expired txs no longer in the txpool can not come back on a reorg.
+	// Those not reported yet (txs mined in a block) are reported as evicted now.
 	pub fn truncate_reorg_cache(&mut self, cutoff: DateTime<Utc>) {
-		let mut cache = self.reorg_cache.write();
-
-		while cache.front().map(|x| x.tx_at < cutoff).unwrap_or(false) {
-			let _tx = cache.pop_front();
-			debug!(
-				"truncate_reorg_cache: for {:?},  new size: {}",
-				_tx,
-				cache.len()
-			);
+		let mut expired = vec![];
+		{
+			let mut cache = self.reorg_cache.write();
+			while cache.front().map(|x| x.tx_at < cutoff).unwrap_or(false) {
+				if let Some(entry) = cache.pop_front() {
+					debug!(
+						"truncate_reorg_cache: for {:?},  new size: {}",
+						entry,
+						cache.len()
+					);
+					expired.push(entry);
+				}
+			}
+		}
+		for entry in &expired {
+			self.dropped_from_reorg_cache(entry);
+		}
+	}
+
+	fn dropped_from_reorg_cache(&mut self, entry: &PoolEntry) {
+		let reported = self.evicted.remove(&entry.tx.hash());
+		if !reported && !self.txpool.entries.iter().any(|x| x.tx == entry.tx) {
+			self.adapter.tx_evicted(entry);
 		}
 	}
 
@@ -227,7 +324,9 @@ impl TransactionPool {
 			header.hash(),
 		);
 		for entry in entries {
-			let _ = &self.add_to_txpool(entry.clone(), header);
+			if self.add_to_txpool(entry.clone(), header).is_ok() {
+				self.evicted.remove(&entry.tx.hash());
+			}
 		}
 		debug!(
 			"reconcile_reorg_cache: block: {:?} ... done.",
@@ -257,9 +356,18 @@ impl TransactionPool {
 			debug!("---------------- BEFORE END --------------");
 		}
 
-		// First reconcile the txpool.
-		self.txpool.reconcile_block(block);
-		self.txpool.reconcile(None, &block.header)?;
+		// First reconcile the txpool, reporting the txs it drops. Txs mined in the
+		// block are not evicted, they are reported once expired from the reorg cache.
+		let mut evicted: Vec<_> = self
+			.txpool
+			.reconcile_block(block)
+			.into_iter()
+			.filter(|x| !x.tx.kernels().iter().all(|k| block.kernels().contains(k)))
+			.collect();
+		evicted.extend(self.txpool.reconcile(None, &block.header)?);
+		for entry in &evicted {
+			self.report_evicted(entry);
+		}
 
 		// Now reconcile our stempool, accounting for the updated txpool txs.
 		self.stempool.reconcile_block(block);
@@ -26,6 +26,7 @@ use self::core::{consensus, global};
 use failure::Fail;
 use mimble_core as core;
 use mimble_keychain as keychain;
+use mimble_util::secp::pedersen::Commitment;
 
 /// Dandelion "epoch" length.
 const DANDELION_EPOCH_SECS: u16 = 600;
@@ -223,12 +224,24 @@ pub enum PoolError {
 	/// Transaction fee is too low given its weight
 	#[fail(display = "Tx Pool Low fee transaction {}", _0)]
 	LowFeeTransaction(u64),
-	/// Attempt to add a duplicate output to the pool.
-	#[fail(display = "Tx Pool Duplicate commitment")]
-	DuplicateCommitment,
+	/// Attempt to add an output that already exists in the UTXO set.
+	#[fail(display = "Tx Pool Duplicate commitment {:?}", _0)]
+	DuplicateCommitment(Commitment),
+	/// Attempt to spend an output that is not in the UTXO set (missing or already spent).
+	#[fail(display = "Tx Pool Input not found {:?}", _0)]
+	InputNotFound(Commitment),
+	/// Dry-run validation of a tx with a kernel already present in the pool.
+	#[fail(display = "Tx Pool Duplicate kernel {:?}", _0)]
+	DuplicateKernel(Commitment),
 	/// Attempt to add a duplicate tx to the pool.
 	#[fail(display = "Tx Pool Duplicate tx")]
 	DuplicateTx,
+	/// NRD kernels are not valid until the NRD activation height.
+	#[fail(display = "Tx Pool NRD kernel not enabled")]
+	NRDKernelNotEnabled,
+	/// NRD kernel seen on chain within its relative lock height.
+	#[fail(display = "Tx Pool NRD kernel relative height")]
+	NRDKernelRelativeHeight,
 	/// Other kinds of error (not yet pulled out into meaningful errors).
 	#[fail(display = "Tx Pool General error {}", _0)]
 	Other(String),
@@ -285,6 +298,11 @@ pub trait PoolAdapter: Send + Sync {
 
 	/// The stem transaction pool has accepted this transactions as valid.
 	fn stem_tx_accepted(&self, entry: &PoolEntry) -> Result<(), PoolError>;
+
+	/// The transaction pool has dropped this transaction, either to make room,
+	/// because a block included or conflicted with it, or because it expired
+	/// from the reorg cache.
+	fn tx_evicted(&self, entry: &PoolEntry);
 }
 
 /// Dummy adapter used as a placeholder for real implementations
@@ -296,4 +314,5 @@ impl PoolAdapter for NoopAdapter {
 	fn stem_tx_accepted(&self, _entry: &PoolEntry) -> Result<(), PoolError> {
 		Ok(())
 	}
+	fn tx_evicted(&self, _entry: &PoolEntry) {}
 }
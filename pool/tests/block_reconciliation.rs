@@ -23,6 +23,7 @@ use self::keychain::{ExtKeychain, Keychain};
 use self::util::RwLock;
 use crate::common::ChainAdapter;
 use crate::common::*;
+use chrono::{Duration, Utc};
 use mimble_core as core;
 use mimble_keychain as keychain;
 use mimble_util as util;
@@ -47,7 +48,12 @@ fn test_transaction_pool_block_reconciliation() {
 		let verifier_cache = Arc::new(RwLock::new(LruVerifierCache::new()));
 
 		// Initialize a new pool with our chain adapter.
-		let pool = RwLock::new(test_setup(chain.clone(), verifier_cache.clone()));
+		let pool_adapter = Arc::new(EvictionAdapter::default());
+		let pool = RwLock::new(test_setup_with_adapter(
+			chain.clone(),
+			verifier_cache.clone(),
+			pool_adapter.clone(),
+		));
 
 		let header = {
 			let height = 1;
@@ -217,6 +223,27 @@ fn test_transaction_pool_block_reconciliation() {
 			assert_eq!(write_pool.txpool.entries[2].tx, conflict_valid_child);
 			assert_eq!(write_pool.txpool.entries[3].tx, valid_child_valid);
 		}
+
+		// The txs conflicting with the block were reported as evicted,
+		// the txs mined in the block were not.
+		{
+			let evicted = pool_adapter.evicted.read();
+			assert_eq!(evicted.len(), 4);
+			assert!(evicted.contains(&valid_child_conflict));
+			assert!(!evicted.contains(&txs_to_add[0]));
+			assert!(!evicted.contains(&valid_transaction));
+		}
+
+		// Once expired from the reorg cache the mined txs are reported too,
+		// every tx exactly once.
+		{
+			let mut write_pool = pool.write();
+			write_pool.truncate_reorg_cache(Utc::now() + Duration::minutes(1));
+			let evicted = pool_adapter.evicted.read();
+			assert_eq!(evicted.len(), txs_to_add.len() - 4);
+			assert!(evicted.contains(&txs_to_add[0]));
+			assert!(!evicted.contains(&valid_transaction));
+		}
 	}
 	// Cleanup db directory
 	clean_output_dir(db_root.clone());
@@ -123,13 +123,13 @@ impl BlockChain for ChainAdapter {
 
 		for x in tx.outputs() {
 			if utxo.contains(&x.commitment()) {
-				return Err(PoolError::Other(format!("output commitment not unique")));
+				return Err(PoolError::DuplicateCommitment(x.commitment()));
 			}
 		}
 
 		for x in tx.inputs() {
 			if !utxo.contains(&x.commitment()) {
-				return Err(PoolError::Other(format!("not in utxo set")));
+				return Err(PoolError::InputNotFound(x.commitment()));
 			}
 		}
 
@@ -148,9 +148,33 @@ impl BlockChain for ChainAdapter {
 	}
 }
 
+/// Pool adapter keeping track of the transactions the pool evicted.
+#[derive(Default)]
+pub struct EvictionAdapter {
+	pub evicted: RwLock<Vec<Transaction>>,
+}
+
+impl PoolAdapter for EvictionAdapter {
+	fn tx_accepted(&self, _entry: &PoolEntry) {}
+	fn stem_tx_accepted(&self, _entry: &PoolEntry) -> Result<(), PoolError> {
+		Ok(())
+	}
+	fn tx_evicted(&self, entry: &PoolEntry) {
+		self.evicted.write().push(entry.tx.clone());
+	}
+}
+
 pub fn test_setup(
 	chain: Arc<dyn BlockChain>,
 	verifier_cache: Arc<RwLock<dyn VerifierCache>>,
+) -> TransactionPool {
+	test_setup_with_adapter(chain, verifier_cache, Arc::new(NoopAdapter {}))
+}
+
+pub fn test_setup_with_adapter(
+	chain: Arc<dyn BlockChain>,
+	verifier_cache: Arc<RwLock<dyn VerifierCache>>,
+	adapter: Arc<dyn PoolAdapter>,
 ) -> TransactionPool {
 	TransactionPool::new(
 		PoolConfig {
@@ -162,7 +186,7 @@ pub fn test_setup(
 		},
 		chain.clone(),
 		verifier_cache.clone(),
-		Arc::new(NoopAdapter {}),
+		adapter,
 	)
 }
 
@@ -0,0 +1,195 @@
+// Copyright 2020 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod common;
+
+use self::core::core::verifier_cache::LruVerifierCache;
+use self::core::core::{
+	Block, BlockHeader, KernelFeatures, NRDRelativeHeight, Transaction, TxKernel,
+};
+use self::core::global::{self, ChainTypes, ConsensusRule};
+use self::core::libtx::{self, aggsig, build};
+use self::core::pow::Difficulty;
+use self::keychain::{BlindingFactor, ExtKeychain, Keychain, SwitchCommitmentType};
+use self::pool::PoolError;
+use self::util::RwLock;
+use crate::common::*;
+use mimble_core as core;
+use mimble_keychain as keychain;
+use mimble_pool as pool;
+use mimble_util as util;
+use std::sync::Arc;
+
+// Spend a single output using the provided (pre-built) NRD kernel with a fee of 1.
+fn test_nrd_transaction<K>(
+	keychain: &K,
+	kernel: &TxKernel,
+	excess: &BlindingFactor,
+	input_value: u64,
+) -> Transaction
+where
+	K: Keychain,
+{
+	let key_id_in = ExtKeychain::derive_key_id(1, input_value as u32, 0, 0, 0);
+	let key_id_out = ExtKeychain::derive_key_id(1, input_value as u32 - 1, 0, 0, 0);
+	build::transaction_with_kernel(
+		vec![
+			build::input(input_value, key_id_in),
+			build::output(input_value - 1, key_id_out),
+		],
+		kernel.clone(),
+		excess.clone(),
+		keychain,
+		&libtx::ProofBuilder::new(keychain),
+	)
+	.unwrap()
+}
+
+// Build an NRD kernel with the provided features, signed with the excess.
+fn test_nrd_kernel<K>(keychain: &K, features: KernelFeatures, excess: &BlindingFactor) -> TxKernel
+where
+	K: Keychain,
+{
+	let mut kernel = TxKernel::with_features(features);
+	let msg = kernel.msg_to_sign().unwrap();
+	let skey = excess.secret_key(keychain.secp()).unwrap();
+	kernel.excess = keychain.secp().commit(0, skey).unwrap();
+	let pubkey = kernel.excess.to_pubkey(keychain.secp()).unwrap();
+	kernel.excess_sig =
+		aggsig::sign_with_blinding(keychain.secp(), &msg, excess, Some(&pubkey)).unwrap();
+	kernel
+}
+
+/// Test NRD kernels are only accepted once enabled and cannot be
+/// duplicated across the stempool and txpool.
+#[test]
+fn test_nrd_kernels_pool() {
+	global::set_mining_mode(ChainTypes::AutomatedTesting);
+	let keychain: ExtKeychain = Keychain::from_random_seed(false).unwrap();
+
+	let db_root = ".mimble_nrd_kernels".to_string();
+	clean_output_dir(db_root.clone());
+
+	let chain = Arc::new(ChainAdapter::init(db_root.clone()).unwrap());
+	let verifier_cache = Arc::new(RwLock::new(LruVerifierCache::new()));
+	let pool = RwLock::new(test_setup(chain.clone(), verifier_cache.clone()));
+
+	let add_block = |prev: &BlockHeader| {
+		let height = prev.height + 1;
+		let key_id = ExtKeychain::derive_key_id(1, height as u32, 0, 0, 0);
+		let reward = libtx::reward::output(
+			&keychain,
+			&libtx::ProofBuilder::new(&keychain),
+			&key_id,
+			0,
+			false,
+			height,
+		)
+		.unwrap();
+		let block = Block::new(prev, vec![], Difficulty::min(), reward).unwrap();
+		chain.update_db_for_block(&block);
+		block.header
+	};
+
+	let header_1 = add_block(&BlockHeader::default());
+
+	let initial_tx = test_transaction_spending_coinbase(&keychain, &header_1, vec![500, 600]);
+	pool.write()
+		.add_to_pool(test_source(), initial_tx, false, &header_1)
+		.unwrap();
+
+	let key_id = ExtKeychain::derive_key_id(1, 1_000, 0, 0, 0);
+	let skey = keychain
+		.derive_key(0, &key_id, SwitchCommitmentType::None)
+		.unwrap();
+	let excess = BlindingFactor::from_secret_key(skey);
+	let kernel = test_nrd_kernel(
+		&keychain,
+		KernelFeatures::NoRecentDuplicate {
+			fee: 1,
+			relative_height: NRDRelativeHeight::new(1_440).unwrap(),
+		},
+		&excess,
+	);
+
+	// A different NRD kernel sharing the same excess.
+	let kernel_2 = test_nrd_kernel(
+		&keychain,
+		KernelFeatures::NoRecentDuplicate {
+			fee: 1,
+			relative_height: NRDRelativeHeight::new(1_441).unwrap(),
+		},
+		&excess,
+	);
+	assert_ne!(kernel, kernel_2);
+	assert_eq!(kernel.excess, kernel_2.excess);
+
+	let tx1 = test_nrd_transaction(&keychain, &kernel, &excess, 500);
+	let tx2 = test_nrd_transaction(&keychain, &kernel, &excess, 600);
+	let tx3 = test_nrd_transaction(&keychain, &kernel_2, &excess, 600);
+
+	// NRD kernels are rejected (both stem and fluff) prior to the activation height.
+	{
+		let mut write_pool = pool.write();
+		for stem in vec![true, false] {
+			assert_eq!(
+				write_pool.add_to_pool(test_source(), tx1.clone(), stem, &header_1),
+				Err(PoolError::NRDKernelNotEnabled)
+			);
+		}
+		assert_eq!(write_pool.total_size(), 1);
+		assert_eq!(write_pool.stempool.size(), 0);
+	}
+
+	// Accepted once the next block is at the activation height.
+	let activation_height = global::activation_height(ConsensusRule::NRDKernels).unwrap();
+	let mut header = header_1;
+	while header.height + 1 < activation_height {
+		header = add_block(&header);
+	}
+	{
+		let mut write_pool = pool.write();
+		write_pool
+			.add_to_pool(test_source(), tx1.clone(), false, &header)
+			.unwrap();
+		assert_eq!(write_pool.total_size(), 2);
+
+		// The same NRD kernel cannot be added to the stempool (or txpool) again.
+		assert!(write_pool
+			.add_to_pool(test_source(), tx2.clone(), true, &header)
+			.is_err());
+		assert_eq!(write_pool.total_size(), 2);
+
+		// Dry-run validation reports the duplicate kernel.
+		assert_eq!(
+			write_pool.validate_tx(&tx2, &header),
+			Err(PoolError::DuplicateKernel(kernel.excess))
+		);
+		assert_eq!(write_pool.stempool.size(), 0);
+
+		// A different NRD kernel sharing the excess cannot be aggregated with
+		// the txpool as both would be in the next block (stem falls back to fluff).
+		for stem in vec![true, false] {
+			assert_eq!(
+				write_pool.add_to_pool(test_source(), tx3.clone(), stem, &header),
+				Err(PoolError::NRDKernelRelativeHeight)
+			);
+		}
+		assert_eq!(write_pool.total_size(), 2);
+		assert_eq!(write_pool.stempool.size(), 0);
+	}
+
+	// Cleanup db directory
+	clean_output_dir(db_root.clone());
+}
@@ -19,7 +19,7 @@ use self::core::core::{transaction, Block, BlockHeader, Weighting};
 use self::core::libtx;
 use self::core::pow::Difficulty;
 use self::keychain::{ExtKeychain, Keychain};
-use self::pool::TxSource;
+use self::pool::{PoolError, TxSource};
 use self::util::RwLock;
 use crate::common::*;
 use env_logger;
@@ -163,6 +163,25 @@ fn test_the_transaction_pool() {
 			.is_err());
 	}
 
+	// Dry-run validation reports the same errors without touching the pool.
+	{
+		let read_pool = pool.read();
+		let bad_tx = test_transaction(&keychain, vec![10_001], vec![10_000]);
+		let missing = bad_tx.inputs()[0].commitment();
+		assert_eq!(
+			read_pool.validate_tx(&bad_tx, &header),
+			Err(PoolError::InputNotFound(missing))
+		);
+		assert_eq!(
+			read_pool.validate_tx(&tx1, &header),
+			Err(PoolError::DuplicateTx)
+		);
+
+		let tx = test_transaction(&keychain, vec![800], vec![799]);
+		assert_eq!(read_pool.validate_tx(&tx, &header), Ok(()));
+		assert_eq!(read_pool.total_size(), 3);
+	}
+
 	// Test adding a tx that would result in a duplicate output (conflicts with
 	// output from tx2). For reasons of security all outputs in the UTXO set must
 	// be unique. Otherwise spending one will almost certainly cause the other
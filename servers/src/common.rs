@@ -16,5 +16,6 @@
 
 pub mod adapters;
 pub mod hooks;
+pub mod metrics;
 pub mod stats;
 pub mod types;
@@ -28,6 +28,7 @@ use std::sync::Mutex;
 
 use crate::chain::{self, BlockStatus, ChainAdapter, Options, SyncState, SyncStatus};
 use crate::common::hooks::{ChainEvents, NetEvents};
+use crate::common::stats::ServerStateInfo;
 use crate::common::types::{ChainValidationMode, DandelionEpoch, ServerConfig};
 use crate::core::core::hash::{Hash, Hashed};
 use crate::core::core::transaction::Transaction;
@@ -95,6 +96,7 @@ pub struct NetToChainAdapter {
 	peers: OneTime<Weak<p2p::Peers>>,
 	config: ServerConfig,
 	hooks: Vec<Box<dyn NetEvents + Send + Sync>>,
+	state_info: ServerStateInfo,
 
 	// local in mem cache
 	processed_headers: EventCache,
@@ -254,6 +256,9 @@ impl p2p::ChainAdapter for NetToChainAdapter {
 				self.get_invalid_block_hashes(),
 			) {
 				debug!("Invalid compact block header {}: {:?}", cb_hash, e.kind());
+				if let chain::ErrorKind::ReorgTooDeep(_) = e.kind() {
+					self.refused_reorg_peer(peer_info);
+				}
 				return Ok(!e.is_bad_data());
 			}
 
@@ -351,6 +356,10 @@ impl p2p::ChainAdapter for NetToChainAdapter {
 				bh.hash(),
 				e.kind()
 			);
+			if let chain::ErrorKind::ReorgTooDeep(_) = e.kind() {
+				self.refused_reorg_peer(peer_info);
+				return Ok(true);
+			}
 			if e.is_bad_data() {
 				return Ok(false);
 			} else {
@@ -496,11 +505,14 @@ impl p2p::ChainAdapter for NetToChainAdapter {
 	) -> Result<bool, chain::Error> {
 		let mut hashmap = self.header_cache.lock().unwrap();
 		// try to add headers to our header chain
-		match self.chain().sync_block_headers(
+		let now = Instant::now();
+		let res = self.chain().sync_block_headers(
 			bhs,
 			chain::Options::SYNC,
 			self.get_invalid_block_hashes(),
-		) {
+		);
+		self.state_info.header_latency.observe(now.elapsed());
+		match res {
 			Ok(_) => {
 				for bh in bhs {
 					let mut tip_processed = self.tip_processed.lock().unwrap();
@@ -518,6 +530,10 @@ impl p2p::ChainAdapter for NetToChainAdapter {
 			}
 			Err(e) => {
 				debug!("Block headers refused by chain: {:?}", e);
+				if let chain::ErrorKind::ReorgTooDeep(_) = e.kind() {
+					self.refused_reorg_peer(peer_info);
+					return Ok(true);
+				}
 				if e.is_bad_data() {
 					return Ok(false);
 				} else {
@@ -726,6 +742,7 @@ impl NetToChainAdapter {
 		verifier_cache: Arc<RwLock<dyn VerifierCache>>,
 		config: ServerConfig,
 		hooks: Vec<Box<dyn NetEvents + Send + Sync>>,
+		state_info: ServerStateInfo,
 	) -> NetToChainAdapter {
 		NetToChainAdapter {
 			sync_state,
@@ -735,6 +752,7 @@ impl NetToChainAdapter {
 			peers: OneTime::new(),
 			config,
 			hooks,
+			state_info,
 			processed_headers: EventCache::new(),
 			processed_blocks: EventCache::new(),
 			processed_transactions: EventCache::new(),
@@ -772,6 +790,16 @@ impl NetToChainAdapter {
 			.expect("Failed to upgrade weak ref to our peers.")
 	}
 
+	// A peer sending us a fork deeper than the maximum reorg depth is not
+	// banned, the fork is honest from its point of view and we need the peer
+	// to get the fork once the operator accepts the reorg.
+	fn refused_reorg_peer(&self, peer_info: &PeerInfo) {
+		warn!(
+			"Peer {} sent a fork deeper than the maximum reorg depth, refused",
+			peer_info.addr
+		);
+	}
+
 	fn chain(&self) -> Arc<chain::Chain> {
 		self.chain
 			.upgrade()
@@ -852,10 +880,12 @@ impl NetToChainAdapter {
 		let bhash = b.hash();
 		let previous = self.chain().get_previous_header(&b.header);
 
-		match self
+		let now = Instant::now();
+		let res = self
 			.chain()
-			.process_block(b, opts, self.get_invalid_block_hashes())
-		{
+			.process_block(b, opts, self.get_invalid_block_hashes());
+		self.state_info.block_latency.observe(now.elapsed());
+		match res {
 			Ok(_) => {
 				self.validate_chain(bhash);
 				self.check_compact();
@@ -879,6 +909,10 @@ impl NetToChainAdapter {
 						}
 						Ok(true)
 					}
+					chain::ErrorKind::ReorgTooDeep(_) => {
+						self.refused_reorg_peer(peer_info);
+						Ok(true)
+					}
 					_ => {
 						debug!(
 							"process_block: block {} refused by chain: {}",
@@ -926,10 +960,10 @@ impl NetToChainAdapter {
 			return;
 		}
 
-		// Roll the dice to trigger compaction at 1/COMPACTION_CHECK chance per block,
+		// Roll the dice to trigger compaction at 1/compaction_check() chance per block,
 		// uses a different thread to avoid blocking the caller thread (likely a peer)
 		let mut rng = thread_rng();
-		if 0 == rng.gen_range(0, global::COMPACTION_CHECK) {
+		if 0 == rng.gen_range(0, global::compaction_check()) {
 			let chain = self.chain().clone();
 			let _ = thread::Builder::new()
 				.name("compactor".to_string())
@@ -1013,6 +1047,7 @@ pub struct ChainToPoolAndNetAdapter {
 	tx_pool: Arc<RwLock<pool::TransactionPool>>,
 	peers: OneTime<Weak<p2p::Peers>>,
 	hooks: Vec<Box<dyn ChainEvents + Send + Sync>>,
+	reorg_depth_threshold: u64,
 }
 
 impl ChainAdapter for ChainToPoolAndNetAdapter {
@@ -1049,7 +1084,7 @@ impl ChainAdapter for ChainToPoolAndNetAdapter {
 			let _ = tx_pool.reconcile_block(b);
 
 			// First "age out" any old txs in the reorg_cache.
-			let cutoff = Utc::now() - Duration::minutes(tx_pool.config.reorg_cache_timeout);
+			let cutoff = global::now() - Duration::minutes(tx_pool.config.reorg_cache_timeout);
 			tx_pool.truncate_reorg_cache(cutoff);
 		}
 
@@ -1057,6 +1092,21 @@ impl ChainAdapter for ChainToPoolAndNetAdapter {
 			let _ = self.tx_pool.write().reconcile_reorg_cache(&b.header);
 		}
 	}
+
+	fn reorg(&self, depth: u64, old_tip: &chain::Tip, new_tip: &chain::Tip) {
+		if depth <= self.reorg_depth_threshold {
+			return;
+		}
+		for hook in &self.hooks {
+			hook.on_reorg(depth, old_tip, new_tip);
+		}
+	}
+
+	fn reorg_refused(&self, reorg: &chain::RefusedReorg) {
+		for hook in &self.hooks {
+			hook.on_reorg_refused(reorg);
+		}
+	}
 }
 
 impl ChainToPoolAndNetAdapter {
@@ -1064,11 +1114,13 @@ impl ChainToPoolAndNetAdapter {
 	pub fn new(
 		tx_pool: Arc<RwLock<pool::TransactionPool>>,
 		hooks: Vec<Box<dyn ChainEvents + Send + Sync>>,
+		reorg_depth_threshold: u64,
 	) -> ChainToPoolAndNetAdapter {
 		ChainToPoolAndNetAdapter {
 			tx_pool,
 			peers: OneTime::new(),
 			hooks: hooks,
+			reorg_depth_threshold,
 		}
 	}
 
@@ -1091,6 +1143,7 @@ impl ChainToPoolAndNetAdapter {
 pub struct PoolToNetAdapter {
 	peers: OneTime<Weak<p2p::Peers>>,
 	dandelion_epoch: Arc<RwLock<DandelionEpoch>>,
+	hooks: Vec<Box<dyn NetEvents + Send + Sync>>,
 }
 
 /// Adapter between the Dandelion monitor and the current Dandelion "epoch".
@@ -1122,6 +1175,15 @@ impl DandelionAdapter for PoolToNetAdapter {
 impl pool::PoolAdapter for PoolToNetAdapter {
 	fn tx_accepted(&self, entry: &pool::PoolEntry) {
 		self.peers().broadcast_transaction(&entry.tx);
+		for hook in &self.hooks {
+			hook.on_tx_pool_added(entry)
+		}
+	}
+
+	fn tx_evicted(&self, entry: &pool::PoolEntry) {
+		for hook in &self.hooks {
+			hook.on_tx_pool_evicted(entry)
+		}
 	}
 
 	fn stem_tx_accepted(&self, entry: &pool::PoolEntry) -> Result<(), pool::PoolError> {
@@ -1158,10 +1220,14 @@ impl pool::PoolAdapter for PoolToNetAdapter {
 
 impl PoolToNetAdapter {
 	/// Create a new pool to net adapter
-	pub fn new(config: pool::DandelionConfig) -> PoolToNetAdapter {
+	pub fn new(
+		config: pool::DandelionConfig,
+		hooks: Vec<Box<dyn NetEvents + Send + Sync>>,
+	) -> PoolToNetAdapter {
 		PoolToNetAdapter {
 			peers: OneTime::new(),
 			dandelion_epoch: Arc::new(RwLock::new(DandelionEpoch::new(config))),
+			hooks,
 		}
 	}
 
@@ -1227,9 +1293,14 @@ impl pool::BlockChain for PoolToChainAdapter {
 	}
 
 	fn validate_tx(&self, tx: &Transaction) -> Result<(), pool::PoolError> {
-		self.chain()
-			.validate_tx(tx)
-			.map_err(|e| pool::PoolError::Other(format!("failed to validate tx, {}", e)))
+		self.chain().validate_tx(tx).map_err(|e| match e.kind() {
+			chain::ErrorKind::AlreadySpent(commit) => pool::PoolError::InputNotFound(commit),
+			chain::ErrorKind::DuplicateCommitment(commit) => {
+				pool::PoolError::DuplicateCommitment(commit)
+			}
+			chain::ErrorKind::NRDRelativeHeight => pool::PoolError::NRDKernelRelativeHeight,
+			_ => pool::PoolError::Other(format!("failed to validate tx, {}", e)),
+		})
 	}
 
 	fn verify_coinbase_maturity(&self, tx: &Transaction) -> Result<(), pool::PoolError> {
@@ -19,11 +19,14 @@ extern crate hyper;
 extern crate hyper_rustls;
 extern crate tokio;
 
-use crate::chain::BlockStatus;
+use crate::api::{self, EventBroadcaster, Topic};
+use crate::chain::{self, BlockStatus};
 use crate::common::types::{ServerConfig, WebHooksConfig};
 use crate::core::core;
 use crate::core::core::hash::Hashed;
 use crate::p2p::types::PeerAddr;
+use crate::pool::PoolEntry;
+use crate::util;
 use futures::TryFutureExt;
 use hyper::client::HttpConnector;
 use hyper::header::HeaderValue;
@@ -32,6 +35,7 @@ use hyper::{Body, Method, Request};
 use hyper_rustls::HttpsConnector;
 use serde::Serialize;
 use serde_json::{json, to_string};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::runtime::{Builder, Runtime};
 
@@ -48,13 +52,29 @@ pub fn init_net_hooks(config: &ServerConfig) -> Vec<Box<dyn NetEvents + Send + S
 	list
 }
 
+/// Returns the list of event hooks that will be initialized for transaction
+/// pool events
+pub fn init_pool_hooks(
+	broadcaster: Arc<EventBroadcaster>,
+) -> Vec<Box<dyn NetEvents + Send + Sync>> {
+	let mut list: Vec<Box<dyn NetEvents + Send + Sync>> = Vec::new();
+	list.push(Box::new(EventStream { broadcaster }));
+	list
+}
+
 /// Returns the list of event hooks that will be initialized for chain events
-pub fn init_chain_hooks(config: &ServerConfig) -> Vec<Box<dyn ChainEvents + Send + Sync>> {
+pub fn init_chain_hooks(
+	config: &ServerConfig,
+	broadcaster: Arc<EventBroadcaster>,
+) -> Vec<Box<dyn ChainEvents + Send + Sync>> {
 	let mut list: Vec<Box<dyn ChainEvents + Send + Sync>> = Vec::new();
 	list.push(Box::new(EventLogger));
-	if config.webhook_config.block_accepted_url.is_some() {
+	if config.webhook_config.block_accepted_url.is_some()
+		|| config.webhook_config.reorg_url.is_some()
+	{
 		list.push(Box::new(WebHook::from_config(&config.webhook_config)));
 	}
+	list.push(Box::new(EventStream { broadcaster }));
 	list
 }
 
@@ -69,6 +89,12 @@ pub trait NetEvents {
 
 	/// Triggers when a new block header arrives
 	fn on_header_received(&self, header: &core::BlockHeader, addr: &PeerAddr) {}
+
+	/// Triggers when a transaction is accepted into the transaction pool
+	fn on_tx_pool_added(&self, entry: &PoolEntry) {}
+
+	/// Triggers when a transaction is evicted from the transaction pool
+	fn on_tx_pool_evicted(&self, entry: &PoolEntry) {}
 }
 
 #[allow(unused_variables)]
@@ -76,6 +102,13 @@ pub trait NetEvents {
 pub trait ChainEvents {
 	/// Triggers when a new block is accepted by the chain (might be a Reorg or a Fork)
 	fn on_block_accepted(&self, block: &core::Block, status: &BlockStatus) {}
+
+	/// Triggers when the chain head switches to another fork, rewinding more
+	/// blocks than the configured threshold
+	fn on_reorg(&self, depth: u64, old_tip: &chain::Tip, new_tip: &chain::Tip) {}
+
+	/// Triggers when the chain refuses a fork deeper than the maximum reorg depth
+	fn on_reorg_refused(&self, reorg: &chain::RefusedReorg) {}
 }
 
 /// Basic Logger
@@ -144,6 +177,88 @@ impl ChainEvents for EventLogger {
 			}
 		}
 	}
+
+	fn on_reorg(&self, depth: u64, old_tip: &chain::Tip, new_tip: &chain::Tip) {
+		warn!(
+			"reorg: {} blocks rewound, tip {:?} at {} (diff: {}) replaced by {:?} at {} (diff: {})",
+			depth,
+			old_tip.last_block_h,
+			old_tip.height,
+			old_tip.total_difficulty,
+			new_tip.last_block_h,
+			new_tip.height,
+			new_tip.total_difficulty,
+		);
+	}
+
+	fn on_reorg_refused(&self, reorg: &chain::RefusedReorg) {
+		warn!(
+			"reorg_refused: fork at {:?} {} (diff: {}) is deeper than the max reorg depth of {}",
+			reorg.hash, reorg.height, reorg.total_difficulty, reorg.max_depth,
+		);
+	}
+}
+
+/// Publishes events to the clients subscribed on the foreign API
+/// (`/v2/foreign/subscribe`)
+struct EventStream {
+	broadcaster: Arc<EventBroadcaster>,
+}
+
+impl EventStream {
+	fn tx_payload(entry: &PoolEntry) -> serde_json::Value {
+		let kernels: Vec<String> = entry
+			.tx
+			.kernels()
+			.iter()
+			.map(|k| util::to_hex(k.excess.0.to_vec()))
+			.collect();
+		json!({
+			"hash": entry.tx.hash().to_hex(),
+			"fee": entry.tx.fee(),
+			"weight": entry.tx.tx_weight(),
+			"kernels": kernels,
+			"src": entry.src,
+			"tx_at": entry.tx_at,
+		})
+	}
+}
+
+impl ChainEvents for EventStream {
+	fn on_block_accepted(&self, block: &core::Block, status: &BlockStatus) {
+		let (status_str, depth) = match status {
+			BlockStatus::Reorg(depth) => ("reorg", Some(depth)),
+			BlockStatus::Fork => ("fork", None),
+			BlockStatus::Next => ("next", None),
+		};
+		let payload = json!({
+			"hash": block.header.hash().to_hex(),
+			"height": block.header.height,
+			"prev_hash": block.header.prev_hash.to_hex(),
+			"total_difficulty": block.header.total_difficulty().to_num(),
+			"status": status_str,
+			"depth": depth,
+		});
+		self.broadcaster.publish(Topic::BlockAccepted, &payload);
+
+		if let BlockStatus::Fork = status {
+			return;
+		}
+		let tip = api::Tip::from_tip(chain::Tip::from_header(&block.header));
+		self.broadcaster.publish(Topic::NewTip, &tip);
+	}
+}
+
+impl NetEvents for EventStream {
+	fn on_tx_pool_added(&self, entry: &PoolEntry) {
+		self.broadcaster
+			.publish(Topic::TxPoolAdded, &EventStream::tx_payload(entry));
+	}
+
+	fn on_tx_pool_evicted(&self, entry: &PoolEntry) {
+		self.broadcaster
+			.publish(Topic::TxPoolEvicted, &EventStream::tx_payload(entry));
+	}
 }
 
 fn parse_url(value: &Option<String>) -> Option<hyper::Uri> {
@@ -176,6 +291,8 @@ struct WebHook {
 	block_received_url: Option<hyper::Uri>,
 	/// url to POST block data when a new block is accepted by our node (might be a reorg or a fork)
 	block_accepted_url: Option<hyper::Uri>,
+	/// url to POST the old and new tips when a deep reorg happens
+	reorg_url: Option<hyper::Uri>,
 	/// The hyper client to be used for all requests
 	client: Client<HttpsConnector<HttpConnector>>,
 	/// The tokio event loop
@@ -189,6 +306,7 @@ impl WebHook {
 		header_received_url: Option<hyper::Uri>,
 		block_received_url: Option<hyper::Uri>,
 		block_accepted_url: Option<hyper::Uri>,
+		reorg_url: Option<hyper::Uri>,
 		nthreads: u16,
 		timeout: u16,
 	) -> WebHook {
@@ -209,6 +327,7 @@ impl WebHook {
 			block_received_url,
 			header_received_url,
 			block_accepted_url,
+			reorg_url,
 			client,
 			runtime: Builder::new()
 				.threaded_scheduler()
@@ -226,6 +345,7 @@ impl WebHook {
 			parse_url(&config.header_received_url),
 			parse_url(&config.block_received_url),
 			parse_url(&config.block_accepted_url),
+			parse_url(&config.reorg_url),
 			config.nthreads,
 			config.timeout,
 		)
@@ -293,6 +413,17 @@ impl ChainEvents for WebHook {
 			);
 		}
 	}
+
+	fn on_reorg(&self, depth: u64, old_tip: &chain::Tip, new_tip: &chain::Tip) {
+		let payload = json!({
+			"depth": depth,
+			"old_tip": api::Tip::from_tip(old_tip.clone()),
+			"new_tip": api::Tip::from_tip(new_tip.clone()),
+		});
+		if !self.make_request(&payload, &self.reorg_url) {
+			error!("Failed to serialize reorg to {}", new_tip.last_block_h);
+		}
+	}
 }
 
 impl NetEvents for WebHook {
@@ -0,0 +1,476 @@
+// Copyright 2020 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Prometheus metrics endpoint, exposes the server stats in the text
+//! exposition format on a dedicated listener.
+
+use std::fmt::{Display, Write};
+use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use futures::future::ok;
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Request, Response, StatusCode};
+
+use crate::api::{self, ApiServer, BasicAuthMiddleware, Handler, ResponseFuture, Router};
+use crate::chain::{self, SyncState, SyncStatus};
+use crate::common::stats::{LatencyHistogram, ServerStateInfo};
+use crate::common::types::Error;
+use crate::core::global;
+use crate::p2p::{self, types::Direction, Capabilities};
+use crate::pool;
+use crate::util::{to_base64, RwLock};
+
+const CONTENT_TYPE_TEXT: &str = "text/plain; version=0.0.4";
+
+/// Serves the node metrics in the Prometheus text format.
+/// GET /metrics
+pub struct MetricsHandler {
+	/// The chain
+	pub chain: Weak<chain::Chain>,
+	/// Transaction and stem pools
+	pub tx_pool: Weak<RwLock<pool::TransactionPool>>,
+	/// Connected peers
+	pub peers: Weak<p2p::Peers>,
+	/// Sync status
+	pub sync_state: Weak<SyncState>,
+	/// Stratum stats and processing latencies
+	pub state_info: ServerStateInfo,
+}
+
+impl MetricsHandler {
+	fn collect(&self) -> Result<String, api::Error> {
+		let chain = w(&self.chain)?;
+		let mut m = MetricsWriter::new();
+
+		let head = chain.head_header()?;
+		let difficulty = if head.height > 0 {
+			let prev = chain.get_previous_header(&head)?;
+			head.total_difficulty().to_num() - prev.total_difficulty().to_num()
+		} else {
+			head.total_difficulty().to_num()
+		};
+		m.gauge(
+			"mimble_chain_height",
+			"Height of the chain head",
+			head.height,
+		);
+		m.gauge(
+			"mimble_chain_total_difficulty",
+			"Total difficulty of the chain head",
+			head.total_difficulty().to_num(),
+		);
+		m.gauge(
+			"mimble_chain_difficulty",
+			"Difficulty of the block at the chain head",
+			difficulty,
+		);
+		let header_head = chain.header_head()?;
+		m.gauge(
+			"mimble_header_height",
+			"Height of the header chain head",
+			header_head.height,
+		);
+		m.gauge(
+			"mimble_header_total_difficulty",
+			"Total difficulty of the header chain head",
+			header_head.total_difficulty.to_num(),
+		);
+		m.gauge(
+			"mimble_chain_orphans",
+			"Number of blocks in the orphan pool",
+			chain.orphans_len(),
+		);
+		m.counter(
+			"mimble_chain_orphans_evicted_total",
+			"Number of orphans evicted because the orphan pool was full",
+			chain.orphans_evicted_len(),
+		);
+		let (map_size, used) = chain
+			.store()
+			.map_usage()
+			.map_err(|e| api::ErrorKind::Internal(format!("db map usage, {}", e)))?;
+		m.gauge(
+			"mimble_lmdb_map_size_bytes",
+			"Map size of the chain LMDB environment",
+			map_size,
+		);
+		m.gauge(
+			"mimble_lmdb_used_bytes",
+			"Space used in the chain LMDB environment",
+			used,
+		);
+
+		self.collect_sync(&mut m)?;
+		self.collect_pool(&mut m)?;
+		self.collect_peers(&mut m)?;
+		self.collect_stratum(&mut m);
+
+		m.histogram(
+			"mimble_block_processing_seconds",
+			"Time spent processing a full block",
+			&self.state_info.block_latency,
+		);
+		m.histogram(
+			"mimble_header_processing_seconds",
+			"Time spent processing a batch of headers during sync",
+			&self.state_info.header_latency,
+		);
+		Ok(m.finish())
+	}
+
+	fn collect_sync(&self, m: &mut MetricsWriter) -> Result<(), api::Error> {
+		let status = w(&self.sync_state)?.status();
+		let name = format!("{:?}", status);
+		let name = name.split(|c| c == ' ' || c == '(').next().unwrap_or("");
+		m.labeled(
+			"mimble_sync_status",
+			"Current sync status, the active one is set to 1",
+			"gauge",
+			vec![(vec![("status", name.to_owned())], 1)],
+		);
+		let (stage, current, highest) = match status {
+			SyncStatus::HeaderSync {
+				current_height,
+				highest_height,
+			} => ("header", current_height, highest_height),
+			SyncStatus::BodySync {
+				current_height,
+				highest_height,
+			} => ("body", current_height, highest_height),
+			SyncStatus::TxHashsetDownload {
+				downloaded_size,
+				total_size,
+				..
+			} => ("txhashset", downloaded_size, total_size),
+			_ => return Ok(()),
+		};
+		let label = || vec![("stage", stage.to_owned())];
+		m.labeled(
+			"mimble_sync_current",
+			"Sync progress of the current stage (height, or bytes for the txhashset)",
+			"gauge",
+			vec![(label(), current)],
+		);
+		m.labeled(
+			"mimble_sync_target",
+			"Sync target of the current stage (height, or bytes for the txhashset)",
+			"gauge",
+			vec![(label(), highest)],
+		);
+		Ok(())
+	}
+
+	fn collect_pool(&self, m: &mut MetricsWriter) -> Result<(), api::Error> {
+		let tx_pool = w(&self.tx_pool)?;
+		// Scraping should not block the pool, skip the pool metrics if busy
+		let pool = match tx_pool.try_read_for(Duration::from_millis(500)) {
+			Some(pool) => pool,
+			None => return Ok(()),
+		};
+		m.labeled(
+			"mimble_pool_transactions",
+			"Number of transactions in the pool",
+			"gauge",
+			vec![
+				(vec![("pool", "txpool".to_owned())], pool.txpool.size()),
+				(vec![("pool", "stempool".to_owned())], pool.stempool.size()),
+			],
+		);
+		m.labeled(
+			"mimble_pool_kernels",
+			"Number of transaction kernels in the pool",
+			"gauge",
+			vec![
+				(
+					vec![("pool", "txpool".to_owned())],
+					pool.txpool.kernel_count(),
+				),
+				(
+					vec![("pool", "stempool".to_owned())],
+					pool.stempool.kernel_count(),
+				),
+			],
+		);
+		Ok(())
+	}
+
+	fn collect_peers(&self, m: &mut MetricsWriter) -> Result<(), api::Error> {
+		let peers = w(&self.peers)?.connected_peers();
+		let directions = [
+			(Direction::Inbound, "inbound"),
+			(Direction::Outbound, "outbound"),
+			(Direction::InboundTor, "inbound_tor"),
+			(Direction::OutboundTor, "outbound_tor"),
+		];
+		m.labeled(
+			"mimble_peers_connected",
+			"Number of connected peers by direction",
+			"gauge",
+			directions
+				.iter()
+				.map(|(direction, name)| {
+					let count = peers
+						.iter()
+						.filter(|p| p.info.direction == *direction)
+						.count();
+					(vec![("direction", (*name).to_owned())], count)
+				})
+				.collect(),
+		);
+		let capabilities = [
+			(Capabilities::HEADER_HIST, "header_hist"),
+			(Capabilities::TXHASHSET_HIST, "txhashset_hist"),
+			(Capabilities::PEER_LIST, "peer_list"),
+			(Capabilities::TX_KERNEL_HASH, "tx_kernel_hash"),
+			(Capabilities::TOR_ADDRESS, "tor_address"),
+		];
+		m.labeled(
+			"mimble_peers_capability",
+			"Number of connected peers advertising a capability",
+			"gauge",
+			capabilities
+				.iter()
+				.map(|(capability, name)| {
+					let count = peers
+						.iter()
+						.filter(|p| p.info.capabilities.contains(*capability))
+						.count();
+					(vec![("capability", (*name).to_owned())], count)
+				})
+				.collect(),
+		);
+		let addr = |p: &Arc<p2p::Peer>| vec![("addr", p.info.addr.to_string())];
+		m.labeled(
+			"mimble_peer_sent_bytes",
+			"Bytes sent to the peer over the last minute",
+			"gauge",
+			peers
+				.iter()
+				.map(|p| (addr(p), p.last_min_sent_bytes().unwrap_or(0)))
+				.collect(),
+		);
+		m.labeled(
+			"mimble_peer_received_bytes",
+			"Bytes received from the peer over the last minute",
+			"gauge",
+			peers
+				.iter()
+				.map(|p| (addr(p), p.last_min_received_bytes().unwrap_or(0)))
+				.collect(),
+		);
+		Ok(())
+	}
+
+	fn collect_stratum(&self, m: &mut MetricsWriter) {
+		let stats = &self.state_info.stratum_stats;
+		let workers = stats.get_worker_stats();
+		m.gauge(
+			"mimble_stratum_workers",
+			"Number of connected stratum workers",
+			stats.num_workers.load(Ordering::Relaxed),
+		);
+		m.counter(
+			"mimble_stratum_shares_accepted_total",
+			"Valid shares submitted by the stratum workers",
+			workers.iter().map(|w| w.num_accepted).sum::<u64>(),
+		);
+		m.counter(
+			"mimble_stratum_shares_rejected_total",
+			"Invalid shares submitted by the stratum workers",
+			workers.iter().map(|w| w.num_rejected).sum::<u64>(),
+		);
+		m.counter(
+			"mimble_stratum_shares_stale_total",
+			"Shares submitted too late by the stratum workers",
+			workers.iter().map(|w| w.num_stale).sum::<u64>(),
+		);
+		m.counter(
+			"mimble_stratum_blocks_found_total",
+			"Blocks found by the stratum workers",
+			workers.iter().map(|w| w.num_blocks_found).sum::<u64>(),
+		);
+	}
+}
+
+impl Handler for MetricsHandler {
+	fn get(&self, _req: Request<Body>) -> ResponseFuture {
+		match self.collect() {
+			Ok(text) => {
+				let res = Response::builder()
+					.status(StatusCode::OK)
+					.header(CONTENT_TYPE, CONTENT_TYPE_TEXT)
+					.body(text.into())
+					.unwrap();
+				Box::pin(ok(res))
+			}
+			Err(e) => api::response(StatusCode::INTERNAL_SERVER_ERROR, format!("{}", e)),
+		}
+	}
+}
+
+fn w<T>(weak: &Weak<T>) -> Result<Arc<T>, api::Error> {
+	weak.upgrade().ok_or_else(|| {
+		api::ErrorKind::Internal("failed to upgrade weak reference".to_owned()).into()
+	})
+}
+
+/// Start the metrics listener at the provided address, with basic auth if
+/// a secret is provided.
+pub fn start_metrics_server(
+	addr: &str,
+	secret: Option<String>,
+	handler: MetricsHandler,
+) -> Result<(), Error> {
+	let socket_addr: SocketAddr = addr
+		.parse()
+		.map_err(|e| Error::Configuration(format!("invalid metrics address {}, {}", addr, e)))?;
+
+	let mut router = Router::new();
+	router
+		.add_route("/metrics", Arc::new(handler))
+		.map_err(|e| Error::API(api::ErrorKind::Router(e).into()))?;
+
+	if let Some(secret) = secret {
+		let basic_auth_key = if global::is_mainnet() {
+			"mwcmain"
+		} else if global::is_floonet() {
+			"mwcfloo"
+		} else {
+			"mwc"
+		};
+		let basic_auth = format!(
+			"Basic {}",
+			to_base64(&format!("{}:{}", basic_auth_key, secret))
+		);
+		router.add_middleware(Arc::new(BasicAuthMiddleware::new(
+			basic_auth,
+			&api::MWC_METRICS_BASIC_REALM,
+			None,
+		)));
+	}
+
+	warn!("Starting metrics server at {}.", addr);
+	ApiServer::new().start(socket_addr, router, None)?;
+	Ok(())
+}
+
+/// Accumulates metrics in the Prometheus text exposition format.
+struct MetricsWriter {
+	out: String,
+}
+
+impl MetricsWriter {
+	fn new() -> MetricsWriter {
+		MetricsWriter { out: String::new() }
+	}
+
+	fn header(&mut self, name: &str, help: &str, kind: &str) {
+		let _ = writeln!(self.out, "# HELP {} {}", name, help);
+		let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+	}
+
+	fn gauge<T: Display>(&mut self, name: &str, help: &str, value: T) {
+		self.header(name, help, "gauge");
+		let _ = writeln!(self.out, "{} {}", name, value);
+	}
+
+	fn counter<T: Display>(&mut self, name: &str, help: &str, value: T) {
+		self.header(name, help, "counter");
+		let _ = writeln!(self.out, "{} {}", name, value);
+	}
+
+	fn labeled<T: Display>(
+		&mut self,
+		name: &str,
+		help: &str,
+		kind: &str,
+		samples: Vec<(Vec<(&str, String)>, T)>,
+	) {
+		self.header(name, help, kind);
+		for (labels, value) in samples {
+			let _ = writeln!(self.out, "{}{{{}}} {}", name, format_labels(&labels), value);
+		}
+	}
+
+	fn histogram(&mut self, name: &str, help: &str, histogram: &LatencyHistogram) {
+		self.header(name, help, "histogram");
+		let counts = histogram.cumulative_counts();
+		for (bound, count) in histogram.bounds_ms().iter().zip(counts) {
+			let _ = writeln!(
+				self.out,
+				"{}_bucket{{le=\"{}\"}} {}",
+				name,
+				*bound as f64 / 1000.0,
+				count
+			);
+		}
+		let count = histogram.count();
+		let _ = writeln!(self.out, "{}_bucket{{le=\"+Inf\"}} {}", name, count);
+		let _ = writeln!(self.out, "{}_sum {}", name, histogram.sum().as_secs_f64());
+		let _ = writeln!(self.out, "{}_count {}", name, count);
+	}
+
+	fn finish(self) -> String {
+		self.out
+	}
+}
+
+fn format_labels(labels: &[(&str, String)]) -> String {
+	labels
+		.iter()
+		.map(|(k, v)| {
+			let v = v
+				.replace('\\', "\\\\")
+				.replace('"', "\\\"")
+				.replace('\n', "\\n");
+			format!("{}=\"{}\"", k, v)
+		})
+		.collect::<Vec<_>>()
+		.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_metrics_format() {
+		let histogram = LatencyHistogram::default();
+		histogram.observe(Duration::from_millis(3));
+		histogram.observe(Duration::from_millis(70));
+		histogram.observe(Duration::from_secs(60));
+
+		let mut m = MetricsWriter::new();
+		m.gauge("a_height", "Height", 12u64);
+		m.labeled(
+			"a_peers",
+			"Peers",
+			"gauge",
+			vec![(vec![("addr", "1.2.3.4:\"5\"".to_owned())], 2usize)],
+		);
+		m.histogram("a_seconds", "Latency", &histogram);
+		let out = m.finish();
+
+		assert!(out.contains("# TYPE a_height gauge\na_height 12\n"));
+		assert!(out.contains("a_peers{addr=\"1.2.3.4:\\\"5\\\"\"} 2\n"));
+		assert!(out.contains("a_seconds_bucket{le=\"0.005\"} 1\n"));
+		assert!(out.contains("a_seconds_bucket{le=\"0.1\"} 2\n"));
+		assert!(out.contains("a_seconds_bucket{le=\"10\"} 2\n"));
+		assert!(out.contains("a_seconds_bucket{le=\"+Inf\"} 3\n"));
+		assert!(out.contains("a_seconds_count 3\n"));
+	}
+}
@@ -18,7 +18,7 @@
 use crate::util::RwLock;
 use std::sync::atomic::*;
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use crate::core::consensus::graph_weight;
 use crate::core::core::hash::Hash;
@@ -26,7 +26,7 @@ use crate::core::ser::ProtocolVersion;
 
 use chrono::prelude::*;
 
-use crate::chain::SyncStatus;
+use crate::chain::{SyncStatus, ValidationStatus};
 use crate::p2p;
 use mimble_core::pow::Difficulty;
 
@@ -36,12 +36,18 @@ use mimble_core::pow::Difficulty;
 pub struct ServerStateInfo {
 	/// Stratum stats
 	pub stratum_stats: Arc<StratumStats>,
+	/// Time spent processing full blocks
+	pub block_latency: Arc<LatencyHistogram>,
+	/// Time spent processing batches of headers during sync
+	pub header_latency: Arc<LatencyHistogram>,
 }
 
 impl Default for ServerStateInfo {
 	fn default() -> ServerStateInfo {
 		ServerStateInfo {
 			stratum_stats: Arc::new(StratumStats::default()),
+			block_latency: Arc::new(LatencyHistogram::default()),
+			header_latency: Arc::new(LatencyHistogram::default()),
 		}
 	}
 }
@@ -67,6 +73,8 @@ pub struct ServerStats {
 	pub tx_stats: Option<TxStats>,
 	/// Disk usage in GB
 	pub disk_usage_gb: String,
+	/// Progress of the full chain validation
+	pub validation_status: ValidationStatus,
 }
 
 /// Chain Statistics
@@ -135,6 +143,19 @@ pub struct StratumStats {
 	worker_stats: RwLock<Vec<WorkerStats>>,
 }
 
+/// Upper bounds of the latency histogram buckets, in milliseconds
+const LATENCY_BUCKETS_MS: [u64; 11] = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+/// Histogram of processing durations since the server started
+#[derive(Debug)]
+pub struct LatencyHistogram {
+	/// Number of observations per bucket, the extra last bucket counts
+	/// everything above the highest bound
+	buckets: Vec<AtomicU64>,
+	/// Sum of all the observed durations in microseconds
+	sum_us: AtomicU64,
+}
+
 /// Stats on the last WINDOW blocks and the difficulty calculation
 #[derive(Clone)]
 pub struct DiffStats {
@@ -262,6 +283,52 @@ impl StratumStats {
 	}
 }
 
+impl LatencyHistogram {
+	/// Record a duration
+	pub fn observe(&self, duration: Duration) {
+		let ms = duration.as_millis() as u64;
+		let idx = LATENCY_BUCKETS_MS
+			.iter()
+			.position(|bound| ms <= *bound)
+			.unwrap_or(LATENCY_BUCKETS_MS.len());
+		self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+		self.sum_us
+			.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+	}
+
+	/// Upper bounds of the buckets, in milliseconds
+	pub fn bounds_ms(&self) -> &'static [u64] {
+		&LATENCY_BUCKETS_MS
+	}
+
+	/// Number of observations less than or equal to each bound, in the
+	/// order of `bounds_ms`
+	pub fn cumulative_counts(&self) -> Vec<u64> {
+		let mut total = 0;
+		LATENCY_BUCKETS_MS
+			.iter()
+			.zip(self.buckets.iter())
+			.map(|(_, count)| {
+				total += count.load(Ordering::Relaxed);
+				total
+			})
+			.collect()
+	}
+
+	/// Total number of observations
+	pub fn count(&self) -> u64 {
+		self.buckets
+			.iter()
+			.map(|count| count.load(Ordering::Relaxed))
+			.sum()
+	}
+
+	/// Sum of all the observed durations
+	pub fn sum(&self) -> Duration {
+		Duration::from_micros(self.sum_us.load(Ordering::Relaxed))
+	}
+}
+
 impl PeerStats {
 	/// Convert from a peer directly
 	pub fn from_peer(peer: &p2p::Peer) -> PeerStats {
@@ -311,6 +378,17 @@ impl Default for WorkerStats {
 	}
 }
 
+impl Default for LatencyHistogram {
+	fn default() -> LatencyHistogram {
+		LatencyHistogram {
+			buckets: (0..=LATENCY_BUCKETS_MS.len())
+				.map(|_| AtomicU64::new(0))
+				.collect(),
+			sum_us: AtomicU64::new(0),
+		}
+	}
+}
+
 impl Default for StratumStats {
 	fn default() -> StratumStats {
 		StratumStats {
@@ -16,12 +16,11 @@
 use std::convert::From;
 use std::sync::Arc;
 
-use chrono::prelude::Utc;
 use rand::prelude::*;
 
 use crate::api;
 use crate::chain;
-use crate::core::global::ChainTypes;
+use crate::core::global::{self, ChainTypes};
 use crate::core::{core, libtx, pow};
 use crate::keychain;
 use crate::p2p;
@@ -191,6 +190,31 @@ pub struct ServerConfig {
 	/// Location of secret for basic auth on v2 Foreign API server.
 	pub foreign_api_secret_path: Option<String>,
 
+	/// Number of events buffered per client of the v2 Foreign API event
+	/// subscription before the client is considered too slow and dropped.
+	pub api_subscribe_buffer_size: Option<usize>,
+
+	/// Maximum number of clients connected to the v2 Foreign API event
+	/// subscription at once, new clients above it are rejected.
+	pub api_max_subscribers: Option<usize>,
+
+	/// Maximum number of calls accepted in a single JSON-RPC batch request
+	/// on the v2 APIs.
+	pub api_max_batch_size: Option<usize>,
+
+	/// Network address for the Prometheus metrics endpoint, disabled if not set.
+	pub metrics_http_addr: Option<String>,
+
+	/// Location of secret for basic auth on the metrics endpoint.
+	pub metrics_secret_path: Option<String>,
+
+	/// Scoped API tokens, each restricted to a list of owner or foreign API
+	/// methods and v1 routes, accepted along with the API secrets.
+	pub api_tokens: Option<Vec<api::ApiToken>>,
+
+	/// Per client rate limiting of the v2 Foreign API, disabled if not set.
+	pub api_rate_limit: Option<api::RateLimitConfig>,
+
 	/// TLS certificate file
 	pub tls_certificate_file: Option<String>,
 	/// TLS certificate private key file
@@ -200,6 +224,10 @@ pub struct ServerConfig {
 	#[serde(default)]
 	pub chain_type: ChainTypes,
 
+	/// Chain spec (TOML or JSON) file of a user defined network, required
+	/// with the Custom chain type
+	pub chain_spec_file: Option<String>,
+
 	/// Automatically run full chain validation during normal block processing?
 	#[serde(default)]
 	pub chain_validation_mode: ChainValidationMode,
@@ -207,6 +235,18 @@ pub struct ServerConfig {
 	/// Whether this node is a full archival node or a fast-sync, pruned node
 	pub archive_mode: Option<bool>,
 
+	/// Whether to maintain an index of kernel excess commitments for fast
+	/// kernel lookups via the API
+	pub kernel_index: Option<bool>,
+
+	/// Whether to maintain an index of output creation and spend heights
+	/// (archive mode only)
+	pub output_history_index: Option<bool>,
+
+	/// Maximum number of blocks a fork may rewind from our chain head,
+	/// deeper forks are refused. Unlimited if not set.
+	pub max_reorg_depth: Option<u64>,
+
 	/// Whether to skip the sync timeout on startup
 	/// (To assist testing on solo chains)
 	pub skip_sync_wait: Option<bool>,
@@ -268,13 +308,24 @@ impl Default for ServerConfig {
 			api_http_addr: "127.0.0.1:3413".to_string(),
 			api_secret_path: Some(".api_secret".to_string()),
 			foreign_api_secret_path: Some(".foreign_api_secret".to_string()),
+			api_subscribe_buffer_size: Some(api::DEFAULT_SUBSCRIBE_BUFFER_SIZE),
+			api_max_subscribers: Some(api::DEFAULT_MAX_SUBSCRIBERS),
+			api_max_batch_size: Some(api::DEFAULT_MAX_BATCH_SIZE),
+			metrics_http_addr: None,
+			metrics_secret_path: None,
+			api_tokens: None,
+			api_rate_limit: None,
 			tls_certificate_file: None,
 			tls_certificate_key: None,
 			p2p_config: p2p::P2PConfig::default(),
 			dandelion_config: pool::DandelionConfig::default(),
 			stratum_mining_config: Some(StratumServerConfig::default()),
 			chain_type: ChainTypes::default(),
+			chain_spec_file: None,
 			archive_mode: Some(false),
+			kernel_index: Some(false),
+			output_history_index: Some(false),
+			max_reorg_depth: None,
 			chain_validation_mode: ChainValidationMode::default(),
 			pool_config: pool::PoolConfig::default(),
 			skip_sync_wait: Some(false),
@@ -409,18 +460,28 @@ pub struct WebHooksConfig {
 	pub block_received_url: Option<String>,
 	/// url to POST block data when a new block is accepted by our node (might be a reorg or a fork)
 	pub block_accepted_url: Option<String>,
+	/// url to POST the old and new tips when a reorg deeper than
+	/// reorg_depth_threshold happens
+	pub reorg_url: Option<String>,
 	/// number of worker threads in the tokio runtime
 	#[serde(default = "default_nthreads")]
 	pub nthreads: u16,
 	/// timeout in seconds for the http request
 	#[serde(default = "default_timeout")]
 	pub timeout: u16,
+	/// reorgs rewinding more blocks than this trigger the reorg hooks
+	#[serde(default = "default_reorg_depth_threshold")]
+	pub reorg_depth_threshold: u64,
 }
 
 fn default_timeout() -> u16 {
 	10
 }
 
+fn default_reorg_depth_threshold() -> u64 {
+	1
+}
+
 fn default_nthreads() -> u16 {
 	4
 }
@@ -432,8 +493,10 @@ impl Default for WebHooksConfig {
 			header_received_url: None,
 			block_received_url: None,
 			block_accepted_url: None,
+			reorg_url: None,
 			nthreads: default_nthreads(),
 			timeout: default_timeout(),
+			reorg_depth_threshold: default_reorg_depth_threshold(),
 		}
 	}
 }
@@ -469,7 +532,7 @@ impl DandelionEpoch {
 			None => true,
 			Some(start_time) => {
 				let epoch_secs = self.config.epoch_secs;
-				Utc::now().timestamp().saturating_sub(start_time) > epoch_secs as i64
+				global::now().timestamp().saturating_sub(start_time) > epoch_secs as i64
 			}
 		}
 	}
@@ -478,7 +541,7 @@ impl DandelionEpoch {
 	/// Select stem/fluff based on configured stem_probability.
 	/// Choose a new outbound stem relay peer.
 	pub fn next_epoch(&mut self, peers: &Arc<p2p::Peers>) {
-		self.start_time = Some(Utc::now().timestamp());
+		self.start_time = Some(global::now().timestamp());
 		self.relay_peer = peers.outgoing_connected_peers().first().cloned();
 
 		// If stem_probability == 90 then we stem 90% of the time.
@@ -15,6 +15,7 @@
 //! Grin P2P / API server
 
 pub mod dandelion_monitor;
+pub mod offline;
 pub mod seed;
 pub mod server;
 pub mod sync;
@@ -12,7 +12,6 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use chrono::prelude::Utc;
 use rand::{thread_rng, Rng};
 use std::sync::Arc;
 use std::thread;
@@ -22,6 +21,7 @@ use crate::common::adapters::DandelionAdapter;
 use crate::core::core::hash::Hashed;
 use crate::core::core::transaction;
 use crate::core::core::verifier_cache::VerifierCache;
+use crate::core::global;
 use crate::pool::{DandelionConfig, Pool, PoolEntry, PoolError, TransactionPool, TxSource};
 use crate::util::{RwLock, StopState};
 
@@ -91,7 +91,7 @@ pub fn monitor_transactions(
 // Query the pool for transactions older than the cutoff.
 // Used for both periodic fluffing and handling expired embargo timer.
 fn select_txs_cutoff(pool: &Pool, cutoff_secs: u16) -> Vec<PoolEntry> {
-	let cutoff = Utc::now().timestamp() - cutoff_secs as i64;
+	let cutoff = global::now().timestamp() - cutoff_secs as i64;
 	pool.entries
 		.iter()
 		.filter(|x| x.tx_at.timestamp() < cutoff)
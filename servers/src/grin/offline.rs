@@ -0,0 +1,121 @@
+// Copyright 2020 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Access to the chain of a stopped node, for the maintenance commands run
+//! from the command line.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::chain::{self, types::NoopAdapter, ChainOptions, SyncState};
+use crate::common::types::{Error, ServerConfig};
+use crate::core::core::hash::Hashed;
+use crate::core::core::verifier_cache::LruVerifierCache;
+use crate::core::core::BlockHeader;
+use crate::core::pow;
+use crate::core::ser::ProtocolVersion;
+use crate::grin::server::{genesis_block, Server};
+use crate::util::RwLock;
+
+/// The chain of the node opened without any of its servers. The db lock is
+/// held as long as it's alive, so no node can run on the same db meanwhile.
+pub struct OfflineChain {
+	/// The chain, opened on the configured db root
+	pub chain: Arc<chain::Chain>,
+	_lock_file: Arc<File>,
+}
+
+impl OfflineChain {
+	/// Open the chain as configured for the node.
+	pub fn open(config: &ServerConfig) -> Result<OfflineChain, Error> {
+		let lock_file = Server::one_mimble_at_a_time(config)?;
+		let chain = chain::Chain::init(
+			config.db_root.clone(),
+			Arc::new(NoopAdapter {}),
+			genesis_block(config.chain_type.clone(), &config.db_root)?,
+			pow::verify_size,
+			Arc::new(RwLock::new(LruVerifierCache::new())),
+			ChainOptions {
+				archive_mode: config.archive_mode.unwrap_or(false),
+				kernel_index: config.kernel_index.unwrap_or(false),
+				output_history_index: config.output_history_index.unwrap_or(false),
+				max_reorg_depth: config.max_reorg_depth,
+			},
+		)?;
+		Ok(OfflineChain {
+			chain: Arc::new(chain),
+			_lock_file: lock_file,
+		})
+	}
+
+	/// Export a snapshot of the chain state at the provided height (the head
+	/// if none) to a file. Returns the header of the snapshot.
+	pub fn export_snapshot(&self, height: Option<u64>, out: &Path) -> Result<BlockHeader, Error> {
+		let height = match height {
+			Some(height) => height,
+			None => self.chain.head()?.height,
+		};
+		let header = self.chain.get_header_by_height(height)?;
+		self.chain.snapshot_export(header.hash(), out)?;
+		Ok(header)
+	}
+
+	/// Bootstrap the (empty) chain from a snapshot file, without contacting
+	/// any peer. Returns the header of the snapshot.
+	pub fn import_snapshot(&self, snapshot: &Path) -> Result<BlockHeader, Error> {
+		let status = SyncState::new();
+		let header = self.chain.snapshot_import(snapshot, &status)?;
+		Ok(header)
+	}
+
+	/// Export all the blocks, from genesis to the head, to a flat bootstrap
+	/// file. Requires an archive node. Returns the number of blocks exported.
+	pub fn export_blocks(
+		&self,
+		out: &Path,
+		progress: &mut dyn FnMut(u64, u64),
+	) -> Result<u64, Error> {
+		let mut file = BufWriter::new(File::create(out)?);
+		let count = self
+			.chain
+			.export_blocks(&mut file, ProtocolVersion::local(), progress)?;
+		Ok(count)
+	}
+
+	/// Replay the blocks of a bootstrap file through the chain. The proof of
+	/// work isn't verified up to `skip_pow_height` for the blocks already in
+	/// our header chain. Returns the number of blocks processed.
+	pub fn import_blocks(
+		&self,
+		file: &Path,
+		skip_pow_height: Option<u64>,
+		progress: &mut dyn FnMut(u64, u64),
+	) -> Result<u64, Error> {
+		let mut file = BufReader::new(File::open(file)?);
+		let count = self
+			.chain
+			.import_blocks(&mut file, skip_pow_height, progress)?;
+		Ok(count)
+	}
+
+	/// Check the consistency of the chain db against the MMR data files,
+	/// rebuilding the derived indices in repair mode. Returns the
+	/// inconsistencies found.
+	pub fn check_db(&self, repair: bool) -> Result<Vec<chain::DbIssue>, Error> {
+		let issues = self.chain.check_db(repair)?;
+		Ok(issues)
+	}
+}
@@ -103,10 +103,10 @@ pub fn connect_and_monitor(
 				}
 
 				// Check for and remove expired peers from the storage
-				if peer_count > 0 && Utc::now() - prev_expire_check > Duration::hours(1) {
+				if peer_count > 0 && global::now() - prev_expire_check > Duration::hours(1) {
 					peers.remove_expired();
 
-					prev_expire_check = Utc::now();
+					prev_expire_check = global::now();
 				}
 
 				// make several attempts to get peers as quick as possible
@@ -185,7 +185,7 @@ fn monitor_peers(
 	for x in peers.all_peers() {
 		match x.flags {
 			p2p::State::Banned => {
-				let interval = Utc::now().timestamp() - x.last_banned;
+				let interval = global::now().timestamp() - x.last_banned;
 				// Unban peer
 				if interval >= config.ban_window() {
 					if let Err(e) = peers.unban_peer(x.addr.clone()) {
@@ -357,7 +357,7 @@ fn listen_for_addrs(
 	let max_outbound_attempts = 128;
 	for addr in addrs.into_iter().take(max_outbound_attempts) {
 		// ignore the duplicate connecting to same peer within 30 seconds
-		let now = Utc::now();
+		let now = global::now();
 		if let Some(last_connect_time) = connecting_history.get(&addr) {
 			if *last_connect_time + Duration::seconds(connect_min_interval) > now {
 				debug!(
@@ -410,7 +410,7 @@ fn listen_for_addrs(
 	// shrink the connecting history.
 	// put a threshold here to avoid frequent shrinking in every call
 	if connecting_history.len() > 100 {
-		let now = Utc::now();
+		let now = global::now();
 		let old: Vec<_> = connecting_history
 			.iter()
 			.filter(|&(_, t)| *t + Duration::seconds(connect_min_interval) < now)
@@ -424,6 +424,16 @@ fn listen_for_addrs(
 
 pub fn default_dns_seeds() -> Box<dyn Fn() -> Vec<PeerAddr> + Send> {
 	Box::new(|| {
+		if global::get_chain_type() == global::ChainTypes::Custom {
+			let spec = global::get_chain_spec();
+			return resolve_dns_to_addrs(
+				&spec
+					.seeds
+					.iter()
+					.map(|s| format!("{}:{}", s, spec.p2p_port))
+					.collect(),
+			);
+		}
 		let net_seeds = if global::is_floonet() {
 			FLOONET_DNS_SEEDS
 		} else {
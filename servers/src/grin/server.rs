@@ -17,7 +17,7 @@
 //! as a facade.
 
 use crate::tor::config as tor_config;
-use crate::util::{secp, static_secp_instance};
+use crate::util::{from_hex, secp, static_secp_instance, to_hex};
 use spinner::SpinnerBuilder;
 use std::fs;
 use std::fs::File;
@@ -41,11 +41,12 @@ use walkdir::WalkDir;
 
 use crate::api;
 use crate::api::TLSConfig;
-use crate::chain::{self, SyncState, SyncStatus};
+use crate::chain::{self, ChainOptions, SyncState, SyncStatus};
 use crate::common::adapters::{
 	ChainToPoolAndNetAdapter, NetToChainAdapter, PoolToChainAdapter, PoolToNetAdapter,
 };
-use crate::common::hooks::{init_chain_hooks, init_net_hooks};
+use crate::common::hooks::{init_chain_hooks, init_net_hooks, init_pool_hooks};
+use crate::common::metrics::{start_metrics_server, MetricsHandler};
 use crate::common::stats::{
 	ChainStats, DiffBlock, DiffStats, PeerStats, ServerStateInfo, ServerStats, TxStats,
 };
@@ -53,10 +54,12 @@ use crate::common::stats::{
 use crate::common::types::{Error, ServerConfig, StratumServerConfig};
 use crate::core::core::hash::Hashed;
 use crate::core::core::verifier_cache::{LruVerifierCache, VerifierCache};
-use crate::core::ser::ProtocolVersion;
+use crate::core::core::Block;
+use crate::core::ser::{self, ProtocolVersion};
 use crate::core::stratum::connections;
 use crate::core::{consensus, genesis, global, pow};
 use crate::grin::{dandelion_monitor, seed, sync};
+use crate::mining::block_generator::TestBlockGenerator;
 use crate::mining::stratumserver;
 use crate::mining::test_miner::Miner;
 use crate::p2p;
@@ -95,6 +98,50 @@ pub struct Server {
 	dandelion_thread: JoinHandle<()>,
 }
 
+/// File in the db root holding the hex of a Custom genesis block mined on the first start.
+const CUSTOM_GENESIS_FILE: &str = "genesis.hex";
+
+/// Genesis block of the provided chain type.
+pub(crate) fn genesis_block(chain_type: global::ChainTypes, db_root: &str) -> Result<Block, Error> {
+	let gen = match chain_type {
+		global::ChainTypes::AutomatedTesting => pow::mine_genesis_block()?,
+		global::ChainTypes::UserTesting => pow::mine_genesis_block()?,
+		global::ChainTypes::Floonet => genesis::genesis_floo(),
+		global::ChainTypes::Mainnet => genesis::genesis_main(),
+		global::ChainTypes::Custom => custom_genesis_block(db_root)?,
+	};
+	Ok(gen)
+}
+
+/// Genesis block of the Custom chain type. Unless provided by the chain spec,
+/// it is mined on the first start and saved to the db root, so later starts
+/// (and other nodes, copying the hex into their chain spec) use the same one.
+fn custom_genesis_block(db_root: &str) -> Result<Block, Error> {
+	let invalid = |e: ser::Error| Error::Configuration(format!("invalid genesis block, {}", e));
+	if global::get_chain_spec().genesis.is_some() {
+		return genesis::genesis_custom().map_err(invalid);
+	}
+
+	let path = Path::new(db_root).join(CUSTOM_GENESIS_FILE);
+	if path.exists() {
+		let hex = fs::read_to_string(&path)?;
+		let bytes =
+			from_hex(hex.trim()).map_err(|e| invalid(ser::Error::HexError(e.to_string())))?;
+		return ser::deserialize_default(&mut &bytes[..]).map_err(invalid);
+	}
+
+	let gen = pow::mine_genesis(genesis::genesis_custom().map_err(invalid)?)?;
+	let bytes = ser::ser_vec(&gen, ProtocolVersion::local()).map_err(invalid)?;
+	fs::create_dir_all(db_root)?;
+	fs::write(&path, to_hex(bytes))?;
+	info!(
+		"Mined the genesis block {} of the chain spec, saved to {}",
+		gen.hash(),
+		path.display()
+	);
+	Ok(gen)
+}
+
 impl Server {
 	/// Instantiates and starts a new server. Optionally takes a callback
 	/// for the server to send an ARC copy of itself, to allow another process
@@ -161,7 +208,7 @@ impl Server {
 	// Exclusive (advisory) lock_file to ensure we do not run multiple
 	// instance of grin server from the same dir.
 	// This uses fs2 and should be safe cross-platform unless somebody abuses the file itself.
-	fn one_mimble_at_a_time(config: &ServerConfig) -> Result<Arc<File>, Error> {
+	pub(crate) fn one_mimble_at_a_time(config: &ServerConfig) -> Result<Arc<File>, Error> {
 		let path = Path::new(&config.db_root);
 		fs::create_dir_all(path.clone())?;
 		let path = path.join("mimble.lock");
@@ -214,12 +261,27 @@ impl Server {
 
 		let stop_state = Arc::new(StopState::new());
 
+		let state_info = ServerStateInfo::default();
+
 		// Shared cache for verification results.
 		// We cache rangeproof verification and kernel signature verification.
 		let verifier_cache = Arc::new(RwLock::new(LruVerifierCache::new()));
 
+		// Fans out chain and pool events to the foreign API subscribers.
+		let event_broadcaster = Arc::new(api::EventBroadcaster::new(
+			config
+				.api_subscribe_buffer_size
+				.unwrap_or(api::DEFAULT_SUBSCRIBE_BUFFER_SIZE),
+			config
+				.api_max_subscribers
+				.unwrap_or(api::DEFAULT_MAX_SUBSCRIBERS),
+		));
+
 		let pool_adapter = Arc::new(PoolToChainAdapter::new());
-		let pool_net_adapter = Arc::new(PoolToNetAdapter::new(config.dandelion_config.clone()));
+		let pool_net_adapter = Arc::new(PoolToNetAdapter::new(
+			config.dandelion_config.clone(),
+			init_pool_hooks(event_broadcaster.clone()),
+		));
 		let tx_pool = Arc::new(RwLock::new(pool::TransactionPool::new(
 			config.pool_config.clone(),
 			pool_adapter.clone(),
@@ -231,15 +293,11 @@ impl Server {
 
 		let chain_adapter = Arc::new(ChainToPoolAndNetAdapter::new(
 			tx_pool.clone(),
-			init_chain_hooks(&config),
+			init_chain_hooks(&config, event_broadcaster.clone()),
+			config.webhook_config.reorg_depth_threshold,
 		));
 
-		let genesis = match config.chain_type {
-			global::ChainTypes::AutomatedTesting => pow::mine_genesis_block().unwrap(),
-			global::ChainTypes::UserTesting => pow::mine_genesis_block().unwrap(),
-			global::ChainTypes::Floonet => genesis::genesis_floo(),
-			global::ChainTypes::Mainnet => genesis::genesis_main(),
-		};
+		let genesis = genesis_block(config.chain_type.clone(), &config.db_root)?;
 
 		info!("Starting server, genesis block: {}", genesis.hash());
 
@@ -249,11 +307,22 @@ impl Server {
 			genesis.clone(),
 			pow::verify_size,
 			verifier_cache.clone(),
-			archive_mode,
+			ChainOptions {
+				archive_mode,
+				kernel_index: config.kernel_index.unwrap_or(false),
+				output_history_index: config.output_history_index.unwrap_or(false),
+				max_reorg_depth: config.max_reorg_depth,
+			},
 		)?);
 
 		pool_adapter.set_chain(shared_chain.clone());
 
+		// Resume a full chain validation interrupted by the last shutdown.
+		if shared_chain.has_full_validation_checkpoint()? {
+			info!("Resuming the interrupted full chain validation");
+			chain::Chain::start_full_validation(shared_chain.clone())?;
+		}
+
 		let net_adapter = Arc::new(NetToChainAdapter::new(
 			sync_state.clone(),
 			shared_chain.clone(),
@@ -261,6 +330,7 @@ impl Server {
 			verifier_cache.clone(),
 			config.clone(),
 			init_net_hooks(&config),
+			state_info.clone(),
 		));
 
 		// we always support tor, so don't rely on config. This fixes
@@ -454,6 +524,18 @@ impl Server {
 			}
 		};
 
+		// Instant block generation through the owner API, testing chains only
+		let block_generator: Option<Arc<dyn api::BlockGenerator>> = match config.chain_type {
+			global::ChainTypes::AutomatedTesting | global::ChainTypes::UserTesting => {
+				Some(Arc::new(TestBlockGenerator::new(
+					shared_chain.clone(),
+					tx_pool.clone(),
+					verifier_cache.clone(),
+				)))
+			}
+			_ => None,
+		};
+
 		// TODO fix API shutdown and join this thread
 		api::node_apis(
 			&config.api_http_addr,
@@ -466,8 +548,30 @@ impl Server {
 			tls_conf.clone(),
 			allow_to_stop,
 			stratum_ip_pool,
+			event_broadcaster,
+			config
+				.api_max_batch_size
+				.unwrap_or(api::DEFAULT_MAX_BATCH_SIZE),
+			config.api_tokens.clone().unwrap_or(vec![]),
+			config.api_rate_limit.clone(),
+			block_generator,
 		)?;
 
+		if let Some(metrics_addr) = config.metrics_http_addr.clone() {
+			let metrics_handler = MetricsHandler {
+				chain: Arc::downgrade(&shared_chain),
+				tx_pool: Arc::downgrade(&tx_pool),
+				peers: Arc::downgrade(&p2p_server.peers),
+				sync_state: Arc::downgrade(&sync_state),
+				state_info: state_info.clone(),
+			};
+			start_metrics_server(
+				&metrics_addr,
+				get_first_line(config.metrics_secret_path.clone()),
+				metrics_handler,
+			)?;
+		}
+
 		info!("Starting dandelion monitor: {}", &config.api_http_addr);
 		let dandelion_thread = dandelion_monitor::monitor_transactions(
 			config.dandelion_config.clone(),
@@ -485,9 +589,7 @@ impl Server {
 			tx_pool,
 			verifier_cache,
 			sync_state,
-			state_info: ServerStateInfo {
-				..Default::default()
-			},
+			state_info,
 			stop_state,
 			lock_file,
 			connect_thread,
@@ -748,12 +850,14 @@ impl Server {
 
 			let block_time_sum = diff_entries.iter().fold(0, |sum, t| sum + t.duration);
 			let block_diff_sum = diff_entries.iter().fold(0, |sum, d| sum + d.difficulty);
+			let window_size = global::difficulty_adjust_window();
+			let n = window_size.saturating_sub(1).max(1);
 			DiffStats {
 				height: height as u64,
 				last_blocks: diff_entries,
-				average_block_time: block_time_sum / (consensus::DIFFICULTY_ADJUST_WINDOW - 1),
-				average_difficulty: block_diff_sum / (consensus::DIFFICULTY_ADJUST_WINDOW - 1),
-				window_size: consensus::DIFFICULTY_ADJUST_WINDOW,
+				average_block_time: block_time_sum / n,
+				average_difficulty: block_diff_sum / n,
+				window_size,
 			}
 		};
 
@@ -817,6 +921,7 @@ impl Server {
 			peer_stats: peer_stats,
 			diff_stats: diff_stats,
 			tx_stats: tx_stats,
+			validation_status: self.chain.full_validation_status(),
 		})
 	}
 
@@ -826,6 +931,11 @@ impl Server {
 			self.sync_state.update(SyncStatus::Shutdown);
 			self.stop_state.stop();
 
+			// The full chain validation resumes from its checkpoint on restart.
+			if self.chain.cancel_full_validation() {
+				info!("full chain validation interrupted");
+			}
+
 			if let Some(connect_thread) = self.connect_thread {
 				match connect_thread.join() {
 					Err(e) => error!("failed to join to connect_and_monitor thread: {:?}", e),
@@ -99,6 +99,22 @@ impl BodySync {
 
 		hashes.reverse();
 
+		// Blocks left off-chain by a chain rewind are still in our db, process
+		// them again rather than asking our peers for them.
+		for hash in &hashes {
+			let block = match self.chain.get_block(hash) {
+				Ok(block) => block,
+				Err(_) => break,
+			};
+			if let Err(e) = self
+				.chain
+				.process_block(block, chain::Options::SKIP_POW, vec![])
+			{
+				debug!("body_sync: failed to process block {} from db: {}", hash, e);
+				break;
+			}
+		}
+
 		let peers = self.peers.more_work_peers()?;
 
 		// if we have 5 peers to sync from then ask for 50 blocks total (peer_count *
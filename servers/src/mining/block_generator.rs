@@ -0,0 +1,167 @@
+// Copyright 2020 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Instant block generation for the testing chains, backing the owner API
+//! `generate_blocks` call. Blocks are built like the test miner does, solved
+//! with the low edge bits test proof of work and applied synchronously.
+
+use crate::util::RwLock;
+use std::sync::Arc;
+
+use crate::api;
+use crate::chain;
+use crate::core::core::hash::{Hash, Hashed};
+use crate::core::core::verifier_cache::VerifierCache;
+use crate::core::core::{Block, BlockHeader};
+use crate::core::global;
+use crate::mining::mine_block;
+use crate::pool;
+
+pub struct TestBlockGenerator {
+	chain: Arc<chain::Chain>,
+	tx_pool: Arc<RwLock<pool::TransactionPool>>,
+	verifier_cache: Arc<RwLock<dyn VerifierCache>>,
+}
+
+impl TestBlockGenerator {
+	/// Creates a new block generator on top of the chain, including the
+	/// mineable transactions of the pool.
+	pub fn new(
+		chain: Arc<chain::Chain>,
+		tx_pool: Arc<RwLock<pool::TransactionPool>>,
+		verifier_cache: Arc<RwLock<dyn VerifierCache>>,
+	) -> TestBlockGenerator {
+		TestBlockGenerator {
+			chain,
+			tx_pool,
+			verifier_cache,
+		}
+	}
+
+	/// Find a proof of work for the block, iterating over the nonce until the
+	/// solution meets the block difficulty.
+	fn solve(&self, b: &mut Block, prev: &BlockHeader) -> Result<(), api::Error> {
+		let target = b.header.total_difficulty() - prev.total_difficulty();
+		let mut ctx = global::create_pow_context::<u32>(
+			b.header.height,
+			global::min_edge_bits(),
+			global::proofsize(),
+			10,
+		)
+		.map_err(|e| api::ErrorKind::Internal(format!("pow context: {}", e)))?;
+		loop {
+			let pre_pow = b
+				.header
+				.pre_pow()
+				.map_err(|e| api::ErrorKind::Internal(format!("pre pow: {}", e)))?;
+			ctx.set_header_nonce(pre_pow, None, true)
+				.map_err(|e| api::ErrorKind::Internal(format!("pow header nonce: {}", e)))?;
+			if let Ok(proofs) = ctx.find_cycles() {
+				b.header.pow.proof = proofs[0].clone();
+				if b.header.pow.to_difficulty(b.header.height) >= target {
+					return Ok(());
+				}
+			}
+			b.header.pow.nonce = b.header.pow.nonce.wrapping_add(1);
+		}
+	}
+}
+
+impl api::BlockGenerator for TestBlockGenerator {
+	fn generate_blocks(
+		&self,
+		count: u64,
+		reward_destination: Option<String>,
+	) -> Result<Vec<Hash>, api::Error> {
+		let mut hashes = vec![];
+		for _ in 0..count {
+			// Unlike the miner, fail rather than retry when the block can't be
+			// built (e.g. the wallet listener is unreachable).
+			let (mut b, _) = mine_block::build_block(
+				&self.chain,
+				&self.tx_pool,
+				self.verifier_cache.clone(),
+				None,
+				reward_destination.clone(),
+			)
+			.map_err(|e| api::ErrorKind::Internal(format!("build block: {}", e)))?;
+			let prev = self.chain.get_previous_header(&b.header)?;
+			self.solve(&mut b, &prev)?;
+
+			let hash = b.hash();
+			info!(
+				"generate_blocks: adding block {} at height {}",
+				hash, b.header.height
+			);
+			self.chain.process_block(b, chain::Options::MINE, vec![])?;
+			hashes.push(hash);
+		}
+		Ok(hashes)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::api::BlockGenerator;
+	use crate::common::adapters::PoolToChainAdapter;
+	use crate::core::core::verifier_cache::LruVerifierCache;
+	use crate::core::pow;
+	use std::fs;
+
+	#[test]
+	fn test_generate_blocks() {
+		global::set_mining_mode(global::ChainTypes::AutomatedTesting);
+		let db_root = ".mimble_generate_blocks";
+		let _ = fs::remove_dir_all(db_root);
+		{
+			let verifier_cache = Arc::new(RwLock::new(LruVerifierCache::new()));
+			let pool_adapter = Arc::new(PoolToChainAdapter::new());
+			let tx_pool = Arc::new(RwLock::new(pool::TransactionPool::new(
+				pool::PoolConfig::default(),
+				pool_adapter.clone(),
+				verifier_cache.clone(),
+				Arc::new(pool::types::NoopAdapter {}),
+			)));
+			let chain = Arc::new(
+				chain::Chain::init(
+					db_root.to_owned(),
+					Arc::new(chain::types::NoopAdapter {}),
+					pow::mine_genesis_block().unwrap(),
+					pow::verify_size,
+					verifier_cache.clone(),
+					chain::ChainOptions::default(),
+				)
+				.unwrap(),
+			);
+			pool_adapter.set_chain(chain.clone());
+
+			let generator = TestBlockGenerator::new(chain.clone(), tx_pool, verifier_cache);
+			let hashes = generator.generate_blocks(3, None).unwrap();
+			assert_eq!(hashes.len(), 3);
+			for (i, hash) in hashes.iter().enumerate() {
+				let header = chain.get_header_by_height(i as u64 + 1).unwrap();
+				assert_eq!(header.hash(), *hash);
+			}
+			let head = chain.head().unwrap();
+			assert_eq!(head.height, 3);
+			assert_eq!(head.last_block_h, hashes[2]);
+
+			// Generating no block is a no-op.
+			assert!(generator.generate_blocks(0, None).unwrap().is_empty());
+			assert_eq!(chain.head().unwrap().height, 3);
+		}
+		let _ = fs::remove_dir_all(db_root);
+	}
+}
@@ -131,7 +131,7 @@ pub fn get_block(
 
 /// Builds a new block with the chain head as previous and eligible
 /// transactions from the pool.
-fn build_block(
+pub(crate) fn build_block(
 	chain: &Arc<chain::Chain>,
 	tx_pool: &Arc<RwLock<pool::TransactionPool>>,
 	verifier_cache: Arc<RwLock<dyn VerifierCache>>,
@@ -141,7 +141,7 @@ fn build_block(
 	let head = chain.head_header()?;
 
 	// prepare the block header timestamp
-	let mut now_sec = Utc::now().timestamp();
+	let mut now_sec = global::now().timestamp();
 	let head_sec = head.timestamp.timestamp();
 	if now_sec <= head_sec {
 		now_sec = head_sec + 1;
@@ -23,6 +23,7 @@ use crate::p2p;
 use crate::servers::ServerConfig;
 use crate::util::file::get_first_line;
 use failure::Fail;
+use serde_json::{json, Value};
 use term;
 
 pub fn client_command(client_args: &ArgMatches<'_>, global_config: GlobalConfig) -> i32 {
@@ -55,6 +56,15 @@ pub fn client_command(client_args: &ArgMatches<'_>, global_config: GlobalConfig)
 				panic!("Invalid peer address format");
 			}
 		}
+		("rewind_chain", Some(rewind_args)) => {
+			let height = rewind_args.value_of("height").unwrap();
+
+			if let Ok(height) = height.parse() {
+				rewind_chain(&server_config, height, api_secret);
+			} else {
+				panic!("Invalid height");
+			}
+		}
 		_ => panic!("Unknown client command, use 'mimble help client' for details"),
 	}
 	0
@@ -82,6 +92,22 @@ pub fn show_status(config: &ServerConfig, api_secret: Option<String>) {
 			writeln!(e, "Last block hash: {}", status.tip.last_block_pushed).unwrap();
 			writeln!(e, "Previous block hash: {}", status.tip.prev_block_to_last).unwrap();
 			writeln!(e, "Total difficulty: {}", status.tip.total_difficulty).unwrap();
+			for rule in status.active_rules {
+				writeln!(
+					e,
+					"Active consensus rule: {} (since height {})",
+					rule.name, rule.activation_height
+				)
+				.unwrap();
+			}
+			for rule in status.pending_rules {
+				writeln!(
+					e,
+					"Pending consensus rule: {} (at height {})",
+					rule.name, rule.activation_height
+				)
+				.unwrap();
+			}
 		}
 		Err(_) => writeln!(
 			e,
@@ -157,6 +183,38 @@ pub fn list_connected_peers(config: &ServerConfig, api_secret: Option<String>) {
 	e.reset().unwrap();
 }
 
+pub fn rewind_chain(config: &ServerConfig, height: u64, api_secret: Option<String>) {
+	let mut e = term::stdout().unwrap();
+	let url = format!("http://{}/v2/owner", config.api_http_addr);
+	let req = json!({
+		"jsonrpc": "2.0",
+		"method": "rewind_chain",
+		"params": [height],
+		"id": 1,
+	});
+	let res = api::client::post::<Value, Value>(url.as_str(), api_secret, &req);
+
+	match res.map_err(|e| Error::API(url, e)) {
+		Ok(res) => match serde_json::from_value::<api::Tip>(res["result"]["Ok"].clone()) {
+			Ok(tip) => writeln!(
+				e,
+				"Successfully rewound chain to {} at {}",
+				tip.last_block_pushed, tip.height
+			)
+			.unwrap(),
+			Err(_) => writeln!(
+				e,
+				"Failed to rewind chain to {}: {}",
+				height,
+				res["error"]["message"].as_str().unwrap_or("unknown error")
+			)
+			.unwrap(),
+		},
+		Err(_) => writeln!(e, "Failed to rewind chain to {}", height).unwrap(),
+	};
+	e.reset().unwrap();
+}
+
 fn get_status_from_node(
 	config: &ServerConfig,
 	api_secret: Option<String>,
@@ -18,8 +18,18 @@ use crate::core::global;
 use std::env;
 
 /// Create a config file in the current directory
-pub fn config_command_server(chain_type: &global::ChainTypes, file_name: &str) {
+pub fn config_command_server(
+	chain_type: &global::ChainTypes,
+	chain_spec_file: Option<&str>,
+	file_name: &str,
+) {
 	let mut default_config = GlobalConfig::for_chain(chain_type);
+	default_config
+		.members
+		.as_mut()
+		.unwrap()
+		.server
+		.chain_spec_file = chain_spec_file.map(|x| x.to_owned());
 	let current_dir = env::current_dir().unwrap_or_else(|e| {
 		panic!("Error creating config file: {}", e);
 	});
@@ -13,6 +13,8 @@
 // limitations under the License.
 
 /// Grin server commands processing
+use std::io::{self, Write};
+use std::path::Path;
 use std::process::exit;
 use std::thread;
 use std::time::Duration;
@@ -21,6 +23,7 @@ use clap::ArgMatches;
 use ctrlc;
 
 use crate::config::GlobalConfig;
+use crate::core::core::hash::Hashed;
 use crate::core::global;
 use crate::p2p::Seeding;
 use crate::servers;
@@ -88,6 +91,177 @@ fn start_server_tui(
 	}
 }
 
+/// Opens the chain for the maintenance commands, the server must not be running.
+fn open_offline_chain(config: &servers::ServerConfig) -> Option<servers::OfflineChain> {
+	match servers::OfflineChain::open(config) {
+		Ok(chain) => Some(chain),
+		Err(e) => {
+			println!("Unable to open the chain, {}", e);
+			None
+		}
+	}
+}
+
+/// Prints a progress bar of the blocks processed, redrawn on the same line.
+fn show_blocks_progress(done: u64, total: u64) {
+	if total == 0 || (done % (total / 1000).max(1) != 0 && done != total) {
+		return;
+	}
+	let width = 50;
+	let filled = (done * width / total) as usize;
+	print!(
+		"\r[{}{}] {:>5.1}% {}/{} blocks",
+		"#".repeat(filled),
+		" ".repeat(width as usize - filled),
+		done as f64 * 100.0 / total as f64,
+		done,
+		total
+	);
+	let _ = io::stdout().flush();
+	if done == total {
+		println!();
+	}
+}
+
+/// Exports or imports a chain snapshot.
+fn snapshot_command(snapshot_args: &ArgMatches<'_>, config: &servers::ServerConfig) -> i32 {
+	let chain = match open_offline_chain(config) {
+		Some(chain) => chain,
+		None => return 1,
+	};
+
+	match snapshot_args.subcommand() {
+		("export", Some(args)) => {
+			let height = match args.value_of("height").map(|h| h.parse::<u64>()) {
+				Some(Ok(height)) => Some(height),
+				Some(Err(_)) => {
+					println!("Invalid height");
+					return 1;
+				}
+				None => None,
+			};
+			let out = Path::new(args.value_of("out").unwrap());
+			match chain.export_snapshot(height, out) {
+				Ok(header) => println!(
+					"Exported snapshot at {} ({}) to {}",
+					header.height,
+					header.hash(),
+					out.display()
+				),
+				Err(e) => {
+					println!("Snapshot export failed, {}", e);
+					return 1;
+				}
+			}
+		}
+		("import", Some(args)) => {
+			let file = Path::new(args.value_of("file").unwrap());
+			println!(
+				"Importing snapshot {}, validating the chain state...",
+				file.display()
+			);
+			match chain.import_snapshot(file) {
+				Ok(header) => {
+					println!("Imported snapshot at {} ({})", header.height, header.hash())
+				}
+				Err(e) => {
+					println!("Snapshot import failed, {}", e);
+					return 1;
+				}
+			}
+		}
+		_ => {
+			println!("Subcommand required, use 'mimble help server snapshot' for details");
+			return 1;
+		}
+	}
+	0
+}
+
+/// Exports all the blocks to a flat bootstrap file, or replays them from one.
+fn blocks_command(blocks_args: &ArgMatches<'_>, config: &servers::ServerConfig) -> i32 {
+	let chain = match open_offline_chain(config) {
+		Some(chain) => chain,
+		None => return 1,
+	};
+
+	match blocks_args.subcommand() {
+		("export", Some(args)) => {
+			let out = Path::new(args.value_of("out").unwrap());
+			match chain.export_blocks(out, &mut show_blocks_progress) {
+				Ok(count) => println!("Exported {} blocks to {}", count, out.display()),
+				Err(e) => {
+					println!("\nBlocks export failed, {}", e);
+					return 1;
+				}
+			}
+		}
+		("import", Some(args)) => {
+			let skip_pow_height = match args.value_of("skip_pow_height").map(|h| h.parse::<u64>()) {
+				Some(Ok(height)) => Some(height),
+				Some(Err(_)) => {
+					println!("Invalid height");
+					return 1;
+				}
+				None => None,
+			};
+			let file = Path::new(args.value_of("file").unwrap());
+			match chain.import_blocks(file, skip_pow_height, &mut show_blocks_progress) {
+				Ok(count) => println!("Imported {} blocks from {}", count, file.display()),
+				Err(e) => {
+					println!("\nBlocks import failed, {}", e);
+					return 1;
+				}
+			}
+		}
+		_ => {
+			println!("Subcommand required, use 'mimble help server blocks' for details");
+			return 1;
+		}
+	}
+	0
+}
+
+/// Checks the chain db consistency, optionally repairing the derived indices.
+fn check_db_command(check_args: &ArgMatches<'_>, config: &servers::ServerConfig) -> i32 {
+	let chain = match open_offline_chain(config) {
+		Some(chain) => chain,
+		None => return 1,
+	};
+	let repair = check_args.is_present("repair");
+
+	let issues = match chain.check_db(repair) {
+		Ok(issues) => issues,
+		Err(e) => {
+			println!("Database check failed, {}", e);
+			return 1;
+		}
+	};
+	if issues.is_empty() {
+		println!("No inconsistency found");
+		return 0;
+	}
+	for issue in &issues {
+		let fix = match (issue.repairable, repair) {
+			(true, true) => "repaired",
+			(true, false) => "repairable with --repair",
+			(false, _) => "requires a resync",
+		};
+		println!("[{}] {} ({})", issue.check, issue.description, fix);
+	}
+	let remaining = issues.iter().filter(|i| !(i.repairable && repair)).count();
+	println!(
+		"{} inconsistencies found, {} remaining",
+		issues.len(),
+		remaining
+	);
+	if remaining > 0 {
+		1
+	} else {
+		0
+	}
+}
+
 /// Handles the server part of the command line, mostly running, starting and
 /// stopping the Grin blockchain server. Processes all the command line
 /// arguments to build a proper configuration and runs Grin with that
@@ -150,6 +324,15 @@ pub fn server_command(
 			("run", _) => {
 				start_server(server_config, logs_rx, allow_to_stop);
 			}
+			("snapshot", Some(snapshot_args)) => {
+				return snapshot_command(snapshot_args, &server_config);
+			}
+			("blocks", Some(blocks_args)) => {
+				return blocks_command(blocks_args, &server_config);
+			}
+			("check-db", Some(check_args)) => {
+				return check_db_command(check_args, &server_config);
+			}
 			("", _) => {
 				println!("Subcommand required, use 'mimble help server' for details");
 			}
@@ -66,6 +66,13 @@ fn log_build_info() {
 	debug!("{}", detailed_info);
 }
 
+fn load_chain_spec(file_path: &str) {
+	let spec = config::load_chain_spec(file_path).unwrap_or_else(|e| {
+		panic!("Error loading chain spec: {}", e);
+	});
+	global::set_chain_spec(spec);
+}
+
 fn main() {
 	let exit_code = real_main();
 	std::process::exit(exit_code);
@@ -76,7 +83,7 @@ fn real_main() -> i32 {
 	let args = App::from_yaml(yml)
 		.version(built_info::PKG_VERSION)
 		.get_matches();
-	let node_config;
+	let mut node_config;
 
 	// Temporary wallet warning message
 	match args.subcommand() {
@@ -92,7 +99,11 @@ fn real_main() -> i32 {
 		_ => {}
 	}
 
-	let chain_type = if args.is_present("floonet") {
+	let chain_spec_file = args.value_of("chain_spec");
+	let chain_type = if let Some(path) = chain_spec_file {
+		load_chain_spec(path);
+		global::ChainTypes::Custom
+	} else if args.is_present("floonet") {
 		global::ChainTypes::Floonet
 	} else if args.is_present("usernet") {
 		global::ChainTypes::UserTesting
@@ -106,7 +117,7 @@ fn real_main() -> i32 {
 		("server", Some(server_args)) => {
 			// If it's just a server config command, do it and exit
 			if let ("config", Some(_)) = server_args.subcommand() {
-				cmd::config_command_server(&chain_type, SERVER_CONFIG_FILE_NAME);
+				cmd::config_command_server(&chain_type, chain_spec_file, SERVER_CONFIG_FILE_NAME);
 				return 0;
 			}
 		}
@@ -139,6 +150,27 @@ fn real_main() -> i32 {
 		}
 	}
 
+	// A user defined network needs its chain spec, either from the command line
+	// or from the config file.
+	{
+		let server_config = &mut node_config
+			.as_mut()
+			.unwrap()
+			.members
+			.as_mut()
+			.unwrap()
+			.server;
+		if let Some(path) = chain_spec_file {
+			server_config.chain_type = global::ChainTypes::Custom;
+			server_config.chain_spec_file = Some(path.to_owned());
+		} else if server_config.chain_type == global::ChainTypes::Custom {
+			match server_config.chain_spec_file.as_ref() {
+				Some(path) => load_chain_spec(path),
+				None => panic!("The Custom chain type requires a chain_spec_file"),
+			}
+		}
+	}
+
 	let mut config = node_config.clone().unwrap();
 	let mut logging_config = config.members.as_mut().unwrap().logging.clone().unwrap();
 	logging_config.tui_running = config.members.as_mut().unwrap().server.run_tui;
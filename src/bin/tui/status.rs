@@ -24,7 +24,7 @@ use cursive::Cursive;
 use crate::tui::constants::VIEW_BASIC_STATUS;
 use crate::tui::types::TUIStatusListener;
 
-use crate::chain::SyncStatus;
+use crate::chain::{SyncStatus, ValidationPhase, ValidationStatus};
 use crate::servers::ServerStats;
 
 const NANO_TO_MILLIS: f64 = 1.0 / 1_000_000.0;
@@ -132,6 +132,34 @@ impl TUIStatusView {
 			SyncStatus::Shutdown => "Shutting down, closing connections".to_string(),
 		}
 	}
+
+	fn update_validation_status(status: &ValidationStatus) -> String {
+		let percent = if status.total > 0 {
+			(status.done * 100) / status.total
+		} else {
+			0
+		};
+		match status.phase {
+			ValidationPhase::Idle => "Not running".to_string(),
+			ValidationPhase::Sums => format!(
+				"Step 1/3: Validating MMRs and kernel sums at {}: {}%",
+				status.height, percent
+			),
+			ValidationPhase::Rangeproofs => {
+				format!("Step 2/3: Validating range proofs: {}%", percent)
+			}
+			ValidationPhase::Kernels => format!("Step 3/3: Validating kernels: {}%", percent),
+			ValidationPhase::Done => format!("Chain state valid at {}", status.height),
+			ValidationPhase::Cancelled => "Cancelled".to_string(),
+			ValidationPhase::Failed => format!(
+				"Failed: {}",
+				status
+					.error
+					.clone()
+					.unwrap_or_else(|| "unknown error".to_string())
+			),
+		}
+	}
 }
 
 impl TUIStatusListener for TUIStatusView {
@@ -154,6 +182,11 @@ impl TUIStatusListener for TUIStatusView {
 						.child(TextView::new("Disk Usage (GB):              "))
 						.child(TextView::new("0").with_id("disk_usage")),
 				)
+				.child(
+					LinearLayout::new(Orientation::Horizontal)
+						.child(TextView::new("Chain Validation:             "))
+						.child(TextView::new("Not running").with_id("chain_validation")),
+				)
 				.child(
 					LinearLayout::new(Orientation::Horizontal).child(TextView::new(
 						"--------------------------------------------------------",
@@ -258,6 +291,11 @@ impl TUIStatusListener for TUIStatusView {
 		c.call_on_id("disk_usage", |t: &mut TextView| {
 			t.set_content(stats.disk_usage_gb.clone());
 		});
+		c.call_on_id("chain_validation", |t: &mut TextView| {
+			t.set_content(TUIStatusView::update_validation_status(
+				&stats.validation_status,
+			));
+		});
 		c.call_on_id("tip_hash", |t: &mut TextView| {
 			t.set_content(stats.chain_stats.last_block_h.to_string() + "...");
 		});
@@ -320,3 +358,17 @@ fn test_status_txhashset_rproofs() {
 	let basic_status = TUIStatusView::update_sync_status(status);
 	assert!(basic_status.contains("64%"), basic_status);
 }
+
+#[test]
+fn test_status_chain_validation() {
+	let status = ValidationStatus {
+		phase: ValidationPhase::Rangeproofs,
+		height: 1000,
+		done: 643,
+		total: 1000,
+		resumed: false,
+		error: None,
+	};
+	let validation_status = TUIStatusView::update_validation_status(&status);
+	assert!(validation_status.contains("64%"), validation_status);
+}
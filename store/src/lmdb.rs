@@ -182,6 +182,14 @@ impl Store {
 		}
 	}
 
+	/// Current map size of the environment and the space actually used by
+	/// the data, both in bytes.
+	pub fn map_usage(&self) -> Result<(usize, usize), Error> {
+		let env_info = self.env.info()?;
+		let stat = self.env.stat()?;
+		Ok((env_info.mapsize, stat.psize as usize * env_info.last_pgno))
+	}
+
 	/// Increments the database size by as many ALLOC_CHUNK_SIZES
 	/// to give a minimum threshold of free space
 	pub fn do_resize(&self) -> Result<(), Error> {
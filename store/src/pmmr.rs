@@ -300,6 +300,21 @@ impl<T: PMMRable> PMMRBackend<T> {
 		self.is_pruned(pos) && !self.is_pruned_root(pos)
 	}
 
+	/// Leaf positions in the leaf set whose data can't be read because they
+	/// were pruned or compacted, i.e. the leaf set and prune list disagree.
+	pub fn inconsistent_leaves(&self) -> Vec<u64> {
+		if !self.prunable {
+			return vec![];
+		}
+		let size = self.unpruned_size();
+		self.leaf_set
+			.iter()
+			.filter(|&pos| {
+				pos > size || self.is_pruned(pos) || self.get_data_from_file(pos).is_none()
+			})
+			.collect()
+	}
+
 	/// Number of hashes in the PMMR stored by this backend. Only produces the
 	/// fully sync'd size.
 	pub fn unpruned_size(&self) -> u64 {
@@ -112,6 +112,13 @@ pub fn to_base64(s: &str) -> String {
 	base64::encode(s)
 }
 
+/// Decode a base64 string to an utf8 string
+pub fn from_base64(s: &str) -> Option<String> {
+	base64::decode(s)
+		.ok()
+		.and_then(|bytes| String::from_utf8(bytes).ok())
+}
+
 /// Global stopped/paused state shared across various subcomponents of Grin.
 ///
 /// "Stopped" allows a clean shutdown of the Grin server.
@@ -34,6 +34,21 @@ pub fn static_secp_instance() -> Arc<Mutex<secp::Secp256k1>> {
 	SECP256K1.clone()
 }
 
+thread_local! {
+	/// Per-thread secp instance, so batch verification can run in parallel
+	/// without contending on the static instance lock
+	static THREAD_SECP256K1: secp::Secp256k1
+		= secp::Secp256k1::with_caps(secp::ContextFlag::Commit);
+}
+
+/// Runs the provided closure with the secp instance of the current thread.
+pub fn with_thread_secp_instance<F, T>(f: F) -> T
+where
+	F: FnOnce(&secp::Secp256k1) -> T,
+{
+	THREAD_SECP256K1.with(|secp| f(secp))
+}
+
 /// Convenient way to generate a commitment to zero.
 pub fn commit_to_zero_value() -> secp::pedersen::Commitment {
 	secp::pedersen::Commitment::from_vec(vec![0])